@@ -0,0 +1,3 @@
+-- How the work interval continues after an idle suspension
+
+ALTER TABLE settings ADD COLUMN idle_resume_policy TEXT NOT NULL DEFAULT 'restart_if_long';
@@ -0,0 +1,4 @@
+-- Media playback (MPRIS) counts as activity for idle suspension
+
+ALTER TABLE settings ADD COLUMN media_activity_enabled INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE settings ADD COLUMN media_player_allowlist TEXT NOT NULL DEFAULT '[]';
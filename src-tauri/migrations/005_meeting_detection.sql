@@ -0,0 +1,13 @@
+-- Meeting detection: defer breaks while camera or microphone are in use
+
+ALTER TABLE settings ADD COLUMN meeting_detection_enabled INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE settings ADD COLUMN meeting_max_defer_minutes INTEGER NOT NULL DEFAULT 60;
+
+CREATE TABLE IF NOT EXISTS break_deferrals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    deferred_seconds INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_break_deferrals_started_at ON break_deferrals(started_at);
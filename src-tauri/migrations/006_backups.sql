@@ -0,0 +1,4 @@
+-- Rolling backup retention
+
+ALTER TABLE settings ADD COLUMN backup_daily_keep INTEGER NOT NULL DEFAULT 7;
+ALTER TABLE settings ADD COLUMN backup_weekly_keep INTEGER NOT NULL DEFAULT 4;
@@ -0,0 +1,26 @@
+-- Raw break record retention with weekly/monthly roll-ups
+
+ALTER TABLE settings ADD COLUMN retention_amount INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE settings ADD COLUMN retention_unit TEXT NOT NULL DEFAULT 'days';
+
+-- Aggregates over daily_stats_cache, keyed '2024-W03' (ISO week) or '2024-01'
+CREATE TABLE IF NOT EXISTS period_stats (
+    period TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    breaks_completed INTEGER NOT NULL DEFAULT 0,
+    breaks_skipped INTEGER NOT NULL DEFAULT 0,
+    total_rest_seconds INTEGER NOT NULL DEFAULT 0,
+    days_tracked INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_period_stats_kind ON period_stats(kind, period);
+
+-- Raw records that started before pruned_before (ms) have been deleted; their
+-- days in daily_stats_cache are final and must not be recomputed.
+CREATE TABLE IF NOT EXISTS retention_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    pruned_before INTEGER NOT NULL DEFAULT 0,
+    last_run_at INTEGER
+);
+
+INSERT OR IGNORE INTO retention_state (id) VALUES (1);
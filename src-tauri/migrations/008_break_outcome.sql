@@ -0,0 +1,19 @@
+-- Break end time, outcome, and what triggered it
+
+ALTER TABLE break_records ADD COLUMN ended_at INTEGER;
+-- completed | skipped | reset | snoozed | interrupted | demo; NULL while in progress
+ALTER TABLE break_records ADD COLUMN outcome TEXT;
+-- tray | overlay | main_window | cli | idle; NULL when the break ended on its own
+ALTER TABLE break_records ADD COLUMN source TEXT;
+
+-- Existing rows only have the flags. Resets were recorded as skips and can't
+-- be told apart; a row with neither flag never finished.
+UPDATE break_records SET
+    outcome = CASE
+        WHEN completed = 1 THEN 'completed'
+        WHEN skipped = 1 THEN 'skipped'
+        ELSE 'interrupted'
+    END,
+    ended_at = started_at + duration_seconds * 1000;
+
+CREATE INDEX IF NOT EXISTS idx_break_records_outcome ON break_records(outcome, started_at);
@@ -0,0 +1,14 @@
+-- Timeline of timer phases: one row per contiguous Working/Paused/Suspended/Breaking interval
+
+CREATE TABLE IF NOT EXISTS phase_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    phase TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    -- NULL while the interval is still open
+    ended_at INTEGER,
+    -- Last time the app confirmed the open interval was live; used to close it
+    -- after a crash
+    last_seen_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_phase_log_started_at ON phase_log(started_at);
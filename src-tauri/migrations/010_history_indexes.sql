@@ -0,0 +1,7 @@
+-- Indexes for the filtered break history query
+
+-- Covers date-range scans that also filter on outcome or duration, so counting
+-- a filtered history never touches the table. Rows tie-break on id, which every
+-- index carries implicitly.
+CREATE INDEX IF NOT EXISTS idx_break_records_history
+    ON break_records(started_at, outcome, duration_seconds);
@@ -0,0 +1,23 @@
+-- User corrections to break history
+
+ALTER TABLE break_records ADD COLUMN note TEXT;
+
+-- One row per edit or deletion, with the values before and after. Rows outlive
+-- the record they describe, so they carry its start time rather than a
+-- foreign key.
+CREATE TABLE IF NOT EXISTS break_record_edits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    break_id INTEGER NOT NULL,
+    break_started_at INTEGER NOT NULL,
+    -- edit | delete
+    action TEXT NOT NULL,
+    edited_at INTEGER NOT NULL,
+    old_outcome TEXT,
+    new_outcome TEXT,
+    old_duration_seconds INTEGER,
+    new_duration_seconds INTEGER,
+    old_note TEXT,
+    new_note TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_break_record_edits_break ON break_record_edits(break_id);
@@ -0,0 +1,5 @@
+-- Automatic weekly report
+
+ALTER TABLE settings ADD COLUMN report_auto_enabled INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE settings ADD COLUMN report_format TEXT NOT NULL DEFAULT 'html';
+ALTER TABLE settings ADD COLUMN report_dir TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,22 @@
+-- Workday-aware streaks with freezes
+
+-- JSON array of weekdays that count toward streaks ("mon" .. "sun")
+ALTER TABLE settings ADD COLUMN workdays TEXT NOT NULL
+    DEFAULT '["mon","tue","wed","thu","fri","sat","sun"]';
+
+-- Missed workdays covered by a freeze (YYYY-MM-DD, UTC). Kept so streaks
+-- computed later come out the same.
+CREATE TABLE IF NOT EXISTS streak_freezes (
+    date TEXT PRIMARY KEY,
+    frozen_at INTEGER NOT NULL
+);
+
+-- Progress of freeze earning: days up to processed_through are settled.
+CREATE TABLE IF NOT EXISTS streak_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    processed_through TEXT,
+    current_run INTEGER NOT NULL DEFAULT 0,
+    freezes_available INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO streak_state (id) VALUES (1);
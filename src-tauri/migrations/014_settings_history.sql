@@ -0,0 +1,10 @@
+-- Goal, interval and break length in effect from each date on (YYYY-MM-DD,
+-- UTC), so past days keep being judged by the settings they ran under. Rows
+-- are added when these settings change; with none, the current settings
+-- apply to every day.
+CREATE TABLE IF NOT EXISTS settings_history (
+    effective_date TEXT PRIMARY KEY,
+    daily_goal INTEGER NOT NULL,
+    work_interval_minutes INTEGER NOT NULL,
+    break_duration_seconds INTEGER NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Unlocked achievements, keyed by the rule id in achievements.rs
+
+CREATE TABLE IF NOT EXISTS achievements (
+    id TEXT PRIMARY KEY,
+    unlocked_at INTEGER NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Running totals and streaks through the last settled day, so the dashboard
+-- doesn't rescan all history. Derived data: rebuilt from scratch when
+-- settled_through is NULL.
+
+CREATE TABLE IF NOT EXISTS aggregates (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    -- Last day (YYYY-MM-DD, UTC) folded in
+    settled_through TEXT,
+    -- Workdays setting the streaks were computed with
+    workdays TEXT NOT NULL DEFAULT '',
+    lifetime_breaks INTEGER NOT NULL DEFAULT 0,
+    lifetime_rest_seconds INTEGER NOT NULL DEFAULT 0,
+    current_run INTEGER NOT NULL DEFAULT 0,
+    best_run INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO aggregates (id) VALUES (1);
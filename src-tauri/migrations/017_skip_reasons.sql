@@ -0,0 +1,7 @@
+-- Why a break was skipped, picked from a configurable list
+
+ALTER TABLE break_records ADD COLUMN skip_reason TEXT;
+
+-- JSON array of the reasons offered when skipping
+ALTER TABLE settings ADD COLUMN skip_reasons TEXT NOT NULL
+    DEFAULT '["In a meeting","Deep focus","Presenting","Other"]';
@@ -0,0 +1,12 @@
+-- Daily summary notification with insights
+
+ALTER TABLE settings ADD COLUMN daily_summary_enabled INTEGER NOT NULL DEFAULT 1;
+
+-- Last day (YYYY-MM-DD, UTC) a summary was sent for, so each day is
+-- summarized once however often maintenance runs.
+CREATE TABLE IF NOT EXISTS daily_summary_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_summarized TEXT
+);
+
+INSERT OR IGNORE INTO daily_summary_state (id) VALUES (1);
@@ -0,0 +1,4 @@
+-- How long an idle absence must last for "restart_if_long" to restart the
+-- work interval. The idle threshold alone is always longer than a break.
+
+ALTER TABLE settings ADD COLUMN idle_long_away_minutes INTEGER NOT NULL DEFAULT 15;
@@ -0,0 +1,294 @@
+use crate::analytics;
+use crate::state::Achievement;
+use crate::streaks::Workdays;
+use chrono::{DateTime, TimeZone, Timelike};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::collections::HashMap;
+
+/// What has to happen for an achievement to unlock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    /// At least this many completed breaks, ever.
+    LifetimeBreaks(u64),
+    /// A run of this many goal-meeting workdays.
+    DayStreak(u32),
+    /// Today's goal met without skipping a break.
+    PerfectDay,
+    /// A break completed before this local hour.
+    EarlyBird { before_hour: u32 },
+}
+
+pub struct Definition {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub rule: Rule,
+}
+
+/// Every achievement, in display order. Ids are stored, so never rename one.
+pub const ACHIEVEMENTS: &[Definition] = &[
+    Definition {
+        id: "first_break",
+        title: "First look away",
+        description: "Complete your first break",
+        rule: Rule::LifetimeBreaks(1),
+    },
+    Definition {
+        id: "breaks_100",
+        title: "Century",
+        description: "Complete 100 breaks",
+        rule: Rule::LifetimeBreaks(100),
+    },
+    Definition {
+        id: "breaks_1000",
+        title: "Thousand-yard stare",
+        description: "Complete 1,000 breaks",
+        rule: Rule::LifetimeBreaks(1000),
+    },
+    Definition {
+        id: "streak_7",
+        title: "Week streak",
+        description: "Meet your daily goal 7 workdays in a row",
+        rule: Rule::DayStreak(7),
+    },
+    Definition {
+        id: "streak_30",
+        title: "Month streak",
+        description: "Meet your daily goal 30 workdays in a row",
+        rule: Rule::DayStreak(30),
+    },
+    Definition {
+        id: "perfect_day",
+        title: "Perfect day",
+        description: "Meet your daily goal without skipping a break",
+        rule: Rule::PerfectDay,
+    },
+    Definition {
+        id: "early_bird",
+        title: "Early bird",
+        description: "Complete a break before 8am",
+        rule: Rule::EarlyBird { before_hour: 8 },
+    },
+];
+
+/// The numbers rules are checked against.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    pub lifetime_breaks: u64,
+    pub best_streak: u32,
+    pub perfect_day: bool,
+    /// Local hour of today's first completed break.
+    pub first_break_hour: Option<u32>,
+}
+
+impl Rule {
+    pub fn is_met(&self, progress: &Progress) -> bool {
+        match *self {
+            Rule::LifetimeBreaks(n) => progress.lifetime_breaks >= n,
+            Rule::DayStreak(n) => progress.best_streak >= n,
+            Rule::PerfectDay => progress.perfect_day,
+            Rule::EarlyBird { before_hour } => {
+                progress.first_break_hour.is_some_and(|h| h < before_hour)
+            }
+        }
+    }
+}
+
+impl Progress {
+    pub fn load<Tz: TimeZone>(
+        conn: &Connection,
+        daily_goal: u32,
+        workdays: &Workdays,
+        now: &DateTime<Tz>,
+    ) -> SqlResult<Self> {
+        let summary = analytics::build_analytics_summary(conn, daily_goal, workdays)?;
+        let today = &summary.today;
+
+        let tz = now.timezone();
+        let midnight = analytics::local_midnight(&tz, now.date_naive());
+        let first_break: Option<i64> = conn
+            .query_row(
+                "SELECT MIN(started_at) FROM break_records
+                 WHERE outcome = 'completed' AND started_at >= ?1",
+                params![midnight],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        Ok(Progress {
+            lifetime_breaks: summary.lifetime_breaks,
+            best_streak: summary.best_day_streak,
+            perfect_day: today.breaks_completed > 0
+                && today.breaks_completed >= daily_goal
+                && today.breaks_skipped == 0,
+            first_break_hour: first_break
+                .and_then(|ms| tz.timestamp_millis_opt(ms).single())
+                .map(|local| local.hour()),
+        })
+    }
+}
+
+/// Achievements whose rule is now met but that aren't unlocked yet, stamped
+/// with `now`. Only reads; `unlock` records them.
+pub fn newly_earned<Tz: TimeZone>(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    now: &DateTime<Tz>,
+) -> SqlResult<Vec<Achievement>> {
+    let progress = Progress::load(conn, daily_goal, workdays, now)?;
+    let unlocked = unlocked_at(conn)?;
+    let at = now.timestamp_millis() as u64;
+
+    Ok(ACHIEVEMENTS
+        .iter()
+        .filter(|def| !unlocked.contains_key(def.id) && def.rule.is_met(&progress))
+        .map(|def| to_achievement(def, Some(at)))
+        .collect())
+}
+
+/// Record earned achievements. Returns the ones this call unlocked, so an
+/// achievement two checks both found is only announced once.
+pub fn unlock(conn: &Connection, earned: &[Achievement]) -> SqlResult<Vec<Achievement>> {
+    let mut newly = Vec::new();
+    for achievement in earned {
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO achievements (id, unlocked_at) VALUES (?1, ?2)",
+            params![achievement.id, achievement.unlocked_at.map(|at| at as i64)],
+        )?;
+        if inserted > 0 {
+            newly.push(achievement.clone());
+        }
+    }
+    Ok(newly)
+}
+
+/// Every achievement with its unlock time, locked ones included.
+pub fn get_achievements(conn: &Connection) -> SqlResult<Vec<Achievement>> {
+    let unlocked = unlocked_at(conn)?;
+    Ok(ACHIEVEMENTS
+        .iter()
+        .map(|def| to_achievement(def, unlocked.get(def.id).copied()))
+        .collect())
+}
+
+fn unlocked_at(conn: &Connection) -> SqlResult<HashMap<String, u64>> {
+    let mut stmt = conn.prepare("SELECT id, unlocked_at FROM achievements")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+    })?;
+    rows.collect()
+}
+
+fn to_achievement(def: &Definition, unlocked_at: Option<u64>) -> Achievement {
+    Achievement {
+        id: def.id.to_string(),
+        title: def.title.to_string(),
+        description: def.description.to_string(),
+        unlocked_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, finish_break_record, insert_break_record};
+    use crate::state::BreakOutcome;
+    use chrono::Utc;
+    use std::collections::HashSet;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn add_break(conn: &Connection, started_at: u64, outcome: BreakOutcome) {
+        let id = insert_break_record(conn, started_at, 1200).unwrap();
+        finish_break_record(conn, id, 20, outcome, None, None).unwrap();
+        let day = DateTime::<Utc>::from_timestamp_millis(started_at as i64)
+            .unwrap()
+            .format("%Y-%m-%d")
+            .to_string();
+        db::recompute_daily_stats(conn, &day).unwrap();
+    }
+
+    #[test]
+    fn test_rules() {
+        let progress = Progress {
+            lifetime_breaks: 120,
+            best_streak: 6,
+            perfect_day: false,
+            first_break_hour: Some(7),
+        };
+        assert!(Rule::LifetimeBreaks(100).is_met(&progress));
+        assert!(!Rule::LifetimeBreaks(1000).is_met(&progress));
+        assert!(!Rule::DayStreak(7).is_met(&progress));
+        assert!(!Rule::PerfectDay.is_met(&progress));
+        assert!(Rule::EarlyBird { before_hour: 8 }.is_met(&progress));
+        assert!(!Rule::EarlyBird { before_hour: 7 }.is_met(&progress));
+        assert!(!Rule::EarlyBird { before_hour: 8 }.is_met(&Progress::default()));
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        let ids: HashSet<_> = ACHIEVEMENTS.iter().map(|a| a.id).collect();
+        assert_eq!(ids.len(), ACHIEVEMENTS.len());
+    }
+
+    #[test]
+    fn test_earned_achievements_unlock_once() {
+        let conn = setup_test_db();
+        let now = Utc::now();
+        assert!(newly_earned(&conn, 2, &Workdays::ALL, &now)
+            .unwrap()
+            .is_empty());
+
+        add_break(
+            &conn,
+            now.timestamp_millis() as u64,
+            BreakOutcome::Completed,
+        );
+        let earned = newly_earned(&conn, 1, &Workdays::ALL, &now).unwrap();
+        let ids: Vec<_> = earned.iter().map(|a| a.id.as_str()).collect();
+        assert!(ids.contains(&"first_break"));
+        assert!(ids.contains(&"perfect_day"));
+        assert!(!ids.contains(&"breaks_100"));
+
+        // Earning doesn't unlock; only the first unlock reports them
+        assert!(get_achievements(&conn)
+            .unwrap()
+            .iter()
+            .all(|a| a.unlocked_at.is_none()));
+        assert_eq!(unlock(&conn, &earned).unwrap().len(), earned.len());
+        assert!(unlock(&conn, &earned).unwrap().is_empty());
+
+        // Nothing new the second time round
+        assert!(newly_earned(&conn, 1, &Workdays::ALL, &now)
+            .unwrap()
+            .is_empty());
+        let all = get_achievements(&conn).unwrap();
+        assert_eq!(all.len(), ACHIEVEMENTS.len());
+        assert_eq!(
+            all.iter()
+                .find(|a| a.id == "first_break")
+                .unwrap()
+                .unlocked_at,
+            Some(now.timestamp_millis() as u64)
+        );
+    }
+
+    #[test]
+    fn test_a_skip_spoils_the_perfect_day() {
+        let conn = setup_test_db();
+        let now = Utc::now();
+        let ms = now.timestamp_millis() as u64;
+        add_break(&conn, ms - 60_000, BreakOutcome::Skipped);
+        add_break(&conn, ms, BreakOutcome::Completed);
+
+        let progress = Progress::load(&conn, 1, &Workdays::ALL, &now).unwrap();
+        assert!(!progress.perfect_day);
+        assert_eq!(progress.lifetime_breaks, 1);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::db;
+use crate::streaks::{self, apply_freezes, GoalHistory, StreakRun, Workdays};
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, Result as SqlResult};
+
+/// Lifetime totals and streaks through the end of `settled_through`. Today is
+/// always added live on top, so these only change at day rollover.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Aggregates {
+    pub settled_through: Option<NaiveDate>,
+    /// Workdays the streaks were computed with, as stored in settings.
+    pub workdays: String,
+    pub lifetime_breaks: u64,
+    pub lifetime_rest_seconds: u64,
+    pub run: StreakRun,
+}
+
+pub fn load(conn: &Connection) -> SqlResult<Aggregates> {
+    conn.query_row(
+        "SELECT settled_through, workdays, lifetime_breaks, lifetime_rest_seconds,
+                current_run, best_run
+         FROM aggregates WHERE id = 1",
+        [],
+        |row| {
+            Ok(Aggregates {
+                settled_through: row
+                    .get::<_, Option<String>>(0)?
+                    .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+                workdays: row.get(1)?,
+                lifetime_breaks: row.get::<_, i64>(2)? as u64,
+                lifetime_rest_seconds: row.get::<_, i64>(3)? as u64,
+                run: StreakRun {
+                    current: row.get::<_, i32>(4)? as u32,
+                    best: row.get::<_, i32>(5)? as u32,
+                },
+            })
+        },
+    )
+}
+
+fn store(conn: &Connection, agg: &Aggregates) -> SqlResult<()> {
+    conn.execute(
+        "UPDATE aggregates
+         SET settled_through = ?1, workdays = ?2, lifetime_breaks = ?3,
+             lifetime_rest_seconds = ?4, current_run = ?5, best_run = ?6
+         WHERE id = 1",
+        params![
+            agg.settled_through
+                .map(|d| d.format("%Y-%m-%d").to_string()),
+            agg.workdays,
+            agg.lifetime_breaks as i64,
+            agg.lifetime_rest_seconds as i64,
+            agg.run.current as i32,
+            agg.run.best as i32
+        ],
+    )?;
+    Ok(())
+}
+
+/// The aggregates through yesterday, without writing anything. Continues from
+/// the stored ones, so only days since the last settle are read; starts over
+/// if there are none or they were computed for other workdays.
+pub fn current(
+    conn: &Connection,
+    goals: &GoalHistory,
+    workdays: &Workdays,
+    today: NaiveDate,
+) -> SqlResult<Aggregates> {
+    let yesterday = today - Duration::days(1);
+    let stored = load(conn)?;
+    let usable = stored.settled_through.is_some_and(|d| d <= yesterday)
+        && Workdays::parse(&stored.workdays) == Some(*workdays);
+
+    let (mut agg, start, totals_from) = match stored.settled_through {
+        Some(settled) if usable => {
+            let start = settled + Duration::days(1);
+            (stored, Some(start), day_start_ms(start))
+        }
+        _ => (Aggregates::default(), streaks::first_day(conn)?, 0),
+    };
+
+    if let Some(start) = start.filter(|s| *s <= yesterday) {
+        let completed = streaks::completed_between(conn, start, yesterday)?;
+        let frozen = streaks::frozen_days(conn)?;
+        agg.run
+            .advance(goals, workdays, &completed, &frozen, start, yesterday);
+    }
+    let (breaks, seconds) = totals_between(conn, totals_from, day_start_ms(today))?;
+    agg.lifetime_breaks += breaks;
+    agg.lifetime_rest_seconds += seconds;
+    agg.settled_through = Some(yesterday);
+    agg.workdays = workdays.to_json();
+    Ok(agg)
+}
+
+/// Settle the days that ended since the last run: spend or earn streak
+/// freezes for them, then fold them into the stored aggregates. Cheap when
+/// nothing has changed, so it's safe to call after every break.
+pub fn settle(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    today: NaiveDate,
+) -> SqlResult<Aggregates> {
+    apply_freezes(conn, daily_goal, workdays, today)?;
+    let goals = GoalHistory::load(conn, daily_goal)?;
+    let agg = current(conn, &goals, workdays, today)?;
+    store(conn, &agg)?;
+    Ok(agg)
+}
+
+/// Forget the stored aggregates after past days changed. The next `settle`
+/// (or `current`) recomputes them from scratch.
+pub fn invalidate(conn: &Connection) -> SqlResult<()> {
+    conn.execute(
+        "UPDATE aggregates SET settled_through = NULL WHERE id = 1",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Recompute the stored aggregates from all history, for repair.
+pub fn rebuild(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    today: NaiveDate,
+) -> SqlResult<Aggregates> {
+    invalidate(conn)?;
+    settle(conn, daily_goal, workdays, today)
+}
+
+/// Completed breaks and their rest seconds for breaks started in
+/// `[from_ms, to_ms)`. Days already pruned by retention only survive in
+/// daily_stats_cache, so those are counted from there.
+pub fn totals_between(conn: &Connection, from_ms: i64, to_ms: i64) -> SqlResult<(u64, u64)> {
+    let pruned_before = db::pruned_before(conn)? as i64;
+
+    let (raw_count, raw_seconds) = conn.query_row(
+        "SELECT COALESCE(COUNT(*), 0), COALESCE(SUM(duration_seconds), 0)
+         FROM break_records
+         WHERE completed = 1 AND started_at >= MAX(?1, ?2) AND started_at < ?3",
+        params![from_ms, pruned_before, to_ms],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+    )?;
+    if pruned_before <= from_ms {
+        return Ok((raw_count, raw_seconds));
+    }
+
+    let day = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let (rolled_count, rolled_seconds) = conn.query_row(
+        "SELECT COALESCE(SUM(breaks_completed), 0), COALESCE(SUM(total_rest_seconds), 0)
+         FROM daily_stats_cache WHERE date >= ?1 AND date < ?2",
+        params![day(from_ms), day(pruned_before.min(to_ms))],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+    )?;
+
+    Ok((raw_count + rolled_count, raw_seconds + rolled_seconds))
+}
+
+pub fn day_start_ms(day: NaiveDate) -> i64 {
+    day.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{finish_break_record, insert_break_record};
+    use crate::state::BreakOutcome;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn complete_on(conn: &Connection, day: NaiveDate, breaks: u32) {
+        for i in 0..breaks {
+            let at = day_start_ms(day) as u64 + 36_000_000 + i as u64 * 1_200_000;
+            let id = insert_break_record(conn, at, 1200).unwrap();
+            finish_break_record(conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+        }
+        db::recompute_daily_stats(conn, &day.format("%Y-%m-%d").to_string()).unwrap();
+    }
+
+    fn from_scratch(conn: &Connection, today: NaiveDate) -> Aggregates {
+        let goals = GoalHistory::load(conn, 1).unwrap();
+        invalidate(conn).unwrap();
+        current(conn, &goals, &Workdays::ALL, today).unwrap()
+    }
+
+    #[test]
+    fn test_settling_day_by_day_matches_a_full_rebuild() {
+        let conn = setup_test_db();
+        let first = date(2024, 1, 1);
+        // Met, met, missed, met, met, met
+        for (i, breaks) in [2, 1, 0, 1, 3, 1].into_iter().enumerate() {
+            complete_on(&conn, first + Duration::days(i as i64), breaks);
+        }
+
+        for i in 1..=6 {
+            settle(&conn, 1, &Workdays::ALL, first + Duration::days(i)).unwrap();
+        }
+        let settled = load(&conn).unwrap();
+        assert_eq!(settled.settled_through, Some(date(2024, 1, 6)));
+        assert_eq!(
+            settled.run,
+            StreakRun {
+                current: 3,
+                best: 3
+            }
+        );
+        assert_eq!(settled.lifetime_breaks, 8);
+        assert_eq!(settled.lifetime_rest_seconds, 160);
+
+        assert_eq!(from_scratch(&conn, date(2024, 1, 7)), settled);
+    }
+
+    #[test]
+    fn test_changed_workdays_start_over() {
+        let conn = setup_test_db();
+        // Fri 2024-01-05 and Mon 01-08 met, the weekend between missed
+        complete_on(&conn, date(2024, 1, 5), 1);
+        complete_on(&conn, date(2024, 1, 8), 1);
+        settle(&conn, 1, &Workdays::ALL, date(2024, 1, 9)).unwrap();
+        assert_eq!(load(&conn).unwrap().run.best, 1);
+
+        let weekdays = Workdays::parse(r#"["mon","tue","wed","thu","fri"]"#).unwrap();
+        let goals = GoalHistory::load(&conn, 1).unwrap();
+        let agg = current(&conn, &goals, &weekdays, date(2024, 1, 9)).unwrap();
+        assert_eq!(
+            agg.run,
+            StreakRun {
+                current: 2,
+                best: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_totals_count_pruned_days_from_the_cache() {
+        let conn = setup_test_db();
+        complete_on(&conn, date(2024, 1, 1), 2);
+        complete_on(&conn, date(2024, 1, 3), 1);
+        crate::retention::prune_before(&conn, date(2024, 1, 2)).unwrap();
+
+        assert_eq!(totals_between(&conn, 0, i64::MAX).unwrap(), (3, 60));
+        let jan_2 = day_start_ms(date(2024, 1, 2));
+        assert_eq!(totals_between(&conn, 0, jan_2).unwrap(), (2, 40));
+        assert_eq!(totals_between(&conn, jan_2, i64::MAX).unwrap(), (1, 20));
+    }
+}
@@ -1,17 +1,29 @@
+use crate::aggregates;
+use crate::coverage;
 use crate::db;
-use crate::state::{AnalyticsSummary, DailyStats};
-use chrono::{NaiveDate, Utc};
-use rusqlite::{Connection, Result as SqlResult};
+use crate::state::{
+    AnalyticsSummary, BreakOutcome, HeatmapCell, HourlyHeatmap, SkipReasonBreakdown,
+    SkipReasonStats,
+};
+use crate::streaks::{self, GoalHistory, Workdays};
+use crate::trends;
+use chrono::{Datelike, Local, NaiveDate, TimeZone, Timelike, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
 
 /// Build the full analytics summary for the dashboard.
 ///
-/// `daily_goal` is the user's configured breaks-per-day target, used for streak calculations.
-pub fn build_analytics_summary(conn: &Connection, daily_goal: u32) -> SqlResult<AnalyticsSummary> {
+/// `daily_goal` is the user's configured breaks-per-day target, used for streak
+/// calculations together with `workdays`.
+pub fn build_analytics_summary(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+) -> SqlResult<AnalyticsSummary> {
     let today = Utc::now().date_naive();
     let today_str = today.format("%Y-%m-%d").to_string();
 
     // 1. Always recompute today's stats fresh (the day is still in progress)
-    let today_stats = db::recompute_daily_stats(conn, &today_str)?;
+    let today_stats = db::compute_daily_stats(conn, &today_str)?;
 
     // 2. Last 7 days (today - 6 days through today), zero-filled
     let seven_days_ago = today - chrono::Duration::days(6);
@@ -29,14 +41,22 @@ pub fn build_analytics_summary(conn: &Connection, daily_goal: u32) -> SqlResult<
         &today_str,
     )?;
 
-    // 4. Current day streak: consecutive days (ending at today or yesterday) meeting the goal
-    let current_day_streak = compute_current_streak(conn, daily_goal, &today, &today_stats)?;
-
-    // 5. Best day streak: all-time longest consecutive run meeting the goal
-    let best_day_streak = compute_best_streak(conn, daily_goal)?;
-
-    // 6. Lifetime totals: only count completed breaks
-    let (lifetime_breaks, lifetime_rest_seconds) = compute_lifetime_totals(conn)?;
+    // 4. Streaks and lifetime totals: stored through yesterday (only the days
+    // since the last settle are read), with today added live. Past days are
+    // judged by the goal in effect at the time.
+    let goals = GoalHistory::load(conn, daily_goal)?;
+    let settled = aggregates::current(conn, &goals, workdays, today)?;
+    let today_met = workdays.is_workday(today) && goals.met(today, today_stats.breaks_completed);
+    // If today hasn't met the goal yet the day isn't over, so the streak
+    // through yesterday still stands
+    let current_day_streak = settled.run.current + today_met as u32;
+    let best_day_streak = settled.run.best.max(current_day_streak);
+
+    // 5. Lifetime totals: only count completed breaks
+    let (today_breaks, today_rest_seconds) =
+        aggregates::totals_between(conn, aggregates::day_start_ms(today), i64::MAX)?;
+    let lifetime_breaks = settled.lifetime_breaks + today_breaks;
+    let lifetime_rest_seconds = settled.lifetime_rest_seconds + today_rest_seconds;
 
     Ok(AnalyticsSummary {
         today: today_stats,
@@ -44,146 +64,171 @@ pub fn build_analytics_summary(conn: &Connection, daily_goal: u32) -> SqlResult<
         last_30_days,
         current_day_streak,
         best_day_streak,
+        streak_freezes_available: streaks::freezes_available(conn)?,
+        today_coverage: coverage::day_coverage(conn, &today_str)?,
+        trends: trends::build_trends(conn, today)?,
         lifetime_breaks,
         lifetime_rest_seconds,
     })
 }
 
-/// Walk backwards from today counting consecutive days where daily_goal was met.
-///
-/// Special case: if today hasn't met the goal *yet*, don't break the streak —
-/// the day isn't over. Start counting from yesterday instead, and if today
-/// has met the goal, include it.
-fn compute_current_streak(
-    conn: &Connection,
-    daily_goal: u32,
-    today: &NaiveDate,
-    today_stats: &DailyStats,
-) -> SqlResult<u32> {
-    let today_met_goal = today_stats.breaks_completed >= daily_goal;
-
-    // Load all cached daily stats, ordered by date descending
-    let mut stmt =
-        conn.prepare("SELECT date, breaks_completed FROM daily_stats_cache ORDER BY date DESC")?;
-
-    let entries: Vec<(String, u32)> = stmt
-        .query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)? as u32))
-        })?
-        .collect::<SqlResult<Vec<_>>>()?;
-
-    // Build a set for O(1) lookup
-    let stats_map: std::collections::HashMap<String, u32> = entries.into_iter().collect();
-
-    let mut streak: u32 = 0;
-
-    if today_met_goal {
-        // Today counts, start walking from today
-        streak = 1;
-        let mut day = *today - chrono::Duration::days(1);
-        loop {
-            let day_str = day.format("%Y-%m-%d").to_string();
-            match stats_map.get(&day_str) {
-                Some(&completed) if completed >= daily_goal => {
-                    streak += 1;
-                    day -= chrono::Duration::days(1);
-                }
-                _ => break,
-            }
-        }
-    } else {
-        // Today hasn't met goal yet — don't break streak, start from yesterday
-        let mut day = *today - chrono::Duration::days(1);
-        loop {
-            let day_str = day.format("%Y-%m-%d").to_string();
-            match stats_map.get(&day_str) {
-                Some(&completed) if completed >= daily_goal => {
-                    streak += 1;
-                    day -= chrono::Duration::days(1);
-                }
-                _ => break,
-            }
-        }
-    }
+/// Local midnight starting `day` in ms, or the first instant after it on a
+/// DST-gap day.
+pub fn local_midnight<Tz: TimeZone>(tz: &Tz, day: NaiveDate) -> i64 {
+    let naive = day.and_hms_opt(0, 0, 0).unwrap();
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&naive))
+        .timestamp_millis()
+}
+
+/// Parse a YYYY-MM-DD date argument.
+fn parse_date(date: &str) -> SqlResult<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| rusqlite::Error::InvalidParameterName(format!("bad date '{}': {}", date, e)))
+}
+
+/// Start and end in ms of the UTC days `from` to `to` inclusive, the same day
+/// boundaries daily stats use.
+fn utc_day_range(from: &str, to: &str) -> SqlResult<(i64, i64)> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+    Ok((
+        aggregates::day_start_ms(from),
+        aggregates::day_start_ms(to + chrono::Duration::days(1)),
+    ))
+}
 
-    Ok(streak)
+/// Completed and skipped breaks bucketed by local weekday and hour, for
+/// breaks started between `from` and `to` (inclusive UTC days, YYYY-MM-DD).
+///
+/// Built from raw records, so days already pruned by retention are empty.
+pub fn build_hourly_heatmap(conn: &Connection, from: &str, to: &str) -> SqlResult<HourlyHeatmap> {
+    build_hourly_heatmap_in(conn, from, to, &Local)
 }
 
-/// Scan all daily_stats_cache entries to find the longest run of consecutive days
-/// where breaks_completed >= daily_goal.
-fn compute_best_streak(conn: &Connection, daily_goal: u32) -> SqlResult<u32> {
-    let mut stmt =
-        conn.prepare("SELECT date, breaks_completed FROM daily_stats_cache ORDER BY date ASC")?;
-
-    let entries: Vec<(NaiveDate, u32)> = stmt
-        .query_map([], |row| {
-            let date_str: String = row.get(0)?;
-            let completed = row.get::<_, i32>(1)? as u32;
-            Ok((date_str, completed))
-        })?
-        .filter_map(|r| {
-            r.ok().and_then(|(date_str, completed)| {
-                NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                    .ok()
-                    .map(|d| (d, completed))
+fn build_hourly_heatmap_in<Tz: TimeZone>(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    tz: &Tz,
+) -> SqlResult<HourlyHeatmap> {
+    let (start, end) = utc_day_range(from, to)?;
+
+    let mut cells: Vec<HeatmapCell> = (0..7u8)
+        .flat_map(|weekday| {
+            (0..24u8).map(move |hour| HeatmapCell {
+                weekday,
+                hour,
+                breaks_completed: 0,
+                breaks_skipped: 0,
+                compliance_rate: 0.0,
             })
         })
         .collect();
 
-    if entries.is_empty() {
-        return Ok(0);
+    let mut stmt = conn.prepare(
+        "SELECT started_at, outcome FROM break_records
+         WHERE started_at >= ?1 AND started_at < ?2 AND outcome IN ('completed', 'skipped')",
+    )?;
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (started_at, outcome) = row?;
+        let Some(local) = tz.timestamp_millis_opt(started_at).single() else {
+            continue;
+        };
+        let index = local.weekday().num_days_from_monday() as usize * 24 + local.hour() as usize;
+        match BreakOutcome::parse(&outcome) {
+            Some(BreakOutcome::Completed) => cells[index].breaks_completed += 1,
+            Some(BreakOutcome::Skipped) => cells[index].breaks_skipped += 1,
+            _ => {}
+        }
     }
 
-    let mut best: u32 = 0;
-    let mut current: u32 = 0;
-    let mut prev_date: Option<NaiveDate> = None;
-
-    for (date, completed) in &entries {
-        if *completed >= daily_goal {
-            // Check if this day is consecutive with previous
-            let is_consecutive = match prev_date {
-                Some(prev) => *date == prev + chrono::Duration::days(1),
-                None => true, // first entry starts a streak
-            };
-
-            if is_consecutive {
-                current += 1;
-            } else {
-                current = 1;
-            }
-
-            if current > best {
-                best = current;
-            }
-            prev_date = Some(*date);
-        } else {
-            current = 0;
-            prev_date = Some(*date);
+    for cell in &mut cells {
+        let total = cell.breaks_completed + cell.breaks_skipped;
+        if total > 0 {
+            cell.compliance_rate = cell.breaks_completed as f64 / total as f64;
         }
     }
 
-    Ok(best)
+    Ok(HourlyHeatmap {
+        from: from.to_string(),
+        to: to.to_string(),
+        cells,
+    })
+}
+
+/// Skipped breaks by reason and local hour, for breaks started between
+/// `from` and `to` (inclusive UTC days, YYYY-MM-DD).
+pub fn build_skip_reasons(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+) -> SqlResult<SkipReasonBreakdown> {
+    build_skip_reasons_in(conn, from, to, &Local)
 }
 
-/// Get lifetime totals: count and total rest seconds for completed breaks only.
-fn compute_lifetime_totals(conn: &Connection) -> SqlResult<(u64, u64)> {
-    let result = conn.query_row(
-        "SELECT COALESCE(COUNT(*), 0), COALESCE(SUM(duration_seconds), 0)
-         FROM break_records WHERE completed = 1",
-        [],
-        |row| {
-            let count = row.get::<_, i64>(0)? as u64;
-            let seconds = row.get::<_, i64>(1)? as u64;
-            Ok((count, seconds))
-        },
+fn build_skip_reasons_in<Tz: TimeZone>(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    tz: &Tz,
+) -> SqlResult<SkipReasonBreakdown> {
+    let (start, end) = utc_day_range(from, to)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT started_at, skip_reason FROM break_records
+         WHERE started_at >= ?1 AND started_at < ?2 AND outcome = 'skipped'",
     )?;
-    Ok(result)
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+    })?;
+
+    let mut reasons: Vec<SkipReasonStats> = Vec::new();
+    let mut total_skipped = 0;
+    for row in rows {
+        let (started_at, reason) = row?;
+        let Some(local) = tz.timestamp_millis_opt(started_at).single() else {
+            continue;
+        };
+        let index = match reasons.iter().position(|r| r.reason == reason) {
+            Some(index) => index,
+            None => {
+                reasons.push(SkipReasonStats {
+                    reason,
+                    count: 0,
+                    by_hour: vec![0; 24],
+                });
+                reasons.len() - 1
+            }
+        };
+        reasons[index].count += 1;
+        reasons[index].by_hour[local.hour() as usize] += 1;
+        total_skipped += 1;
+    }
+    // Most common first; skips without a reason sort after named ones on ties
+    reasons.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.reason.is_none().cmp(&b.reason.is_none()))
+            .then_with(|| a.reason.cmp(&b.reason))
+    });
+
+    Ok(SkipReasonBreakdown {
+        from: from.to_string(),
+        to: to.to_string(),
+        total_skipped,
+        reasons,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::{init_db_conn, insert_break_record, update_break_completion};
+    use crate::db::{finish_break_record, init_db_conn, insert_break_record};
+    use crate::state::{BreakOutcome, UserSettings};
 
     fn setup_test_db() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
@@ -191,10 +236,31 @@ mod tests {
         conn
     }
 
+    fn best_streak(conn: &Connection, daily_goal: u32, workdays: &Workdays) -> u32 {
+        let goals = GoalHistory::load(conn, daily_goal).unwrap();
+        let tomorrow = Utc::now().date_naive() + chrono::Duration::days(1);
+        aggregates::current(conn, &goals, workdays, tomorrow)
+            .unwrap()
+            .run
+            .best
+    }
+
+    /// One completed break at 10:00 UTC on `day`, with the day cached.
+    fn complete_day(conn: &Connection, day: NaiveDate) {
+        let at = day
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis() as u64;
+        let id = insert_break_record(conn, at, 1200).unwrap();
+        finish_break_record(conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+        db::recompute_daily_stats(conn, &day.format("%Y-%m-%d").to_string()).unwrap();
+    }
+
     #[test]
     fn test_zero_data_returns_valid_struct() {
         let conn = setup_test_db();
-        let summary = build_analytics_summary(&conn, 24).unwrap();
+        let summary = build_analytics_summary(&conn, 24, &Workdays::ALL).unwrap();
 
         assert_eq!(summary.today.breaks_completed, 0);
         assert_eq!(summary.today.breaks_skipped, 0);
@@ -219,9 +285,9 @@ mod tests {
             .timestamp_millis() as u64;
 
         let id = insert_break_record(&conn, base, 1200).unwrap();
-        update_break_completion(&conn, id, 20, true, false).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
 
-        let summary = build_analytics_summary(&conn, 24).unwrap();
+        let summary = build_analytics_summary(&conn, 24, &Workdays::ALL).unwrap();
         assert_eq!(summary.last_7_days.len(), 7);
 
         // Only today should have data
@@ -237,7 +303,7 @@ mod tests {
     #[test]
     fn test_last_30_days_always_30_elements() {
         let conn = setup_test_db();
-        let summary = build_analytics_summary(&conn, 24).unwrap();
+        let summary = build_analytics_summary(&conn, 24, &Workdays::ALL).unwrap();
         assert_eq!(summary.last_30_days.len(), 30);
     }
 
@@ -254,12 +320,12 @@ mod tests {
         // 3 completed, 1 skipped → compliance ≈ 0.75
         for i in 0..3 {
             let id = insert_break_record(&conn, base + i * 1_200_000, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         }
         let id = insert_break_record(&conn, base + 3_600_000, 1200).unwrap();
-        update_break_completion(&conn, id, 5, false, true).unwrap();
+        finish_break_record(&conn, id, 5, BreakOutcome::Skipped, None, None).unwrap();
 
-        let summary = build_analytics_summary(&conn, 24).unwrap();
+        let summary = build_analytics_summary(&conn, 24, &Workdays::ALL).unwrap();
         assert_eq!(summary.today.breaks_completed, 3);
         assert_eq!(summary.today.breaks_skipped, 1);
         assert!((summary.today.compliance_rate - 0.75).abs() < 0.001);
@@ -282,14 +348,14 @@ mod tests {
 
             for j in 0..daily_goal {
                 let id = insert_break_record(&conn, base + j as u64 * 1_200_000, 1200).unwrap();
-                update_break_completion(&conn, id, 20, true, false).unwrap();
+                finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
             }
             // Force cache computation for that day
             db::recompute_daily_stats(&conn, &date.format("%Y-%m-%d").to_string()).unwrap();
         }
 
         // Today has 0 breaks (hasn't met goal yet) — streak should still be 3
-        let summary = build_analytics_summary(&conn, daily_goal).unwrap();
+        let summary = build_analytics_summary(&conn, daily_goal, &Workdays::ALL).unwrap();
         assert_eq!(summary.current_day_streak, 3);
     }
 
@@ -307,7 +373,7 @@ mod tests {
             .timestamp_millis() as u64;
         for j in 0..daily_goal {
             let id = insert_break_record(&conn, base + j as u64 * 1_200_000, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         }
 
         // Yesterday also met the goal
@@ -319,11 +385,11 @@ mod tests {
             .timestamp_millis() as u64;
         for j in 0..daily_goal {
             let id = insert_break_record(&conn, ybase + j as u64 * 1_200_000, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         }
         db::recompute_daily_stats(&conn, &yesterday.format("%Y-%m-%d").to_string()).unwrap();
 
-        let summary = build_analytics_summary(&conn, daily_goal).unwrap();
+        let summary = build_analytics_summary(&conn, daily_goal, &Workdays::ALL).unwrap();
         assert_eq!(summary.current_day_streak, 2); // today + yesterday
     }
 
@@ -341,7 +407,7 @@ mod tests {
             .and_utc()
             .timestamp_millis() as u64;
         let id = insert_break_record(&conn, base3, 1200).unwrap();
-        update_break_completion(&conn, id, 20, true, false).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         db::recompute_daily_stats(&conn, &d3.format("%Y-%m-%d").to_string()).unwrap();
 
         // 2 days ago: zero breaks (gap!)
@@ -356,11 +422,11 @@ mod tests {
             .and_utc()
             .timestamp_millis() as u64;
         let id = insert_break_record(&conn, base1, 1200).unwrap();
-        update_break_completion(&conn, id, 20, true, false).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         db::recompute_daily_stats(&conn, &d1.format("%Y-%m-%d").to_string()).unwrap();
 
         // Today: 0 breaks
-        let summary = build_analytics_summary(&conn, daily_goal).unwrap();
+        let summary = build_analytics_summary(&conn, daily_goal, &Workdays::ALL).unwrap();
         // Current streak should be 1 (only yesterday), not 2 (gap breaks it)
         assert_eq!(summary.current_day_streak, 1);
     }
@@ -380,7 +446,7 @@ mod tests {
                 .and_utc()
                 .timestamp_millis() as u64;
             let id = insert_break_record(&conn, base, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
             db::recompute_daily_stats(&conn, &date.format("%Y-%m-%d").to_string()).unwrap();
         }
 
@@ -393,11 +459,11 @@ mod tests {
                 .and_utc()
                 .timestamp_millis() as u64;
             let id = insert_break_record(&conn, base, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
             db::recompute_daily_stats(&conn, &date.format("%Y-%m-%d").to_string()).unwrap();
         }
 
-        let summary = build_analytics_summary(&conn, daily_goal).unwrap();
+        let summary = build_analytics_summary(&conn, daily_goal, &Workdays::ALL).unwrap();
         assert_eq!(summary.best_day_streak, 5);
         assert_eq!(summary.current_day_streak, 2);
     }
@@ -415,14 +481,14 @@ mod tests {
         // 3 completed (20s each), 2 skipped (5s each)
         for i in 0..3 {
             let id = insert_break_record(&conn, base + i * 1_200_000, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         }
         for i in 3..5 {
             let id = insert_break_record(&conn, base + i * 1_200_000, 1200).unwrap();
-            update_break_completion(&conn, id, 5, false, true).unwrap();
+            finish_break_record(&conn, id, 5, BreakOutcome::Skipped, None, None).unwrap();
         }
 
-        let summary = build_analytics_summary(&conn, 24).unwrap();
+        let summary = build_analytics_summary(&conn, 24, &Workdays::ALL).unwrap();
         assert_eq!(summary.lifetime_breaks, 3);
         assert_eq!(summary.lifetime_rest_seconds, 60); // 3 * 20
     }
@@ -444,9 +510,10 @@ mod tests {
             for j in 0..35 {
                 let id = insert_break_record(&conn, base + j * 1_200_000, 1200).unwrap();
                 if j % 4 != 0 {
-                    update_break_completion(&conn, id, 20, true, false).unwrap();
+                    finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None)
+                        .unwrap();
                 } else {
-                    update_break_completion(&conn, id, 5, false, true).unwrap();
+                    finish_break_record(&conn, id, 5, BreakOutcome::Skipped, None, None).unwrap();
                 }
             }
             // Pre-cache non-today stats
@@ -457,7 +524,7 @@ mod tests {
 
         // Total: 30 * 35 = 1050 records
         let start = std::time::Instant::now();
-        let summary = build_analytics_summary(&conn, 24).unwrap();
+        let summary = build_analytics_summary(&conn, 24, &Workdays::ALL).unwrap();
         let elapsed = start.elapsed();
 
         assert!(
@@ -470,10 +537,48 @@ mod tests {
         assert!(summary.lifetime_breaks > 0);
     }
 
+    #[test]
+    fn test_performance_with_years_of_history() {
+        let conn = setup_test_db();
+        let today = Utc::now().date_naive();
+
+        // Five years of 12 breaks a day: ~22,000 records
+        let tx = conn.unchecked_transaction().unwrap();
+        for days_ago in 1..=5 * 365 {
+            let date = today - chrono::Duration::days(days_ago);
+            let base = aggregates::day_start_ms(date) as u64 + 8 * 3_600_000;
+            for j in 0..12 {
+                let id = insert_break_record(&tx, base + j * 1_200_000, 1200).unwrap();
+                finish_break_record(&tx, id, 20, BreakOutcome::Completed, None, None).unwrap();
+            }
+            db::recompute_daily_stats(&tx, &date.format("%Y-%m-%d").to_string()).unwrap();
+        }
+        tx.commit().unwrap();
+
+        // Without stored aggregates everything is rescanned
+        let scanned = build_analytics_summary(&conn, 8, &Workdays::ALL).unwrap();
+
+        aggregates::settle(&conn, 8, &Workdays::ALL, today).unwrap();
+        let start = std::time::Instant::now();
+        let summary = build_analytics_summary(&conn, 8, &Workdays::ALL).unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(
+            elapsed.as_millis() < 50,
+            "Took {}ms, expected <50ms",
+            elapsed.as_millis()
+        );
+        assert_eq!(summary.lifetime_breaks, 5 * 365 * 12);
+        assert_eq!(summary.lifetime_breaks, scanned.lifetime_breaks);
+        assert_eq!(summary.lifetime_rest_seconds, scanned.lifetime_rest_seconds);
+        assert_eq!(summary.current_day_streak, 5 * 365);
+        assert_eq!(summary.best_day_streak, scanned.best_day_streak);
+    }
+
     #[test]
     fn test_best_streak_with_no_data() {
         let conn = setup_test_db();
-        let best = compute_best_streak(&conn, 24).unwrap();
+        let best = best_streak(&conn, 24, &Workdays::ALL);
         assert_eq!(best, 0);
     }
 
@@ -484,7 +589,7 @@ mod tests {
         let daily_goal = 1;
 
         // Day 1 and Day 3 have data, Day 2 is missing from cache
-        // This tests that compute_best_streak handles gaps (non-consecutive dates)
+        // Gaps (non-consecutive dates) count as missed days
         let d1 = today - chrono::Duration::days(5);
         let d3 = today - chrono::Duration::days(3);
 
@@ -494,7 +599,7 @@ mod tests {
             .and_utc()
             .timestamp_millis() as u64;
         let id = insert_break_record(&conn, base1, 1200).unwrap();
-        update_break_completion(&conn, id, 20, true, false).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         db::recompute_daily_stats(&conn, &d1.format("%Y-%m-%d").to_string()).unwrap();
 
         let base3 = d3
@@ -503,11 +608,175 @@ mod tests {
             .and_utc()
             .timestamp_millis() as u64;
         let id = insert_break_record(&conn, base3, 1200).unwrap();
-        update_break_completion(&conn, id, 20, true, false).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         db::recompute_daily_stats(&conn, &d3.format("%Y-%m-%d").to_string()).unwrap();
 
-        let best = compute_best_streak(&conn, daily_goal).unwrap();
+        let best = best_streak(&conn, daily_goal, &Workdays::ALL);
         // Two entries but not consecutive → best is 1
         assert_eq!(best, 1);
     }
+
+    #[test]
+    fn test_hourly_heatmap_uses_local_time() {
+        let conn = setup_test_db();
+        // UTC+2: 2024-01-15 (a Monday) 23:30 UTC is Tuesday 01:30 local
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let at = |d: u32, h: u32, m: u32| {
+            tz.with_ymd_and_hms(2024, 1, d, h, m, 0)
+                .unwrap()
+                .timestamp_millis() as u64
+        };
+        let add = |started_at: u64, outcome: BreakOutcome| {
+            let id = insert_break_record(&conn, started_at, 1200).unwrap();
+            finish_break_record(&conn, id, 20, outcome, None, None).unwrap();
+        };
+        add(at(16, 1, 30), BreakOutcome::Completed);
+        add(at(16, 1, 50), BreakOutcome::Skipped);
+        add(at(16, 14, 0), BreakOutcome::Completed);
+        // Resets don't count either way
+        add(at(16, 14, 20), BreakOutcome::Reset);
+        // The range is in UTC days: 01:00 local on the 22nd is still the 21st
+        // in UTC, 01:00 local on the 15th is still the 14th
+        add(at(22, 1, 0), BreakOutcome::Completed);
+        add(at(15, 1, 0), BreakOutcome::Completed);
+        // Outside the range
+        add(at(22, 9, 0), BreakOutcome::Completed);
+
+        let heatmap = build_hourly_heatmap_in(&conn, "2024-01-15", "2024-01-21", &tz).unwrap();
+        assert_eq!(heatmap.cells.len(), 7 * 24);
+
+        let tuesday_1am = &heatmap.cells[24 + 1];
+        assert_eq!((tuesday_1am.weekday, tuesday_1am.hour), (1, 1));
+        assert_eq!(tuesday_1am.breaks_completed, 1);
+        assert_eq!(tuesday_1am.breaks_skipped, 1);
+        assert_eq!(tuesday_1am.compliance_rate, 0.5);
+
+        let tuesday_2pm = &heatmap.cells[24 + 14];
+        assert_eq!(tuesday_2pm.breaks_completed, 1);
+        assert_eq!(tuesday_2pm.compliance_rate, 1.0);
+
+        // Bucketed by local time, so it lands on Monday
+        assert_eq!(heatmap.cells[1].breaks_completed, 1);
+
+        let total: u32 = heatmap
+            .cells
+            .iter()
+            .map(|c| c.breaks_completed + c.breaks_skipped)
+            .sum();
+        assert_eq!(total, 4);
+    }
+
+    #[test]
+    fn test_hourly_heatmap_rejects_bad_dates() {
+        let conn = setup_test_db();
+        assert!(build_hourly_heatmap(&conn, "2024-13-01", "2024-01-02").is_err());
+    }
+
+    #[test]
+    fn test_skip_reasons_by_reason_and_hour() {
+        let conn = setup_test_db();
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let skip = |h: u32, m: u32, reason: Option<&str>| {
+            let at = tz
+                .with_ymd_and_hms(2024, 1, 16, h, m, 0)
+                .unwrap()
+                .timestamp_millis() as u64;
+            let id = insert_break_record(&conn, at, 1200).unwrap();
+            finish_break_record(&conn, id, 0, BreakOutcome::Skipped, None, reason).unwrap();
+        };
+        skip(14, 0, Some("In a meeting"));
+        skip(14, 40, Some("In a meeting"));
+        skip(15, 20, Some("In a meeting"));
+        skip(9, 0, Some("Deep focus"));
+        skip(10, 0, None);
+        // 01:00 local is still the 15th in UTC, inside the range
+        skip(1, 0, None);
+        // Completed breaks don't count
+        let id = insert_break_record(&conn, 1_705_410_000_000, 1200).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+
+        let breakdown = build_skip_reasons_in(&conn, "2024-01-15", "2024-01-21", &tz).unwrap();
+        assert_eq!(breakdown.total_skipped, 6);
+        let reasons: Vec<_> = breakdown
+            .reasons
+            .iter()
+            .map(|r| (r.reason.as_deref(), r.count))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (Some("In a meeting"), 3),
+                (None, 2),
+                (Some("Deep focus"), 1)
+            ]
+        );
+        let meeting = &breakdown.reasons[0].by_hour;
+        assert_eq!(meeting.len(), 24);
+        assert_eq!((meeting[14], meeting[15]), (2, 1));
+        assert_eq!(breakdown.reasons[1].by_hour[1], 1);
+        assert_eq!(breakdown.reasons[2].by_hour[9], 1);
+    }
+
+    #[test]
+    fn test_rest_days_neither_count_nor_break_streaks() {
+        let conn = setup_test_db();
+        // Thu 2024-01-11 .. Tue 01-16, weekend untouched
+        for d in [11, 12, 15, 16] {
+            complete_day(&conn, NaiveDate::from_ymd_opt(2024, 1, d).unwrap());
+        }
+        let weekdays = Workdays::parse(r#"["mon","tue","wed","thu","fri"]"#).unwrap();
+        assert_eq!(best_streak(&conn, 1, &Workdays::ALL), 2);
+        assert_eq!(best_streak(&conn, 1, &weekdays), 4);
+    }
+
+    #[test]
+    fn test_frozen_days_keep_the_current_streak() {
+        let conn = setup_test_db();
+        let today = Utc::now().date_naive();
+        for days_ago in [1, 2, 4] {
+            complete_day(&conn, today - chrono::Duration::days(days_ago));
+        }
+        let current = |conn: &Connection| {
+            build_analytics_summary(conn, 1, &Workdays::ALL)
+                .unwrap()
+                .current_day_streak
+        };
+        assert_eq!(current(&conn), 2);
+
+        let missed = today - chrono::Duration::days(3);
+        conn.execute(
+            "INSERT INTO streak_freezes (date, frozen_at) VALUES (?1, 0)",
+            [missed.format("%Y-%m-%d").to_string()],
+        )
+        .unwrap();
+        assert_eq!(current(&conn), 3);
+        assert_eq!(best_streak(&conn, 1, &Workdays::ALL), 3);
+    }
+
+    #[test]
+    fn test_raising_the_goal_keeps_past_streaks() {
+        let conn = setup_test_db();
+        let today = Utc::now().date_naive();
+        // Two breaks on each of the last three days, under a goal of 2
+        for days_ago in 1..=3 {
+            let day = today - chrono::Duration::days(days_ago);
+            complete_day(&conn, day);
+            complete_day(&conn, day);
+        }
+        conn.execute(
+            "INSERT INTO settings_history VALUES ('1970-01-01', 2, 20, 20)",
+            [],
+        )
+        .unwrap();
+
+        let raised = UserSettings {
+            daily_goal: 10,
+            ..UserSettings::default()
+        };
+        db::save_settings(&conn, &raised).unwrap();
+
+        let summary = build_analytics_summary(&conn, 10, &Workdays::ALL).unwrap();
+        assert_eq!(summary.current_day_streak, 3);
+        assert_eq!(summary.best_day_streak, 3);
+    }
 }
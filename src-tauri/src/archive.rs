@@ -0,0 +1,718 @@
+use crate::state::{
+    BreakDeferral, BreakOutcome, BreakRecord, BreakSource, DailyStats, SettingsSnapshot,
+    UserSettings,
+};
+use crate::{aggregates, db, retention, settings};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Identifies a file as a Blinky archive.
+pub const ARCHIVE_FORMAT: &str = "blinky-archive";
+
+/// Bumped whenever the archive layout changes incompatibly.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Everything needed to move a user's history to another machine. The session
+/// timeline (phase_log) stays behind: it describes this machine's sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Archive {
+    pub format: String,
+    pub format_version: u32,
+    pub exported_at: u64,
+    pub app_version: String,
+    pub schema_version: u32,
+    /// Kept as raw JSON so archives from older or newer versions still import:
+    /// missing keys take their defaults and unknown keys are ignored.
+    pub settings: serde_json::Value,
+    /// Goal, interval and break length in effect over time, so past days are
+    /// judged by the settings they ran under.
+    #[serde(default)]
+    pub settings_history: Vec<SettingsSnapshot>,
+    pub break_records: Vec<BreakRecord>,
+    #[serde(default)]
+    pub break_deferrals: Vec<BreakDeferral>,
+    /// Retention watermark (ms): raw records before this were pruned.
+    #[serde(default)]
+    pub pruned_before: u64,
+    /// Daily totals for pruned days, which no longer have raw records.
+    #[serde(default)]
+    pub rolled_up_days: Vec<DailyStats>,
+    /// Missed workdays a streak freeze covered.
+    #[serde(default)]
+    pub streak_freezes: Vec<StreakFreeze>,
+    /// Achievements unlocked so far.
+    #[serde(default)]
+    pub achievements: Vec<UnlockedAchievement>,
+}
+
+/// An unlocked achievement, by the rule id in achievements.rs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnlockedAchievement {
+    pub id: String,
+    pub unlocked_at: u64,
+}
+
+/// A missed workday (YYYY-MM-DD, UTC) covered by a streak freeze.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreakFreeze {
+    pub date: String,
+    pub frozen_at: u64,
+}
+
+/// How an imported archive combines with what's already in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Add the archive's history to the current data; current settings (and
+    /// their history) win.
+    Merge,
+    /// Wipe current data and take the archive's history and settings.
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub records_imported: u32,
+    /// Records already present (same start time) and left alone.
+    pub records_skipped: u32,
+    pub days_rebuilt: u32,
+    pub settings_applied: bool,
+}
+
+/// Snapshot the database into an archive.
+pub fn build_archive(conn: &Connection) -> SqlResult<Archive> {
+    let settings = db::load_settings(conn)?;
+    let pruned_before = db::pruned_before(conn)?;
+    Ok(Archive {
+        format: ARCHIVE_FORMAT.to_string(),
+        format_version: ARCHIVE_FORMAT_VERSION,
+        exported_at: Utc::now().timestamp_millis() as u64,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: db::schema_version(conn)?,
+        settings: serde_json::to_value(&settings).map_err(json_err)?,
+        settings_history: db::get_settings_history(conn)?,
+        break_records: db::get_all_break_records(conn)?,
+        break_deferrals: db::get_all_break_deferrals(conn)?,
+        pruned_before,
+        rolled_up_days: rolled_up_days(conn, pruned_before)?,
+        streak_freezes: streak_freezes(conn)?,
+        achievements: unlocked_achievements(conn)?,
+    })
+}
+
+/// Parse archive JSON and check it's something we can import.
+pub fn parse_archive(contents: &str) -> Result<Archive, String> {
+    let archive: Archive =
+        serde_json::from_str(contents).map_err(|e| format!("Not a valid archive: {}", e))?;
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(format!(
+            "Not a Blinky archive (format '{}')",
+            archive.format
+        ));
+    }
+    if archive.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Archive format version {} is newer than this version of Blinky supports ({})",
+            archive.format_version, ARCHIVE_FORMAT_VERSION
+        ));
+    }
+    Ok(archive)
+}
+
+/// Resolve archived settings against the current defaults and validate them.
+pub fn archive_settings(archive: &Archive) -> Result<UserSettings, String> {
+    let mut merged = serde_json::to_value(UserSettings::default()).map_err(|e| e.to_string())?;
+    let (Some(base), Some(incoming)) = (merged.as_object_mut(), archive.settings.as_object())
+    else {
+        return Err("Archive settings must be a JSON object".to_string());
+    };
+    for (key, value) in incoming {
+        if base.contains_key(key) {
+            base.insert(key.clone(), value.clone());
+        }
+    }
+    let settings: UserSettings =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid archive settings: {}", e))?;
+    settings::validate_settings(&settings)?;
+    Ok(settings)
+}
+
+/// Write an archive into the database in one transaction, then rebuild the
+/// daily stats cache for every day it touched.
+pub fn import_archive(
+    conn: &Connection,
+    archive: &Archive,
+    mode: ImportMode,
+) -> Result<ImportResult, String> {
+    let settings = archive_settings(archive)?;
+    apply_import(conn, archive, mode, &settings).map_err(|e| format!("Import failed: {}", e))
+}
+
+fn apply_import(
+    conn: &Connection,
+    archive: &Archive,
+    mode: ImportMode,
+    settings: &UserSettings,
+) -> SqlResult<ImportResult> {
+    let tx = conn.unchecked_transaction()?;
+
+    let mut days: BTreeSet<String> = BTreeSet::new();
+    let mut seen_records: HashSet<u64> = HashSet::new();
+    let mut seen_deferrals: HashSet<u64> = HashSet::new();
+
+    if mode == ImportMode::Replace {
+        tx.execute_batch(
+            "DELETE FROM break_records;
+             DELETE FROM break_deferrals;
+             DELETE FROM daily_stats_cache;
+             DELETE FROM period_stats;
+             DELETE FROM streak_freezes;
+             DELETE FROM achievements;
+             DELETE FROM phase_log WHERE ended_at IS NOT NULL;
+             UPDATE daily_summary_state SET last_summarized = NULL;",
+        )?;
+        tx.execute(
+            "UPDATE retention_state SET pruned_before = ?1 WHERE id = 1",
+            params![archive.pruned_before as i64],
+        )?;
+        db::save_settings(&tx, settings)?;
+        // Saving snapshots the settings being replaced; the archive's history
+        // takes their place
+        tx.execute("DELETE FROM settings_history", [])?;
+        for s in &archive.settings_history {
+            tx.execute(
+                "INSERT OR REPLACE INTO settings_history
+                    (effective_date, daily_goal, work_interval_minutes, break_duration_seconds)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    s.effective_date,
+                    s.daily_goal,
+                    s.work_interval_minutes,
+                    s.break_duration_seconds
+                ],
+            )?;
+        }
+    } else {
+        seen_records.extend(existing_starts(&tx, "break_records")?);
+        seen_deferrals.extend(existing_starts(&tx, "break_deferrals")?);
+    }
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    for r in &archive.break_records {
+        if !seen_records.insert(r.started_at) {
+            skipped += 1;
+            continue;
+        }
+        // Archives from before outcomes existed only carry the flags
+        let outcome = r
+            .outcome
+            .unwrap_or_else(|| BreakOutcome::from_flags(r.completed, r.skipped));
+        let ended_at = r
+            .ended_at
+            .unwrap_or(r.started_at + r.duration_seconds as u64 * 1000);
+        tx.execute(
+            "INSERT INTO break_records
+                (started_at, duration_seconds, completed, skipped, preceding_work_seconds,
+                 ended_at, outcome, source, note, skip_reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                r.started_at as i64,
+                r.duration_seconds,
+                (outcome == BreakOutcome::Completed) as i32,
+                (outcome == BreakOutcome::Skipped) as i32,
+                r.preceding_work_seconds,
+                ended_at as i64,
+                outcome.as_str(),
+                r.source.map(BreakSource::as_str),
+                r.note,
+                r.skip_reason
+            ],
+        )?;
+        imported += 1;
+        if let Some(day) = utc_day(r.started_at) {
+            days.insert(day);
+        }
+    }
+
+    for d in &archive.break_deferrals {
+        if !seen_deferrals.insert(d.started_at) {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO break_deferrals (started_at, reason, deferred_seconds) VALUES (?1, ?2, ?3)",
+            params![d.started_at as i64, d.reason, d.deferred_seconds],
+        )?;
+    }
+
+    // Pruned days only exist as totals; never overwrite a day we have data for
+    for d in &archive.rolled_up_days {
+        if days.contains(&d.date) {
+            continue;
+        }
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO daily_stats_cache
+                (date, breaks_completed, breaks_skipped, total_rest_seconds, longest_streak, compliance_rate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                d.date,
+                d.breaks_completed,
+                d.breaks_skipped,
+                d.total_rest_seconds,
+                d.longest_streak,
+                d.compliance_rate
+            ],
+        )?;
+        if inserted > 0 {
+            if let Ok(day) = NaiveDate::parse_from_str(&d.date, "%Y-%m-%d") {
+                retention::refresh_periods_containing(&tx, day)?;
+            }
+        }
+    }
+
+    for f in &archive.streak_freezes {
+        tx.execute(
+            "INSERT OR IGNORE INTO streak_freezes (date, frozen_at) VALUES (?1, ?2)",
+            params![f.date, f.frozen_at as i64],
+        )?;
+    }
+
+    // Unlocks are for good; merging keeps the earlier of two
+    for a in &archive.achievements {
+        tx.execute(
+            "INSERT INTO achievements (id, unlocked_at) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET unlocked_at = MIN(unlocked_at, excluded.unlocked_at)",
+            params![a.id, a.unlocked_at as i64],
+        )?;
+    }
+
+    for day in &days {
+        db::recompute_daily_stats(&tx, day)?;
+    }
+    aggregates::invalidate(&tx)?;
+    // Imported days can fall before the last settled one; earning and
+    // spending freezes is replayed over the whole history on the next settle
+    tx.execute(
+        "UPDATE streak_state
+         SET processed_through = NULL, current_run = 0, freezes_available = 0
+         WHERE id = 1",
+        [],
+    )?;
+
+    tx.commit()?;
+
+    Ok(ImportResult {
+        records_imported: imported,
+        records_skipped: skipped,
+        days_rebuilt: days.len() as u32,
+        settings_applied: mode == ImportMode::Replace,
+    })
+}
+
+fn rolled_up_days(conn: &Connection, pruned_before: u64) -> SqlResult<Vec<DailyStats>> {
+    let Some(first_kept_day) = utc_day(pruned_before).filter(|_| pruned_before > 0) else {
+        return Ok(Vec::new());
+    };
+    let mut stmt = conn.prepare(
+        "SELECT date, breaks_completed, breaks_skipped, total_rest_seconds, longest_streak, compliance_rate
+         FROM daily_stats_cache WHERE date < ?1 ORDER BY date ASC",
+    )?;
+    let rows = stmt.query_map(params![first_kept_day], |row| {
+        Ok(DailyStats {
+            date: row.get(0)?,
+            breaks_completed: row.get::<_, i32>(1)? as u32,
+            breaks_skipped: row.get::<_, i32>(2)? as u32,
+            total_rest_seconds: row.get::<_, i32>(3)? as u32,
+            longest_streak: row.get::<_, i32>(4)? as u32,
+            compliance_rate: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn streak_freezes(conn: &Connection) -> SqlResult<Vec<StreakFreeze>> {
+    let mut stmt = conn.prepare("SELECT date, frozen_at FROM streak_freezes ORDER BY date")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(StreakFreeze {
+            date: row.get(0)?,
+            frozen_at: row.get::<_, i64>(1)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+fn unlocked_achievements(conn: &Connection) -> SqlResult<Vec<UnlockedAchievement>> {
+    let mut stmt = conn.prepare("SELECT id, unlocked_at FROM achievements ORDER BY unlocked_at")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(UnlockedAchievement {
+            id: row.get(0)?,
+            unlocked_at: row.get::<_, i64>(1)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+fn existing_starts(conn: &Connection, table: &str) -> SqlResult<Vec<u64>> {
+    let mut stmt = conn.prepare(&format!("SELECT started_at FROM {}", table))?;
+    let rows = stmt.query_map([], |row| Ok(row.get::<_, i64>(0)? as u64))?;
+    rows.collect()
+}
+
+fn utc_day(ms: u64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp_millis(ms as i64).map(|dt| dt.format("%Y-%m-%d").to_string())
+}
+
+fn json_err(e: serde_json::Error) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(format!("json: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::TimerPhase;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    // 2024-01-15 and 2024-01-16, 10:00 UTC
+    const DAY1: u64 = 1_705_312_800_000;
+    const DAY2: u64 = DAY1 + 86_400_000;
+
+    fn add_break(conn: &Connection, started_at: u64, completed: bool) {
+        let id = db::insert_break_record(conn, started_at, 1200).unwrap();
+        db::finish_break_record(
+            conn,
+            id,
+            20,
+            if completed {
+                BreakOutcome::Completed
+            } else {
+                BreakOutcome::Skipped
+            },
+            None,
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_roundtrip_into_empty_db() {
+        let src = setup_test_db();
+        add_break(&src, DAY1, true);
+        add_break(&src, DAY1 + 1_200_000, false);
+        add_break(&src, DAY2, true);
+        let id = db::insert_break_deferral(&src, DAY1 + 600_000, "camera").unwrap();
+        db::finish_break_deferral(&src, id, 90).unwrap();
+        let custom = UserSettings {
+            daily_goal: 12,
+            theme: "dark".to_string(),
+            ..UserSettings::default()
+        };
+        db::save_settings(&src, &custom).unwrap();
+
+        let json = serde_json::to_string(&build_archive(&src).unwrap()).unwrap();
+        let archive = parse_archive(&json).unwrap();
+
+        let dst = setup_test_db();
+        let result = import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+        assert_eq!(result.records_imported, 3);
+        assert_eq!(result.records_skipped, 0);
+        assert_eq!(result.days_rebuilt, 2);
+        assert!(result.settings_applied);
+
+        let settings = db::load_settings(&dst).unwrap();
+        assert_eq!(settings.daily_goal, 12);
+        assert_eq!(settings.theme, "dark");
+        assert_eq!(db::get_all_break_records(&dst).unwrap().len(), 3);
+        assert_eq!(db::get_all_break_deferrals(&dst).unwrap().len(), 1);
+
+        let stats = db::get_daily_stats_range(&dst, "2024-01-15", "2024-01-16").unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].breaks_completed, 1);
+        assert_eq!(stats[0].breaks_skipped, 1);
+        assert_eq!(stats[1].breaks_completed, 1);
+    }
+
+    #[test]
+    fn test_merge_dedupes_and_keeps_settings() {
+        let src = setup_test_db();
+        add_break(&src, DAY1, true);
+        add_break(&src, DAY2, true);
+        db::save_settings(
+            &src,
+            &UserSettings {
+                daily_goal: 30,
+                ..UserSettings::default()
+            },
+        )
+        .unwrap();
+        let archive = build_archive(&src).unwrap();
+
+        let dst = setup_test_db();
+        add_break(&dst, DAY1, true);
+        let result = import_archive(&dst, &archive, ImportMode::Merge).unwrap();
+        assert_eq!(result.records_imported, 1);
+        assert_eq!(result.records_skipped, 1);
+        assert!(!result.settings_applied);
+        assert_eq!(db::get_all_break_records(&dst).unwrap().len(), 2);
+        assert_eq!(
+            db::load_settings(&dst).unwrap().daily_goal,
+            UserSettings::default().daily_goal
+        );
+
+        // Importing the same archive again changes nothing
+        let again = import_archive(&dst, &archive, ImportMode::Merge).unwrap();
+        assert_eq!(again.records_imported, 0);
+        assert_eq!(again.records_skipped, 2);
+    }
+
+    #[test]
+    fn test_replace_clears_existing_history() {
+        let src = setup_test_db();
+        add_break(&src, DAY2, true);
+        let archive = build_archive(&src).unwrap();
+
+        let dst = setup_test_db();
+        add_break(&dst, DAY1, false);
+        db::recompute_daily_stats(&dst, "2024-01-15").unwrap();
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+
+        let records = db::get_all_break_records(&dst).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].started_at, DAY2);
+        let stats = db::get_daily_stats_range(&dst, "2024-01-15", "2024-01-15").unwrap();
+        assert_eq!(stats[0].breaks_skipped, 0);
+    }
+
+    #[test]
+    fn test_replace_drops_the_old_timeline_but_not_the_open_interval() {
+        let src = setup_test_db();
+        let archive = build_archive(&src).unwrap();
+
+        let dst = setup_test_db();
+        crate::timeline::record_phase(&dst, &TimerPhase::Working, DAY1).unwrap();
+        crate::timeline::record_phase(&dst, &TimerPhase::Paused, DAY1 + 600_000).unwrap();
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+
+        let phases: Vec<(String, Option<i64>)> = dst
+            .prepare("SELECT phase, ended_at FROM phase_log")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap();
+        assert_eq!(phases, vec![("Paused".to_string(), None)]);
+    }
+
+    #[test]
+    fn test_replace_takes_the_archived_settings_history() {
+        let src = setup_test_db();
+        src.execute_batch(
+            "INSERT INTO settings_history VALUES ('1970-01-01', 4, 20, 20);
+             INSERT INTO settings_history VALUES ('2024-01-16', 12, 20, 20);",
+        )
+        .unwrap();
+        let archive = build_archive(&src).unwrap();
+        assert_eq!(archive.settings_history.len(), 2);
+
+        let dst = setup_test_db();
+        dst.execute(
+            "INSERT INTO settings_history VALUES ('1970-01-01', 30, 45, 60)",
+            [],
+        )
+        .unwrap();
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+        assert_eq!(
+            db::get_settings_history(&dst).unwrap(),
+            archive.settings_history
+        );
+        assert_eq!(
+            db::settings_in_effect(&dst, "2024-01-15")
+                .unwrap()
+                .daily_goal,
+            4
+        );
+        assert_eq!(
+            db::settings_in_effect(&dst, "2024-01-16")
+                .unwrap()
+                .daily_goal,
+            12
+        );
+
+        // An archive without history leaves none: its settings cover every day
+        let mut archive = archive;
+        archive.settings_history.clear();
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+        assert!(db::get_settings_history(&dst).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replace_forgets_the_last_daily_summary() {
+        let archive = build_archive(&setup_test_db()).unwrap();
+        let dst = setup_test_db();
+        crate::insights::mark_summarized(&dst, "2024-01-15").unwrap();
+
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+
+        let last: Option<String> = dst
+            .query_row(
+                "SELECT last_summarized FROM daily_summary_state WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(last, None);
+    }
+
+    #[test]
+    fn test_replace_takes_the_archived_streak_freezes() {
+        let src = setup_test_db();
+        src.execute(
+            "INSERT INTO streak_freezes (date, frozen_at) VALUES ('2024-01-14', 7)",
+            [],
+        )
+        .unwrap();
+        let archive = build_archive(&src).unwrap();
+
+        let dst = setup_test_db();
+        dst.execute_batch(
+            "INSERT INTO streak_freezes (date, frozen_at) VALUES ('2024-01-15', 9);
+             UPDATE streak_state
+             SET processed_through = '2024-01-20', current_run = 5, freezes_available = 2;",
+        )
+        .unwrap();
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+
+        assert_eq!(streak_freezes(&dst).unwrap(), archive.streak_freezes);
+        let state: (Option<String>, i32, i32) = dst
+            .query_row(
+                "SELECT processed_through, current_run, freezes_available
+                 FROM streak_state WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(state, (None, 0, 0));
+    }
+
+    #[test]
+    fn test_achievements_are_replaced_or_merged() {
+        let src = setup_test_db();
+        src.execute_batch(
+            "INSERT INTO achievements VALUES ('first_break', 100);
+             INSERT INTO achievements VALUES ('perfect_day', 300);",
+        )
+        .unwrap();
+        let archive = build_archive(&src).unwrap();
+
+        let dst = setup_test_db();
+        dst.execute_batch(
+            "INSERT INTO achievements VALUES ('perfect_day', 200);
+             INSERT INTO achievements VALUES ('early_bird', 50);",
+        )
+        .unwrap();
+        import_archive(&dst, &archive, ImportMode::Merge).unwrap();
+        let merged: Vec<_> = unlocked_achievements(&dst)
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.id, a.unlocked_at))
+            .collect();
+        assert_eq!(
+            merged,
+            vec![
+                ("early_bird".to_string(), 50),
+                ("first_break".to_string(), 100),
+                ("perfect_day".to_string(), 200)
+            ]
+        );
+
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+        assert_eq!(unlocked_achievements(&dst).unwrap(), archive.achievements);
+    }
+
+    #[test]
+    fn test_duplicates_within_archive_are_skipped() {
+        let src = setup_test_db();
+        add_break(&src, DAY1, true);
+        let mut archive = build_archive(&src).unwrap();
+        archive.break_records.push(archive.break_records[0].clone());
+
+        let dst = setup_test_db();
+        let result = import_archive(&dst, &archive, ImportMode::Merge).unwrap();
+        assert_eq!(result.records_imported, 1);
+        assert_eq!(result.records_skipped, 1);
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected_without_changes() {
+        let src = setup_test_db();
+        add_break(&src, DAY1, true);
+        let mut archive = build_archive(&src).unwrap();
+        archive.settings["work_interval_minutes"] = serde_json::json!(0);
+
+        let dst = setup_test_db();
+        add_break(&dst, DAY2, true);
+        let err = import_archive(&dst, &archive, ImportMode::Replace).unwrap_err();
+        assert!(err.contains("work_interval_minutes"));
+        let records = db::get_all_break_records(&dst).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].started_at, DAY2);
+    }
+
+    #[test]
+    fn test_partial_settings_use_defaults() {
+        let json = r#"{
+            "format": "blinky-archive",
+            "format_version": 1,
+            "exported_at": 0,
+            "app_version": "0.1.0",
+            "schema_version": 1,
+            "settings": { "daily_goal": 5, "some_future_setting": true },
+            "break_records": []
+        }"#;
+        let archive = parse_archive(json).unwrap();
+        let settings = archive_settings(&archive).unwrap();
+        assert_eq!(settings.daily_goal, 5);
+        assert_eq!(
+            settings.work_interval_minutes,
+            UserSettings::default().work_interval_minutes
+        );
+    }
+
+    #[test]
+    fn test_replace_carries_rolled_up_days() {
+        let src = setup_test_db();
+        add_break(&src, DAY1, true);
+        add_break(&src, DAY2, true);
+        retention::prune_before(&src, NaiveDate::from_ymd_opt(2024, 1, 16).unwrap()).unwrap();
+        let archive = build_archive(&src).unwrap();
+        assert_eq!(archive.break_records.len(), 1);
+        assert_eq!(archive.rolled_up_days.len(), 1);
+
+        let dst = setup_test_db();
+        import_archive(&dst, &archive, ImportMode::Replace).unwrap();
+        assert_eq!(db::pruned_before(&dst).unwrap(), archive.pruned_before);
+        let stats = db::get_daily_stats_range(&dst, "2024-01-15", "2024-01-16").unwrap();
+        assert_eq!(stats[0].breaks_completed, 1);
+        assert_eq!(stats[1].breaks_completed, 1);
+        assert_eq!(retention::get_period_stats(&dst, "month").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_future_archives() {
+        assert!(parse_archive("not json").is_err());
+        let foreign = r#"{"format":"other","format_version":1,"exported_at":0,
+            "app_version":"","schema_version":0,"settings":{},"break_records":[]}"#;
+        assert!(parse_archive(foreign)
+            .unwrap_err()
+            .contains("Not a Blinky archive"));
+        let future = r#"{"format":"blinky-archive","format_version":99,"exported_at":0,
+            "app_version":"","schema_version":0,"settings":{},"break_records":[]}"#;
+        assert!(parse_archive(future).unwrap_err().contains("newer"));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::state::BackupInfo;
+use chrono::{Datelike, NaiveDate, Utc};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags, Result as SqlResult};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Sub-directory of the data directory that holds snapshots.
+const BACKUP_DIR_NAME: &str = "backups";
+
+/// Safety snapshots taken before a restore. Not user-configurable.
+const PRE_RESTORE_KEEP: usize = 3;
+
+/// Wait before retrying a copy that found the source busy or locked, and how
+/// many times to try before giving up.
+const BUSY_RETRY: Duration = Duration::from_millis(50);
+const BUSY_ATTEMPTS: u32 = 100;
+
+/// Returns the backup directory, which sits next to the database file.
+pub fn backups_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(BACKUP_DIR_NAME)
+}
+
+/// Copy the live database into a standalone file at `dest` using SQLite's
+/// online backup API. Writes to a temp file first so a crash never leaves a
+/// truncated snapshot behind.
+pub fn snapshot(conn: &Connection, dest: &Path) -> SqlResult<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    let tmp = dest.with_extension("db.tmp");
+    let _ = std::fs::remove_file(&tmp);
+
+    {
+        let mut dst = Connection::open(&tmp)?;
+        copy_all(conn, &mut dst)?;
+        // The copied header keeps the live DB's WAL flag; a snapshot should be a single file
+        dst.execute_batch("PRAGMA journal_mode=DELETE;")?;
+    }
+
+    std::fs::rename(&tmp, dest).map_err(io_err)?;
+    Ok(())
+}
+
+/// Copy all of `src` into `dst` with the online backup API, in one step. A
+/// snapshot reads from its own connection in a single read transaction, which
+/// WAL lets the writer commit alongside; a copy split into steps would start
+/// over after every such commit.
+fn copy_all(src: &Connection, dst: &mut Connection) -> SqlResult<()> {
+    let backup = Backup::new(src, dst)?;
+    for _ in 0..BUSY_ATTEMPTS {
+        match backup.step(-1)? {
+            StepResult::Done => return Ok(()),
+            // More can't happen with -1; Busy and Locked clear up
+            _ => std::thread::sleep(BUSY_RETRY),
+        }
+    }
+    Err(rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+        Some("database stayed busy during backup".to_string()),
+    ))
+}
+
+/// Take today's daily and this week's weekly snapshot if they don't exist yet,
+/// then prune each kind down to its retention count (0 = kind disabled).
+/// Returns the paths of any snapshots created.
+pub fn run_scheduled_backups(
+    conn: &Connection,
+    dir: &Path,
+    today: NaiveDate,
+    daily_keep: u32,
+    weekly_keep: u32,
+) -> SqlResult<Vec<PathBuf>> {
+    let mut created = Vec::new();
+
+    let iso = today.iso_week();
+    let scheduled = [
+        (
+            "daily",
+            format!("blinky-daily-{}.db", today.format("%Y%m%d")),
+            daily_keep,
+        ),
+        (
+            "weekly",
+            format!("blinky-weekly-{}W{:02}.db", iso.year(), iso.week()),
+            weekly_keep,
+        ),
+    ];
+
+    for (kind, file_name, keep) in scheduled {
+        if keep == 0 {
+            continue;
+        }
+        let path = dir.join(&file_name);
+        if !path.exists() {
+            snapshot(conn, &path)?;
+            created.push(path);
+        }
+        prune(dir, kind, keep as usize)?;
+    }
+
+    Ok(created)
+}
+
+/// List snapshots in `dir`, newest first. A missing directory is an empty list.
+pub fn list_backups(dir: &Path) -> SqlResult<Vec<BackupInfo>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_err(e)),
+    };
+
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let kind = backup_kind(&file_name)?;
+            let meta = entry.metadata().ok()?;
+            let created_at = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            Some(BackupInfo {
+                file_name,
+                kind: kind.to_string(),
+                created_at,
+                size_bytes: meta.len(),
+            })
+        })
+        .collect();
+
+    backups.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| b.file_name.cmp(&a.file_name))
+    });
+    Ok(backups)
+}
+
+/// Replace the contents of the live connection with the snapshot `file_name`.
+///
+/// The snapshot is integrity-checked first and a pre-restore snapshot of the
+/// current data is taken, so a bad restore can itself be undone. Migrations
+/// run afterwards in case the snapshot predates the current schema.
+pub fn restore_backup(conn: &mut Connection, dir: &Path, file_name: &str) -> SqlResult<()> {
+    if backup_kind(file_name).is_none() || file_name.contains(['/', '\\']) {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "not a backup file: {}",
+            file_name
+        )));
+    }
+    let path = dir.join(file_name);
+    if !path.exists() {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "backup not found: {}",
+            file_name
+        )));
+    }
+
+    let src = open_verified(&path, file_name)?;
+
+    let safety = dir.join(format!(
+        "blinky-pre-restore-{}.db",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    snapshot(conn, &safety)?;
+    prune(dir, "pre-restore", PRE_RESTORE_KEEP)?;
+
+    copy_all(&src, conn)?;
+    crate::db::run_migrations(conn)?;
+    Ok(())
+}
+
+/// Copy the newest undamaged snapshot into `conn`, for startup recovery when
+/// the live database is unusable. Returns the file name used, or None if
+/// there's no usable snapshot.
+pub fn restore_latest(conn: &mut Connection, dir: &Path) -> SqlResult<Option<String>> {
+    for info in list_backups(dir)? {
+        let Ok(src) = open_verified(&dir.join(&info.file_name), &info.file_name) else {
+            continue;
+        };
+        copy_all(&src, conn)?;
+        crate::db::run_migrations(conn)?;
+        return Ok(Some(info.file_name));
+    }
+    Ok(None)
+}
+
+/// Open a snapshot read-only, refusing it if `PRAGMA quick_check` finds damage.
+fn open_verified(path: &Path, file_name: &str) -> SqlResult<Connection> {
+    let src = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let check: String = src.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "backup {} is damaged: {}",
+            file_name, check
+        )));
+    }
+    Ok(src)
+}
+
+/// Kind of a snapshot from its file name, or None if it isn't one of ours.
+fn backup_kind(file_name: &str) -> Option<&'static str> {
+    let stem = file_name.strip_prefix("blinky-")?.strip_suffix(".db")?;
+    ["daily", "weekly", "pre-restore"]
+        .into_iter()
+        .find(|kind| stem.starts_with(&format!("{}-", kind)))
+}
+
+/// Delete all but the newest `keep` snapshots of `kind`. File names embed the
+/// date, so name order is age order.
+fn prune(dir: &Path, kind: &str, keep: usize) -> SqlResult<()> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map_err(io_err)?
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| backup_kind(name) == Some(kind))
+        .collect();
+    names.sort_unstable_by(|a, b| b.cmp(a));
+
+    for name in names.iter().skip(keep) {
+        std::fs::remove_file(dir.join(name)).map_err(io_err)?;
+    }
+    Ok(())
+}
+
+fn io_err(e: std::io::Error) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(format!("backup io error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{count_breaks_today, finish_break_record, init_db_conn, insert_break_record};
+    use crate::state::BreakOutcome;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("blinky_backup_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add_completed_break(conn: &Connection) {
+        let now = Utc::now().timestamp_millis() as u64;
+        let id = insert_break_record(conn, now, 1200).unwrap();
+        finish_break_record(conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_is_standalone_copy() {
+        let dir = temp_dir("snapshot");
+        let conn = setup_test_db();
+        add_completed_break(&conn);
+
+        let path = dir.join("blinky-daily-20260101.db");
+        snapshot(&conn, &path).unwrap();
+
+        let copy = Connection::open(&path).unwrap();
+        assert_eq!(count_breaks_today(&copy).unwrap(), 1);
+        let mode: String = copy
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "delete");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scheduled_backups_once_per_period() {
+        let dir = temp_dir("scheduled");
+        let conn = setup_test_db();
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(); // a Monday
+
+        let created = run_scheduled_backups(&conn, &dir, day, 7, 4).unwrap();
+        assert_eq!(created.len(), 2);
+
+        // Same day again: nothing new
+        let created = run_scheduled_backups(&conn, &dir, day, 7, 4).unwrap();
+        assert!(created.is_empty());
+
+        // Next day, same ISO week: only a new daily
+        let created =
+            run_scheduled_backups(&conn, &dir, day + chrono::Duration::days(1), 7, 4).unwrap();
+        assert_eq!(created.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retention_prunes_oldest() {
+        let dir = temp_dir("retention");
+        let conn = setup_test_db();
+        let start = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+
+        for i in 0..30 {
+            run_scheduled_backups(&conn, &dir, start + chrono::Duration::days(i), 7, 2).unwrap();
+        }
+
+        let backups = list_backups(&dir).unwrap();
+        let daily: Vec<_> = backups.iter().filter(|b| b.kind == "daily").collect();
+        let weekly: Vec<_> = backups.iter().filter(|b| b.kind == "weekly").collect();
+        assert_eq!(daily.len(), 7);
+        assert_eq!(weekly.len(), 2);
+        // The newest daily is kept
+        let last = (start + chrono::Duration::days(29)).format("%Y%m%d");
+        assert!(daily
+            .iter()
+            .any(|b| b.file_name == format!("blinky-daily-{}.db", last)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_zero_retention_disables_kind() {
+        let dir = temp_dir("disabled");
+        let conn = setup_test_db();
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+
+        let created = run_scheduled_backups(&conn, &dir, day, 0, 4).unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(list_backups(&dir).unwrap()[0].kind, "weekly");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_ignores_foreign_files_and_missing_dir() {
+        let dir = temp_dir("list");
+        std::fs::write(dir.join("notes.txt"), "hi").unwrap();
+        std::fs::write(dir.join("blinky-daily-20260101.db.tmp"), "").unwrap();
+        assert!(list_backups(&dir).unwrap().is_empty());
+        assert!(list_backups(&dir.join("nope")).unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_replaces_live_data() {
+        let dir = temp_dir("restore");
+        let mut conn = setup_test_db();
+
+        // Snapshot with one break, then add two more to the live DB
+        add_completed_break(&conn);
+        let name = "blinky-daily-20260101.db";
+        snapshot(&conn, &dir.join(name)).unwrap();
+        add_completed_break(&conn);
+        add_completed_break(&conn);
+        assert_eq!(count_breaks_today(&conn).unwrap(), 3);
+
+        restore_backup(&mut conn, &dir, name).unwrap();
+        assert_eq!(count_breaks_today(&conn).unwrap(), 1);
+
+        // The pre-restore state was kept
+        let backups = list_backups(&dir).unwrap();
+        assert!(backups.iter().any(|b| b.kind == "pre-restore"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_rejects_bad_names_and_damaged_files() {
+        let dir = temp_dir("reject");
+        let mut conn = setup_test_db();
+        add_completed_break(&conn);
+
+        assert!(restore_backup(&mut conn, &dir, "../blinky.db").is_err());
+        assert!(restore_backup(&mut conn, &dir, "blinky-daily-20990101.db").is_err());
+
+        std::fs::write(
+            dir.join("blinky-daily-20260101.db"),
+            b"definitely not sqlite",
+        )
+        .unwrap();
+        assert!(restore_backup(&mut conn, &dir, "blinky-daily-20260101.db").is_err());
+
+        // Live data untouched
+        assert_eq!(count_breaks_today(&conn).unwrap(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
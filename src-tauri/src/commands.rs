@@ -1,9 +1,21 @@
+use crate::archive::{ImportMode, ImportResult};
+use crate::db_writer::DbWriter;
+use crate::export::CsvExportOptions;
+use crate::history::{BreakEdit, BreakRecordEdit, HistoryPage, HistoryQuery};
+use crate::report::{ReportFormat, ReportPeriod};
 use crate::state::{
-    AnalyticsSummary, AppState, BreakRecord, DailyStats, DbConnection, OnboardingState, TimerPhase,
-    TimerState, UserSettings,
+    Achievement, AnalyticsSummary, AppState, BackupInfo, BreakRecord, BreakSource, DailyStats,
+    DataLocation, DayCoverage, DayTimeline, DbConnection, DeferralSummary, HourlyHeatmap, Insight,
+    OnboardingState, PeriodStats, RecoveryReport, RetentionResult, SettingsSnapshot,
+    SkipReasonBreakdown, TimerPhase, TimerState, UserSettings,
 };
-use crate::{analytics, autostart, db, onboarding, settings, timer};
-use tauri::{AppHandle, Emitter, Manager, State};
+use crate::streaks::Workdays;
+use crate::{
+    achievements, aggregates, analytics, archive, autostart, backup, coverage, db, history,
+    insights, onboarding, report, retention, settings, timeline, timer,
+};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State, Window};
 
 #[tauri::command]
 pub fn get_timer_state(state: State<AppState>) -> Result<TimerState, String> {
@@ -21,14 +33,40 @@ pub fn resume_timer(app: AppHandle) -> Result<TimerState, String> {
     Ok(timer::resume(&app))
 }
 
+/// Skip the current break, optionally saying why. `reason` must be one of the
+/// configured skip reasons.
+#[tauri::command]
+pub fn skip_break(
+    app: AppHandle,
+    window: Window,
+    reason: Option<String>,
+) -> Result<TimerState, String> {
+    if let Some(reason) = &reason {
+        let settings = app
+            .state::<AppState>()
+            .settings
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone();
+        let offered = settings::parse_skip_reasons(&settings.skip_reasons).unwrap_or_default();
+        if !offered.contains(reason) {
+            return Err(format!("'{}' is not one of the skip reasons", reason));
+        }
+    }
+    let source = BreakSource::from_window_label(window.label());
+    Ok(timer::skip_break(&app, source, reason))
+}
+
 #[tauri::command]
-pub fn skip_break(app: AppHandle) -> Result<TimerState, String> {
-    Ok(timer::skip_break(&app))
+pub fn snooze_break(app: AppHandle, window: Window) -> Result<TimerState, String> {
+    let source = BreakSource::from_window_label(window.label());
+    Ok(timer::snooze_break(&app, source))
 }
 
 #[tauri::command]
-pub fn reset_timer(app: AppHandle) -> Result<TimerState, String> {
-    Ok(timer::reset(&app))
+pub fn reset_timer(app: AppHandle, window: Window) -> Result<TimerState, String> {
+    let source = BreakSource::from_window_label(window.label());
+    Ok(timer::reset(&app, source))
 }
 
 #[tauri::command]
@@ -43,16 +81,13 @@ pub fn update_settings(app: AppHandle, settings: UserSettings) -> Result<UserSet
     settings::validate_settings(&settings)?;
 
     let state = app.state::<AppState>();
-    let db_conn = app.state::<DbConnection>();
+    let writer = app.state::<DbWriter>();
 
     // Check what changed for side effects
     let old_settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
 
     // Save to DB
-    {
-        let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-        db::save_settings(&conn, &settings).map_err(|e| e.to_string())?;
-    }
+    save_settings(&writer, &settings)?;
 
     // Update in-memory state
     {
@@ -65,6 +100,11 @@ pub fn update_settings(app: AppHandle, settings: UserSettings) -> Result<UserSet
         autostart::set_autostart(&app, settings.launch_at_login);
     }
 
+    // Side effect: streaks stored for the old workdays no longer apply
+    if old_settings.workdays != settings.workdays {
+        crate::maintenance::run_rollover(&app);
+    }
+
     // Emit settings-changed event
     let _ = app.emit("settings-changed", &settings);
 
@@ -76,9 +116,105 @@ pub fn get_analytics_summary(
     state: State<AppState>,
     db_conn: State<DbConnection>,
 ) -> Result<AnalyticsSummary, String> {
-    let daily_goal = state.settings.lock().map_err(|e| e.to_string())?.daily_goal;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    analytics::build_analytics_summary(
+        &conn,
+        settings.daily_goal,
+        &Workdays::from_settings(&settings),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Every achievement, with when it was unlocked for the ones that are.
+#[tauri::command]
+pub fn get_achievements(db_conn: State<DbConnection>) -> Result<Vec<Achievement>, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    achievements::get_achievements(&conn).map_err(|e| e.to_string())
+}
+
+/// Breaks taken against the breaks working time called for, per day between
+/// two dates (YYYY-MM-DD, UTC).
+#[tauri::command]
+pub fn get_coverage(
+    db_conn: State<DbConnection>,
+    from: String,
+    to: String,
+) -> Result<Vec<DayCoverage>, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    coverage::coverage_range(&conn, &from, &to).map_err(|e| e.to_string())
+}
+
+/// Findings from the insight rules over recent history, strongest first.
+#[tauri::command]
+pub fn get_insights(
+    state: State<AppState>,
+    db_conn: State<DbConnection>,
+) -> Result<Vec<Insight>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    insights::build_insights(
+        &conn,
+        settings.daily_goal,
+        &Workdays::from_settings(&settings),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Compliance by local weekday and hour between two dates (YYYY-MM-DD, UTC).
+#[tauri::command]
+pub fn get_hourly_heatmap(
+    db_conn: State<DbConnection>,
+    from: String,
+    to: String,
+) -> Result<HourlyHeatmap, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    analytics::build_hourly_heatmap(&conn, &from, &to).map_err(|e| e.to_string())
+}
+
+/// Skipped breaks by reason and local hour between two dates (YYYY-MM-DD, UTC).
+#[tauri::command]
+pub fn get_skip_reasons(
+    db_conn: State<DbConnection>,
+    from: String,
+    to: String,
+) -> Result<SkipReasonBreakdown, String> {
     let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-    analytics::build_analytics_summary(&conn, daily_goal).map_err(|e| e.to_string())
+    analytics::build_skip_reasons(&conn, &from, &to).map_err(|e| e.to_string())
+}
+
+/// Goal, interval and break length over time, one entry per day they changed.
+#[tauri::command]
+pub fn get_settings_history(db_conn: State<DbConnection>) -> Result<Vec<SettingsSnapshot>, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    db::get_settings_history(&conn).map_err(|e| e.to_string())
+}
+
+/// Write a report for the last complete week or month into `destination`
+/// (a folder), or the configured report folder. Returns the file path.
+#[tauri::command]
+pub fn generate_report(
+    state: State<AppState>,
+    db_conn: State<DbConnection>,
+    period: ReportPeriod,
+    format: ReportFormat,
+    destination: Option<String>,
+) -> Result<String, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let dir = match destination {
+        Some(d) if !d.trim().is_empty() => PathBuf::from(d),
+        _ => report::report_dir(&settings),
+    };
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    let path = report::generate(
+        &conn,
+        period,
+        format,
+        chrono::Utc::now().date_naive(),
+        &settings,
+        &dir,
+    )?;
+    Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -91,6 +227,46 @@ pub fn get_break_history(
     db::get_break_records(&conn, limit, offset).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn query_break_history(
+    db_conn: State<DbConnection>,
+    query: Option<HistoryQuery>,
+) -> Result<HistoryPage, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    history::query_history(&conn, &query.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Correct a finished break's outcome, duration or note.
+#[tauri::command]
+pub fn edit_break_record(app: AppHandle, id: i64, edit: BreakEdit) -> Result<BreakRecord, String> {
+    let (record, breaks_today) = app.state::<DbWriter>().call(move |conn| {
+        let record = history::edit_record(conn, id, &edit).map_err(|e| e.to_string())?;
+        let breaks_today = db::count_breaks_today(conn).map_err(|e| e.to_string())?;
+        Ok::<_, String>((record, breaks_today))
+    })??;
+    history_changed(&app, breaks_today)?;
+    Ok(record)
+}
+
+#[tauri::command]
+pub fn delete_break_record(app: AppHandle, id: i64) -> Result<(), String> {
+    let breaks_today = app.state::<DbWriter>().call(move |conn| {
+        history::delete_record(conn, id).map_err(|e| e.to_string())?;
+        db::count_breaks_today(conn).map_err(|e| e.to_string())
+    })??;
+    history_changed(&app, breaks_today)
+}
+
+/// The audit trail of history corrections, newest first.
+#[tauri::command]
+pub fn get_break_record_edits(
+    db_conn: State<DbConnection>,
+    break_id: Option<i64>,
+) -> Result<Vec<BreakRecordEdit>, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    history::get_edits(&conn, break_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_daily_stats_range(
     db_conn: State<DbConnection>,
@@ -102,18 +278,146 @@ pub fn get_daily_stats_range(
 }
 
 #[tauri::command]
-pub fn export_data_csv(db_conn: State<DbConnection>) -> Result<String, String> {
+pub fn get_deferral_summary(
+    db_conn: State<DbConnection>,
+    from: String,
+    to: String,
+) -> Result<Vec<DeferralSummary>, String> {
     let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-    db::export_to_csv(&conn).map_err(|e| e.to_string())
+    db::get_deferral_summary(&conn, &from, &to).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_data_csv(
+    db_conn: State<DbConnection>,
+    options: Option<CsvExportOptions>,
+) -> Result<String, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    db::export_to_csv(&conn, &options.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_daily_stats_csv(
+    db_conn: State<DbConnection>,
+    options: Option<CsvExportOptions>,
+) -> Result<String, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    db::export_daily_stats_csv(&conn, &options.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Write a full JSON archive to `destination`, or to Downloads if not given.
+#[tauri::command]
+pub fn export_archive(
+    db_conn: State<DbConnection>,
+    destination: Option<String>,
+) -> Result<String, String> {
+    let archive = {
+        let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+        archive::build_archive(&conn).map_err(|e| e.to_string())?
+    };
+
+    let path = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => db::downloads_dir().join(format!(
+            "blinky_archive_{}.json",
+            chrono::Utc::now().format("%Y%m%d_%H%M%S")
+        )),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&archive).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn import_archive(
+    app: AppHandle,
+    contents: String,
+    mode: ImportMode,
+) -> Result<ImportResult, String> {
+    let state = app.state::<AppState>();
+    let writer = app.state::<DbWriter>();
+    let parsed = archive::parse_archive(&contents)?;
+
+    let (result, settings, breaks_today) = writer.call(move |conn| {
+        let result = archive::import_archive(conn, &parsed, mode)?;
+        let settings = db::load_settings(conn).map_err(|e| e.to_string())?;
+        let breaks_today = db::count_breaks_today(conn).map_err(|e| e.to_string())?;
+        Ok::<_, String>((result, settings, breaks_today))
+    })??;
+
+    {
+        let mut timer = state.timer.lock().map_err(|e| e.to_string())?;
+        timer.breaks_completed_today = breaks_today;
+    }
+    crate::maintenance::run_rollover(&app);
+
+    if result.settings_applied {
+        let old_settings = {
+            let mut s = state.settings.lock().map_err(|e| e.to_string())?;
+            std::mem::replace(&mut *s, settings.clone())
+        };
+        // Replace wiped the table holding any in-flight break row
+        {
+            let mut internal = state.timer_internal.lock().map_err(|e| e.to_string())?;
+            internal.current_break_started_at = None;
+            internal.deferral = None;
+        }
+        if old_settings.launch_at_login != settings.launch_at_login {
+            autostart::set_autostart(&app, settings.launch_at_login);
+        }
+        let _ = app.emit("settings-changed", &settings);
+    }
+
+    let _ = app.emit("data-imported", &result);
+
+    Ok(result)
 }
 
+/// Apply the retention setting now, then VACUUM the database.
 #[tauri::command]
-pub fn clear_all_data(
+pub fn compact_now(
     state: State<AppState>,
+    writer: State<DbWriter>,
+) -> Result<RetentionResult, String> {
+    let cutoff = {
+        let s = state.settings.lock().map_err(|e| e.to_string())?;
+        retention::cutoff_date(
+            chrono::Utc::now().date_naive(),
+            s.retention_amount,
+            &s.retention_unit,
+        )
+    };
+    writer
+        .call(move |conn| retention::compact(conn, cutoff))?
+        .map_err(|e| e.to_string())
+}
+
+/// Weekly ("week") or monthly ("month") roll-ups of pruned history.
+#[tauri::command]
+pub fn get_period_stats(
     db_conn: State<DbConnection>,
-) -> Result<bool, String> {
+    kind: String,
+) -> Result<Vec<PeriodStats>, String> {
     let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-    db::clear_all_data(&conn).map_err(|e| e.to_string())?;
+    retention::get_period_stats(&conn, &kind).map_err(|e| e.to_string())
+}
+
+/// Phase intervals for one UTC day (YYYY-MM-DD), for the day view.
+#[tauri::command]
+pub fn get_timeline(db_conn: State<DbConnection>, date: String) -> Result<DayTimeline, String> {
+    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
+    timeline::get_timeline(&conn, &date).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn clear_all_data(state: State<AppState>, writer: State<DbWriter>) -> Result<bool, String> {
+    writer
+        .call(|conn| db::clear_all_data(conn))?
+        .map_err(|e| e.to_string())?;
 
     // Reset in-memory settings to defaults
     {
@@ -130,6 +434,97 @@ pub fn clear_all_data(
     Ok(true)
 }
 
+/// Where this session keeps its data, and why.
+#[tauri::command]
+pub fn get_data_location(state: State<AppState>) -> Result<DataLocation, String> {
+    Ok(state.data_location.clone())
+}
+
+/// Recompute the stored lifetime totals and streaks from all history, in
+/// case they ever drift from the break records.
+#[tauri::command]
+pub fn rebuild_aggregates(state: State<AppState>, writer: State<DbWriter>) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    writer
+        .call(move |conn| {
+            aggregates::rebuild(
+                conn,
+                settings.daily_goal,
+                &Workdays::from_settings(&settings),
+                chrono::Utc::now().date_naive(),
+            )
+        })?
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// --- Backup commands ---
+
+/// The startup recovery report, if the database had to be recovered this launch.
+#[tauri::command]
+pub fn get_recovery_report(state: State<AppState>) -> Result<Option<RecoveryReport>, String> {
+    let recovery = state.recovery.lock().map_err(|e| e.to_string())?;
+    Ok(recovery.clone())
+}
+
+#[tauri::command]
+pub fn dismiss_recovery_report(state: State<AppState>) -> Result<bool, String> {
+    let mut recovery = state.recovery.lock().map_err(|e| e.to_string())?;
+    *recovery = None;
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn list_backups(state: State<AppState>) -> Result<Vec<BackupInfo>, String> {
+    let dir = backup::backups_dir(Path::new(&state.db_path));
+    backup::list_backups(&dir).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_backup(app: AppHandle, file_name: String) -> Result<UserSettings, String> {
+    let state = app.state::<AppState>();
+    let writer = app.state::<DbWriter>();
+    let dir = backup::backups_dir(Path::new(&state.db_path));
+
+    // Restore in place on the writer, then re-read everything that was cached
+    // from the old data.
+    let name = file_name.clone();
+    let (settings, breaks_today) = writer
+        .call(move |conn| {
+            backup::restore_backup(conn, &dir, &name)?;
+            let settings = db::load_settings(conn)?;
+            let breaks_today = db::count_breaks_today(conn)?;
+            Ok::<_, rusqlite::Error>((settings, breaks_today))
+        })?
+        .map_err(|e| e.to_string())?;
+
+    let old_settings = {
+        let mut s = state.settings.lock().map_err(|e| e.to_string())?;
+        std::mem::replace(&mut *s, settings.clone())
+    };
+
+    {
+        let mut timer = state.timer.lock().map_err(|e| e.to_string())?;
+        timer.breaks_completed_today = breaks_today;
+    }
+
+    // Any in-flight break row belongs to the replaced data
+    {
+        let mut internal = state.timer_internal.lock().map_err(|e| e.to_string())?;
+        internal.current_break_started_at = None;
+        internal.deferral = None;
+    }
+
+    if old_settings.launch_at_login != settings.launch_at_login {
+        autostart::set_autostart(&app, settings.launch_at_login);
+    }
+
+    let _ = app.emit("settings-changed", &settings);
+    let _ = app.emit("data-restored", &file_name);
+
+    Ok(settings)
+}
+
 // --- Onboarding commands ---
 
 #[tauri::command]
@@ -141,15 +536,14 @@ pub fn get_onboarding_state(state: State<AppState>) -> Result<OnboardingState, S
 #[tauri::command]
 pub fn complete_onboarding(app: AppHandle) -> Result<OnboardingState, String> {
     let state = app.state::<AppState>();
-    let db_conn = app.state::<DbConnection>();
+    let writer = app.state::<DbWriter>();
 
     let result = {
         let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
         onboarding::complete_onboarding(&mut settings);
 
         // Persist to DB
-        let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-        db::save_settings(&conn, &settings).map_err(|e| e.to_string())?;
+        save_settings(&writer, &settings)?;
 
         onboarding::build_onboarding_state(&settings)
     };
@@ -166,14 +560,13 @@ pub fn complete_onboarding(app: AppHandle) -> Result<OnboardingState, String> {
 #[tauri::command]
 pub fn mark_tooltip_seen(app: AppHandle, tooltip_id: String) -> Result<Vec<String>, String> {
     let state = app.state::<AppState>();
-    let db_conn = app.state::<DbConnection>();
+    let writer = app.state::<DbWriter>();
 
     let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
     let seen = onboarding::mark_tooltip_seen(&mut settings, &tooltip_id);
 
     // Persist to DB
-    let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-    db::save_settings(&conn, &settings).map_err(|e| e.to_string())?;
+    save_settings(&writer, &settings)?;
 
     Ok(seen)
 }
@@ -193,7 +586,7 @@ pub fn trigger_demo_break(app: AppHandle) -> Result<bool, String> {
     // Transition timer to Breaking phase with 5-second duration.
     // When the demo break completes, the timer will return to Working,
     // but since onboarding isn't complete, we immediately re-pause it.
-    {
+    let started_at = {
         let mut timer = state.timer.lock().map_err(|e| e.to_string())?;
         let now_ms = chrono::Utc::now().timestamp_millis() as u64;
 
@@ -201,7 +594,19 @@ pub fn trigger_demo_break(app: AppHandle) -> Result<bool, String> {
         timer.phase_duration = 5;
         timer.seconds_remaining = 5;
         timer.phase_started_at = now_ms;
+        now_ms
+    };
+
+    // Record it so the log is complete; it finishes with the demo outcome and
+    // never counts toward stats.
+    {
+        let mut internal = state.timer_internal.lock().map_err(|e| e.to_string())?;
+        internal.current_break_started_at = Some(started_at);
     }
+    timer::write(&app, move |conn| {
+        let _ = db::insert_break_record(conn, started_at, 0);
+    });
+    timer::log_phase(&app, &TimerPhase::Breaking, started_at);
 
     // Show overlay if enabled
     {
@@ -224,15 +629,14 @@ pub fn trigger_demo_break(app: AppHandle) -> Result<bool, String> {
 #[tauri::command]
 pub fn reset_onboarding(app: AppHandle) -> Result<bool, String> {
     let state = app.state::<AppState>();
-    let db_conn = app.state::<DbConnection>();
+    let writer = app.state::<DbWriter>();
 
     {
         let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
         onboarding::reset_onboarding(&mut settings);
 
         // Persist to DB
-        let conn = db_conn.0.lock().map_err(|e| e.to_string())?;
-        db::save_settings(&conn, &settings).map_err(|e| e.to_string())?;
+        save_settings(&writer, &settings)?;
     }
 
     // Pause the timer
@@ -240,3 +644,26 @@ pub fn reset_onboarding(app: AppHandle) -> Result<bool, String> {
 
     Ok(true)
 }
+
+/// Persist settings through the writer and wait for the result.
+fn save_settings(writer: &DbWriter, settings: &UserSettings) -> Result<(), String> {
+    let settings = settings.clone();
+    writer
+        .call(move |conn| db::save_settings(conn, &settings))?
+        .map_err(|e| e.to_string())
+}
+
+/// A correction may have changed today's count and past streaks; keep the
+/// timer in step and let open views refresh.
+fn history_changed(app: &AppHandle, breaks_today: u32) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state
+        .timer
+        .lock()
+        .map_err(|e| e.to_string())?
+        .breaks_completed_today = breaks_today;
+    // The edit dropped the stored streaks and totals; rebuild them now
+    crate::maintenance::run_rollover(app);
+    let _ = app.emit("history-changed", ());
+    Ok(())
+}
@@ -0,0 +1,191 @@
+use crate::aggregates;
+use crate::db;
+use crate::state::DayCoverage;
+use crate::timeline;
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
+
+/// Coverage for `date` (YYYY-MM-DD, UTC), using the work interval in effect
+/// that day.
+pub fn day_coverage(conn: &Connection, date: &str) -> SqlResult<DayCoverage> {
+    day_coverage_at(conn, date, Utc::now().timestamp_millis() as u64)
+}
+
+fn day_coverage_at(conn: &Connection, date: &str, now: u64) -> SqlResult<DayCoverage> {
+    let timeline = timeline::get_timeline_at(conn, date, now)?;
+    let stats = db::compute_daily_stats(conn, date)?;
+    let interval_minutes = db::settings_in_effect(conn, date)?.work_interval_minutes;
+    let deferred_seconds = deferred_seconds(conn, date)?;
+    Ok(coverage(
+        date,
+        timeline.working_seconds.saturating_sub(deferred_seconds),
+        timeline.paused_seconds,
+        interval_minutes,
+        stats.breaks_completed,
+        stats.breaks_skipped,
+    ))
+}
+
+/// Seconds of `date` (UTC) spent with a due break held back by a meeting.
+/// The timeline logs them as working, but no further break was due then. A
+/// deferral still running has no length yet and counts once it ends.
+fn deferred_seconds(conn: &Connection, date: &str) -> SqlResult<u64> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+        rusqlite::Error::InvalidParameterName(format!("bad date '{}': {}", date, e))
+    })?;
+    let day_start = aggregates::day_start_ms(day);
+    let day_end = day_start + 86_400_000;
+    conn.query_row(
+        "SELECT COALESCE(SUM(MIN(started_at + deferred_seconds * 1000, ?2) - MAX(started_at, ?1)), 0)
+         FROM break_deferrals
+         WHERE started_at < ?2 AND started_at + deferred_seconds * 1000 > ?1",
+        params![day_start, day_end],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|ms| ms as u64 / 1000)
+}
+
+/// Coverage for each day from `from` to `to` inclusive.
+pub fn coverage_range(conn: &Connection, from: &str, to: &str) -> SqlResult<Vec<DayCoverage>> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+            rusqlite::Error::InvalidParameterName(format!("bad date '{}': {}", date, e))
+        })
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    let now = Utc::now().timestamp_millis() as u64;
+    from.iter_days()
+        .take_while(|day| *day <= to)
+        .map(|day| day_coverage_at(conn, &day.format("%Y-%m-%d").to_string(), now))
+        .collect()
+}
+
+/// One break is due per full work interval of active time. Suspended time
+/// (away from the computer) earns none; paused time does, since pausing
+/// doesn't stop the work. Paused time without input is logged as suspended by
+/// the idle monitor, so a pause left on overnight doesn't count. Breaks that
+/// were taken or skipped are always expected, so coverage never exceeds 100%.
+fn coverage(
+    date: &str,
+    working_seconds: u64,
+    paused_seconds: u64,
+    interval_minutes: u32,
+    breaks_completed: u32,
+    breaks_skipped: u32,
+) -> DayCoverage {
+    let interval_seconds = (interval_minutes.max(1) as u64) * 60;
+    let active_seconds = working_seconds + paused_seconds;
+    let handled = breaks_completed + breaks_skipped;
+    let expected_breaks = ((active_seconds / interval_seconds) as u32).max(handled);
+    let missed_while_paused =
+        ((paused_seconds / interval_seconds) as u32).min(expected_breaks - handled);
+    let coverage_rate = if expected_breaks == 0 {
+        0.0
+    } else {
+        breaks_completed as f64 / expected_breaks as f64
+    };
+
+    DayCoverage {
+        date: date.to_string(),
+        active_seconds,
+        expected_breaks,
+        breaks_completed,
+        breaks_skipped,
+        missed_while_paused,
+        coverage_rate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{
+        finish_break_deferral, finish_break_record, init_db_conn, insert_break_deferral,
+        insert_break_record,
+    };
+    use crate::state::{BreakOutcome, TimerPhase};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    // 2024-01-15T00:00:00Z
+    const DAY: u64 = 1_705_276_800_000;
+    const MIN: u64 = 60_000;
+
+    #[test]
+    fn test_pausing_all_day_is_not_full_coverage() {
+        let conn = setup_test_db();
+        // One completed break, then four hours paused at a 20 minute interval
+        let id = insert_break_record(&conn, DAY + 20 * MIN, 1200).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Working, DAY).unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Paused, DAY + 20 * MIN).unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Suspended, DAY + 260 * MIN).unwrap();
+
+        let coverage = day_coverage_at(&conn, "2024-01-15", DAY + 300 * MIN).unwrap();
+        assert_eq!(coverage.active_seconds, 260 * 60);
+        assert_eq!(coverage.expected_breaks, 13);
+        assert_eq!(coverage.missed_while_paused, 12);
+        assert!((coverage.coverage_rate - 1.0 / 13.0).abs() < 0.001);
+
+        // Compliance alone says everything is fine
+        let stats = db::compute_daily_stats(&conn, "2024-01-15").unwrap();
+        assert!((stats.compliance_rate - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_overnight_pause_only_counts_until_idle() {
+        let conn = setup_test_db();
+        // Paused at the end of the day, gone five minutes later, back the
+        // next morning
+        timeline::record_phase(&conn, &TimerPhase::Working, DAY + 17 * 60 * MIN).unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Paused, DAY + 18 * 60 * MIN).unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Suspended, DAY + 18 * 60 * MIN + 5 * MIN)
+            .unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Paused, DAY + 32 * 60 * MIN).unwrap();
+
+        let coverage = day_coverage_at(&conn, "2024-01-15", DAY + 33 * 60 * MIN).unwrap();
+        assert_eq!(coverage.active_seconds, 65 * 60);
+        assert_eq!(coverage.expected_breaks, 3);
+        assert_eq!(coverage.missed_while_paused, 0);
+    }
+
+    #[test]
+    fn test_deferred_time_earns_no_breaks() {
+        let conn = setup_test_db();
+        // An hour at the computer, 40 minutes of it in a meeting that held
+        // back the break due after 20
+        timeline::record_phase(&conn, &TimerPhase::Working, DAY).unwrap();
+        let id = insert_break_deferral(&conn, DAY + 20 * MIN, "camera").unwrap();
+        finish_break_deferral(&conn, id, 40 * 60).unwrap();
+        let id = insert_break_record(&conn, DAY + 60 * MIN, 20).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+        timeline::record_phase(&conn, &TimerPhase::Suspended, DAY + 60 * MIN).unwrap();
+
+        let coverage = day_coverage_at(&conn, "2024-01-15", DAY + 120 * MIN).unwrap();
+        assert_eq!(coverage.active_seconds, 20 * 60);
+        assert_eq!(coverage.expected_breaks, 1);
+        assert!((coverage.coverage_rate - 1.0).abs() < 0.001);
+
+        // A deferral running past midnight only counts the part on this day
+        let id = insert_break_deferral(&conn, DAY - 10 * MIN, "microphone").unwrap();
+        finish_break_deferral(&conn, id, 30 * 60).unwrap();
+        assert_eq!(deferred_seconds(&conn, "2024-01-15").unwrap(), 60 * 60);
+    }
+
+    #[test]
+    fn test_handled_breaks_are_always_expected() {
+        // No phase log (e.g. data from before it existed)
+        let day = coverage("2024-01-15", 0, 0, 20, 3, 1);
+        assert_eq!(day.expected_breaks, 4);
+        assert_eq!(day.missed_while_paused, 0);
+        assert!((day.coverage_rate - 0.75).abs() < 0.001);
+
+        let empty = coverage("2024-01-15", 0, 0, 20, 0, 0);
+        assert_eq!(empty.expected_breaks, 0);
+        assert_eq!(empty.coverage_rate, 0.0);
+    }
+}
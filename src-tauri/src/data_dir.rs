@@ -0,0 +1,341 @@
+use crate::state::DataLocation;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the data directory.
+pub const DATA_DIR_ENV: &str = "BLINKY_DATA_DIR";
+
+/// Directory next to the executable that holds data in portable mode. Its
+/// presence is what keeps later launches portable.
+pub const PORTABLE_DIR_NAME: &str = "blinky-data";
+
+/// File in the default directory remembering the last location chosen with
+/// `--data-dir` or the environment, so launches without them (autostart)
+/// keep using it.
+const POINTER_FILE_NAME: &str = "data-location";
+
+/// Everything in a data directory that belongs to Blinky and moves with it.
+const DATA_ENTRIES: &[&str] = &[
+    "blinky.db",
+    "blinky.db-wal",
+    "blinky.db-shm",
+    "backups",
+    "quarantine",
+];
+
+/// Data-directory options from the command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct CliOptions {
+    pub data_dir: Option<PathBuf>,
+    pub portable: bool,
+}
+
+/// Accepts `--data-dir <path>`, `--data-dir=<path>` and `--portable`, and
+/// ignores anything else (the OS and Tauri add their own arguments).
+pub fn parse_args(args: &[String]) -> CliOptions {
+    let mut opts = CliOptions::default();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--portable" {
+            opts.portable = true;
+        } else if arg == "--data-dir" {
+            opts.data_dir = iter.next().map(PathBuf::from);
+        } else if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            opts.data_dir = Some(PathBuf::from(dir));
+        }
+    }
+    opts
+}
+
+/// The default data directory when nothing else is configured.
+pub fn default_dir() -> PathBuf {
+    let base = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("com.blinky.app")
+}
+
+/// Pick the data directory. Precedence: `--data-dir`, then `BLINKY_DATA_DIR`,
+/// then portable mode (`--portable`, or a `blinky-data` directory next to the
+/// executable), then the remembered location if it still exists, then the
+/// default.
+pub fn resolve(
+    cli: &CliOptions,
+    env: Option<&str>,
+    exe_dir: Option<&Path>,
+    remembered: Option<&Path>,
+    default: &Path,
+) -> DataLocation {
+    let location = |dir: &Path, source: &str| DataLocation {
+        dir: dir.to_string_lossy().to_string(),
+        source: source.to_string(),
+    };
+
+    if let Some(dir) = &cli.data_dir {
+        return location(dir, "cli");
+    }
+    if let Some(dir) = env.filter(|d| !d.trim().is_empty()) {
+        return location(Path::new(dir), "env");
+    }
+    if let Some(exe_dir) = exe_dir {
+        let portable = exe_dir.join(PORTABLE_DIR_NAME);
+        if cli.portable || portable.is_dir() {
+            return location(&portable, "portable");
+        }
+    }
+    // A synced folder or removable drive that isn't mounted must not get a
+    // fresh empty database
+    match remembered {
+        Some(dir) if dir.is_dir() => location(dir, "remembered"),
+        _ => location(default, "default"),
+    }
+}
+
+/// The location last chosen explicitly, if any.
+pub fn read_remembered(default: &Path) -> Option<PathBuf> {
+    let contents = fs::read_to_string(default.join(POINTER_FILE_NAME)).ok()?;
+    let trimmed = contents.trim();
+    (!trimmed.is_empty()).then(|| PathBuf::from(trimmed))
+}
+
+/// Remember `dir` for later launches; the default directory clears the pointer.
+pub fn remember(default: &Path, dir: &Path) -> io::Result<()> {
+    let pointer = default.join(POINTER_FILE_NAME);
+    if dir == default {
+        return match fs::remove_file(&pointer) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(default)?;
+    fs::write(pointer, dir.to_string_lossy().as_bytes())
+}
+
+/// Move Blinky's files from `from` to `to` when the data directory changes.
+///
+/// Only runs if `to` has no database yet, so an existing database there is
+/// never overwritten. Everything is copied before anything is deleted: if a
+/// copy fails, what was copied is removed again and `from` is left as it was.
+/// Returns whether anything was moved.
+pub fn migrate(from: &Path, to: &Path) -> io::Result<bool> {
+    if from == to || !from.join("blinky.db").exists() || to.join("blinky.db").exists() {
+        return Ok(false);
+    }
+    // Fold the WAL into the database file, so it's complete on its own
+    checkpoint(&from.join("blinky.db"))?;
+    fs::create_dir_all(to)?;
+
+    let entries: Vec<&str> = DATA_ENTRIES
+        .iter()
+        .copied()
+        .filter(|name| from.join(name).exists())
+        .collect();
+    let mut created = Vec::new();
+    for name in &entries {
+        let dest = to.join(name);
+        if !dest.exists() {
+            created.push(dest.clone());
+        }
+        if let Err(e) = copy_recursive(&from.join(name), &dest) {
+            for path in &created {
+                let _ = remove_entry(path);
+            }
+            return Err(e);
+        }
+    }
+
+    for name in &entries {
+        if let Err(e) = remove_entry(&from.join(name)) {
+            eprintln!("[data_dir] Failed to remove old {}: {}", name, e);
+        }
+    }
+    Ok(true)
+}
+
+fn checkpoint(db: &Path) -> io::Result<()> {
+    let to_io = |e: rusqlite::Error| io::Error::other(e.to_string());
+    let conn = rusqlite::Connection::open(db).map_err(to_io)?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(to_io)
+}
+
+fn remove_entry(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn copy_recursive(src: &Path, dest: &Path) -> io::Result<()> {
+    if src.is_dir() {
+        fs::create_dir_all(dest)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(src, dest).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "blinky-datadir-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("blinky")
+            .chain(list.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&args(&[])), CliOptions::default());
+        assert_eq!(
+            parse_args(&args(&["--data-dir", "/tmp/a"])).data_dir,
+            Some(PathBuf::from("/tmp/a"))
+        );
+        assert_eq!(
+            parse_args(&args(&["--minimized", "--data-dir=/tmp/b"])).data_dir,
+            Some(PathBuf::from("/tmp/b"))
+        );
+        assert!(parse_args(&args(&["--portable"])).portable);
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let root = temp_dir("resolve");
+        let default = root.join("default");
+        let exe_dir = root.join("exe");
+        let remembered = root.join("synced");
+        fs::create_dir_all(&exe_dir).unwrap();
+        fs::create_dir_all(&remembered).unwrap();
+
+        let cli = CliOptions {
+            data_dir: Some(root.join("cli")),
+            portable: true,
+        };
+        let loc = resolve(&cli, Some("/env"), Some(&exe_dir), None, &default);
+        assert_eq!(loc.source, "cli");
+
+        let none = CliOptions::default();
+        let loc = resolve(&none, Some("/env"), Some(&exe_dir), None, &default);
+        assert_eq!((loc.dir.as_str(), loc.source.as_str()), ("/env", "env"));
+
+        let portable = CliOptions {
+            data_dir: None,
+            portable: true,
+        };
+        let loc = resolve(&portable, None, Some(&exe_dir), Some(&remembered), &default);
+        assert_eq!(loc.source, "portable");
+        assert!(loc.dir.ends_with(PORTABLE_DIR_NAME));
+
+        // An existing blinky-data directory keeps later launches portable
+        fs::create_dir_all(exe_dir.join(PORTABLE_DIR_NAME)).unwrap();
+        let loc = resolve(&none, None, Some(&exe_dir), None, &default);
+        assert_eq!(loc.source, "portable");
+        fs::remove_dir_all(exe_dir.join(PORTABLE_DIR_NAME)).unwrap();
+
+        let loc = resolve(&none, None, Some(&exe_dir), Some(&remembered), &default);
+        assert_eq!(loc.source, "remembered");
+
+        // A remembered location that isn't there falls back to the default
+        let gone = root.join("unmounted");
+        let loc = resolve(&none, None, Some(&exe_dir), Some(&gone), &default);
+        assert_eq!(loc.source, "default");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_remember_roundtrip() {
+        let default = temp_dir("remember");
+        assert_eq!(read_remembered(&default), None);
+        remember(&default, Path::new("/mnt/sync/blinky")).unwrap();
+        assert_eq!(
+            read_remembered(&default),
+            Some(PathBuf::from("/mnt/sync/blinky"))
+        );
+        remember(&default, &default).unwrap();
+        assert_eq!(read_remembered(&default), None);
+        fs::remove_dir_all(&default).unwrap();
+    }
+
+    /// A database with a row still in its WAL, and the connection holding it.
+    fn wal_database(dir: &Path) -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open(dir.join("blinky.db")).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode=WAL;
+             PRAGMA wal_autocheckpoint=0;
+             CREATE TABLE t (x TEXT);
+             INSERT INTO t VALUES ('kept');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_migrate_moves_data_once() {
+        let root = temp_dir("migrate");
+        let old = root.join("old");
+        let new = root.join("new");
+        fs::create_dir_all(old.join("backups")).unwrap();
+        let conn = wal_database(&old);
+        assert!(old.join("blinky.db-wal").exists());
+        fs::write(old.join("backups").join("daily.db"), b"snap").unwrap();
+        fs::write(old.join("unrelated.txt"), b"keep").unwrap();
+
+        assert!(migrate(&old, &new).unwrap());
+        drop(conn);
+        let moved = rusqlite::Connection::open(new.join("blinky.db")).unwrap();
+        let x: String = moved
+            .query_row("SELECT x FROM t", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(x, "kept");
+        assert!(new.join("backups").join("daily.db").exists());
+        assert!(!old.join("blinky.db").exists());
+        assert!(!old.join("backups").exists());
+        assert!(old.join("unrelated.txt").exists());
+
+        // Never overwrites an existing database at the destination
+        fs::write(old.join("blinky.db"), b"other").unwrap();
+        assert!(!migrate(&old, &new).unwrap());
+        assert_ne!(fs::read(new.join("blinky.db")).unwrap(), b"other");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_failed_migrate_leaves_the_old_directory_whole() {
+        let root = temp_dir("migrate-fail");
+        let old = root.join("old");
+        let new = root.join("new");
+        fs::create_dir_all(old.join("backups")).unwrap();
+        drop(wal_database(&old));
+        fs::write(old.join("backups").join("daily.db"), b"snap").unwrap();
+        // A directory where a backup file should go makes that copy fail
+        fs::create_dir_all(new.join("backups").join("daily.db")).unwrap();
+
+        assert!(migrate(&old, &new).is_err());
+        assert!(old.join("blinky.db").exists());
+        assert!(old.join("backups").join("daily.db").is_file());
+        // The copied database is removed; what was already there stays
+        assert!(!new.join("blinky.db").exists());
+        assert!(new.join("backups").join("daily.db").is_dir());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,22 +1,43 @@
-use crate::state::{BreakRecord, DailyStats, UserSettings};
+use crate::export::{self, CsvExportOptions};
+use crate::state::{
+    BreakDeferral, BreakOutcome, BreakRecord, BreakSource, DailyStats, DeferralSummary,
+    SettingsSnapshot, UserSettings,
+};
 use chrono::{NaiveDate, Utc};
-use rusqlite::{params, Connection, Result as SqlResult};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result as SqlResult};
 use std::path::PathBuf;
-use std::sync::Mutex;
 
 const MIGRATION_001_SQL: &str = include_str!("../migrations/001_initial.sql");
 const MIGRATION_002_SQL: &str = include_str!("../migrations/002_onboarding.sql");
-
-/// Returns the OS-specific path for the blinky database directory.
-pub fn get_db_dir() -> PathBuf {
-    let base = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
-    base.join("com.blinky.app")
-}
-
-/// Opens (or creates) the database, enables WAL mode, runs migrations.
-/// Returns a Mutex-wrapped connection suitable for Tauri state management.
-pub fn init_db(db_path: &str) -> SqlResult<Mutex<Connection>> {
+const MIGRATION_003_SQL: &str = include_str!("../migrations/003_idle_resume.sql");
+const MIGRATION_004_SQL: &str = include_str!("../migrations/004_media_activity.sql");
+const MIGRATION_005_SQL: &str = include_str!("../migrations/005_meeting_detection.sql");
+const MIGRATION_006_SQL: &str = include_str!("../migrations/006_backups.sql");
+const MIGRATION_007_SQL: &str = include_str!("../migrations/007_retention.sql");
+const MIGRATION_008_SQL: &str = include_str!("../migrations/008_break_outcome.sql");
+const MIGRATION_009_SQL: &str = include_str!("../migrations/009_phase_log.sql");
+const MIGRATION_010_SQL: &str = include_str!("../migrations/010_history_indexes.sql");
+const MIGRATION_011_SQL: &str = include_str!("../migrations/011_break_edits.sql");
+const MIGRATION_012_SQL: &str = include_str!("../migrations/012_reports.sql");
+const MIGRATION_013_SQL: &str = include_str!("../migrations/013_streaks.sql");
+const MIGRATION_014_SQL: &str = include_str!("../migrations/014_settings_history.sql");
+const MIGRATION_015_SQL: &str = include_str!("../migrations/015_achievements.sql");
+const MIGRATION_016_SQL: &str = include_str!("../migrations/016_aggregates.sql");
+const MIGRATION_017_SQL: &str = include_str!("../migrations/017_skip_reasons.sql");
+const MIGRATION_018_SQL: &str = include_str!("../migrations/018_daily_summary.sql");
+const MIGRATION_019_SQL: &str = include_str!("../migrations/019_idle_long_away.sql");
+
+/// How long to wait on a lock held by another connection before giving up.
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Opens (or creates) the database, verifies its integrity, enables WAL mode,
+/// and runs migrations. Corruption surfaces as `SQLITE_CORRUPT` so the caller
+/// can decide how to recover; see `recovery::open_database`.
+pub fn init_db(db_path: &str) -> SqlResult<Connection> {
     let conn = Connection::open(db_path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+
+    check_integrity(&conn)?;
 
     // Enable WAL for concurrent read/write without blocking
     conn.execute_batch("PRAGMA journal_mode=WAL;")?;
@@ -24,7 +45,35 @@ pub fn init_db(db_path: &str) -> SqlResult<Mutex<Connection>> {
 
     run_migrations(&conn)?;
 
-    Ok(Mutex::new(conn))
+    Ok(conn)
+}
+
+/// Open a read-only connection for queries alongside the writer. WAL lets it
+/// read while the writer commits.
+pub fn open_reader(db_path: &str) -> SqlResult<Connection> {
+    let conn = Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(conn)
+}
+
+/// Run `PRAGMA integrity_check`, turning any reported problem into an error.
+pub fn check_integrity(conn: &Connection) -> SqlResult<()> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let problems: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<SqlResult<_>>()?;
+    if problems.len() == 1 && problems[0] == "ok" {
+        return Ok(());
+    }
+    Err(rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+        Some(format!("integrity check failed: {}", problems.join("; "))),
+    ))
 }
 
 /// Same as init_db but takes a Connection directly (for testing with :memory:).
@@ -36,52 +85,230 @@ pub fn init_db_conn(conn: &Connection) -> SqlResult<()> {
     Ok(())
 }
 
-fn run_migrations(conn: &Connection) -> SqlResult<()> {
-    // Check if _migrations table exists
-    let has_migrations_table: bool = conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='_migrations'")?
-        .exists([])?;
+/// A schema migration. Once applied, `PRAGMA user_version` equals `version`.
+struct Migration {
+    version: u32,
+    name: &'static str,
+    sql: &'static str,
+    /// Data fix-ups that run after `sql`, inside the same transaction.
+    post: Option<fn(&Connection) -> SqlResult<()>>,
+}
 
-    let has_001 = if has_migrations_table {
-        conn.prepare("SELECT id FROM _migrations WHERE name = '001_initial'")?
-            .exists([])?
-    } else {
-        false
-    };
+/// Every migration, in order. Append new ones here; never edit or reorder old ones.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "001_initial",
+        sql: MIGRATION_001_SQL,
+        post: None,
+    },
+    Migration {
+        version: 2,
+        name: "002_onboarding",
+        sql: MIGRATION_002_SQL,
+        post: Some(auto_complete_onboarding),
+    },
+    Migration {
+        version: 3,
+        name: "003_idle_resume",
+        sql: MIGRATION_003_SQL,
+        post: None,
+    },
+    Migration {
+        version: 4,
+        name: "004_media_activity",
+        sql: MIGRATION_004_SQL,
+        post: None,
+    },
+    Migration {
+        version: 5,
+        name: "005_meeting_detection",
+        sql: MIGRATION_005_SQL,
+        post: None,
+    },
+    Migration {
+        version: 6,
+        name: "006_backups",
+        sql: MIGRATION_006_SQL,
+        post: None,
+    },
+    Migration {
+        version: 7,
+        name: "007_retention",
+        sql: MIGRATION_007_SQL,
+        post: None,
+    },
+    Migration {
+        version: 8,
+        name: "008_break_outcome",
+        sql: MIGRATION_008_SQL,
+        post: None,
+    },
+    Migration {
+        version: 9,
+        name: "009_phase_log",
+        sql: MIGRATION_009_SQL,
+        post: None,
+    },
+    Migration {
+        version: 10,
+        name: "010_history_indexes",
+        sql: MIGRATION_010_SQL,
+        post: None,
+    },
+    Migration {
+        version: 11,
+        name: "011_break_edits",
+        sql: MIGRATION_011_SQL,
+        post: None,
+    },
+    Migration {
+        version: 12,
+        name: "012_reports",
+        sql: MIGRATION_012_SQL,
+        post: None,
+    },
+    Migration {
+        version: 13,
+        name: "013_streaks",
+        sql: MIGRATION_013_SQL,
+        post: None,
+    },
+    Migration {
+        version: 14,
+        name: "014_settings_history",
+        sql: MIGRATION_014_SQL,
+        post: None,
+    },
+    Migration {
+        version: 15,
+        name: "015_achievements",
+        sql: MIGRATION_015_SQL,
+        post: None,
+    },
+    Migration {
+        version: 16,
+        name: "016_aggregates",
+        sql: MIGRATION_016_SQL,
+        post: None,
+    },
+    Migration {
+        version: 17,
+        name: "017_skip_reasons",
+        sql: MIGRATION_017_SQL,
+        post: None,
+    },
+    Migration {
+        version: 18,
+        name: "018_daily_summary",
+        sql: MIGRATION_018_SQL,
+        post: None,
+    },
+    Migration {
+        version: 19,
+        name: "019_idle_long_away",
+        sql: MIGRATION_019_SQL,
+        post: None,
+    },
+];
+
+pub fn run_migrations(conn: &Connection) -> SqlResult<()> {
+    apply_migrations(conn, MIGRATIONS)
+}
 
-    if !has_001 {
-        conn.execute_batch(MIGRATION_001_SQL)?;
-        conn.execute(
-            "INSERT INTO _migrations (name) VALUES (?1)",
-            params!["001_initial"],
-        )?;
+/// Bring the schema up to the last entry of `migrations`. Each migration runs in
+/// its own transaction, so a failure leaves the DB at the previous version.
+fn apply_migrations(conn: &Connection, migrations: &[Migration]) -> SqlResult<()> {
+    let latest = migrations.last().map_or(0, |m| m.version);
+
+    let mut current = schema_version(conn)?;
+    if current == 0 {
+        // Databases from before user_version tracking only have the _migrations log
+        current = legacy_schema_version(conn, migrations)?;
+        if current > 0 {
+            conn.pragma_update(None, "user_version", current)?;
+        }
     }
 
-    // Migration 002: onboarding columns
-    let has_002 = conn
-        .prepare("SELECT id FROM _migrations WHERE name = '002_onboarding'")?
-        .exists([])?;
+    if current > latest {
+        return Err(schema_too_new(current, latest));
+    }
 
-    if !has_002 {
-        conn.execute_batch(MIGRATION_002_SQL)?;
-        conn.execute(
+    for m in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(m.sql)?;
+        if let Some(post) = m.post {
+            post(&tx)?;
+        }
+        tx.execute(
             "INSERT INTO _migrations (name) VALUES (?1)",
-            params!["002_onboarding"],
+            params![m.name],
         )?;
+        tx.pragma_update(None, "user_version", m.version)?;
+        tx.commit()?;
+    }
 
-        // Auto-complete onboarding for existing users who already have break records.
-        let has_breaks: bool = conn
-            .prepare("SELECT id FROM break_records LIMIT 1")?
-            .exists([])?;
-        if has_breaks {
-            let now_ms = chrono::Utc::now().timestamp_millis();
-            conn.execute(
-                "UPDATE settings SET onboarding_completed = 1, first_break_completed = 1, onboarding_completed_at = ?1 WHERE id = 1",
-                params![now_ms],
-            )?;
+    Ok(())
+}
+
+/// The schema version recorded in the database header.
+pub fn schema_version(conn: &Connection) -> SqlResult<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Highest version whose name appears in the legacy `_migrations` log,
+/// counting only an unbroken run from the first migration.
+fn legacy_schema_version(conn: &Connection, migrations: &[Migration]) -> SqlResult<u32> {
+    let has_migrations_table: bool = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='_migrations'")?
+        .exists([])?;
+    if !has_migrations_table {
+        return Ok(0);
+    }
+
+    let mut version = 0;
+    for m in migrations {
+        let applied = conn
+            .prepare("SELECT id FROM _migrations WHERE name = ?1")?
+            .exists(params![m.name])?;
+        if !applied {
+            break;
         }
+        version = m.version;
     }
+    Ok(version)
+}
+
+const SCHEMA_TOO_NEW_PREFIX: &str = "database schema version";
+
+fn schema_too_new(found: u32, supported: u32) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+        Some(format!(
+            "{} {} is newer than this version of Blinky supports ({}); \
+             please update Blinky",
+            SCHEMA_TOO_NEW_PREFIX, found, supported
+        )),
+    )
+}
+
+/// Whether `e` is the refusal to open a database written by a newer Blinky.
+pub fn is_schema_too_new(e: &rusqlite::Error) -> bool {
+    matches!(e, rusqlite::Error::SqliteFailure(_, Some(msg)) if msg.starts_with(SCHEMA_TOO_NEW_PREFIX))
+}
 
+/// Auto-complete onboarding for existing users who already have break records.
+fn auto_complete_onboarding(conn: &Connection) -> SqlResult<()> {
+    let has_breaks: bool = conn
+        .prepare("SELECT id FROM break_records LIMIT 1")?
+        .exists([])?;
+    if has_breaks {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "UPDATE settings SET onboarding_completed = 1, first_break_completed = 1, onboarding_completed_at = ?1 WHERE id = 1",
+            params![now_ms],
+        )?;
+    }
     Ok(())
 }
 
@@ -99,51 +326,195 @@ pub fn insert_break_record(
     Ok(conn.last_insert_rowid())
 }
 
-/// Update a break record when the break ends (completed or skipped).
-pub fn update_break_completion(
+/// Finalize a break record when the break ends. `source` is None when the
+/// break ended on its own.
+pub fn finish_break_record(
     conn: &Connection,
     id: i64,
     duration_seconds: u32,
-    completed: bool,
-    skipped: bool,
+    outcome: BreakOutcome,
+    source: Option<BreakSource>,
+    skip_reason: Option<&str>,
 ) -> SqlResult<()> {
     conn.execute(
-        "UPDATE break_records SET duration_seconds = ?1, completed = ?2, skipped = ?3 WHERE id = ?4",
-        params![duration_seconds, completed as i32, skipped as i32, id],
+        "UPDATE break_records
+         SET duration_seconds = ?1, completed = ?2, skipped = ?3,
+             ended_at = ?4, outcome = ?5, source = ?6, skip_reason = ?7
+         WHERE id = ?8",
+        params![
+            duration_seconds,
+            (outcome == BreakOutcome::Completed) as i32,
+            (outcome == BreakOutcome::Skipped) as i32,
+            Utc::now().timestamp_millis(),
+            outcome.as_str(),
+            source.map(BreakSource::as_str),
+            skip_reason,
+            id
+        ],
     )?;
     Ok(())
 }
 
-/// Get break records, paginated, newest first.
-pub fn get_break_records(
+/// The still-open break record that started at `started_at`, if any. Lets
+/// queued writes refer to a break before its row id is known.
+pub fn open_break_record_id(conn: &Connection, started_at: u64) -> SqlResult<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM break_records WHERE started_at = ?1 AND outcome IS NULL
+         ORDER BY id DESC LIMIT 1",
+        params![started_at as i64],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Close out breaks left in progress by a previous session (crash or quit
+/// mid-break). Call once at startup, before the timer runs.
+pub fn mark_interrupted_breaks(conn: &Connection) -> SqlResult<usize> {
+    conn.execute(
+        "UPDATE break_records
+         SET outcome = 'interrupted', ended_at = started_at + duration_seconds * 1000
+         WHERE outcome IS NULL",
+        [],
+    )
+}
+
+/// Columns read by `break_record_from_row`, in order.
+pub const BREAK_RECORD_COLUMNS: &str = "id, started_at, duration_seconds, completed, skipped, \
+     preceding_work_seconds, ended_at, outcome, source, note, skip_reason";
+
+pub fn break_record_from_row(row: &rusqlite::Row) -> SqlResult<BreakRecord> {
+    Ok(BreakRecord {
+        id: row.get(0)?,
+        started_at: row.get::<_, i64>(1)? as u64,
+        duration_seconds: row.get::<_, i32>(2)? as u32,
+        completed: row.get::<_, i32>(3)? != 0,
+        skipped: row.get::<_, i32>(4)? != 0,
+        preceding_work_seconds: row.get::<_, i32>(5)? as u32,
+        ended_at: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
+        outcome: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|o| BreakOutcome::parse(&o)),
+        source: row
+            .get::<_, Option<String>>(8)?
+            .and_then(|s| BreakSource::parse(&s)),
+        note: row.get(9)?,
+        skip_reason: row.get(10)?,
+    })
+}
+
+/// Record the start of a break deferral. Returns the row ID.
+pub fn insert_break_deferral(conn: &Connection, started_at: u64, reason: &str) -> SqlResult<i64> {
+    conn.execute(
+        "INSERT INTO break_deferrals (started_at, reason) VALUES (?1, ?2)",
+        params![started_at as i64, reason],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// The deferral that started at `started_at`, if any.
+pub fn break_deferral_id(conn: &Connection, started_at: u64) -> SqlResult<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM break_deferrals WHERE started_at = ?1 ORDER BY id DESC LIMIT 1",
+        params![started_at as i64],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Record how long a deferral held the break back once the break finally starts.
+pub fn finish_break_deferral(conn: &Connection, id: i64, deferred_seconds: u32) -> SqlResult<()> {
+    conn.execute(
+        "UPDATE break_deferrals SET deferred_seconds = ?1 WHERE id = ?2",
+        params![deferred_seconds, id],
+    )?;
+    Ok(())
+}
+
+/// Summarize deferrals started between `from` and `to` (inclusive, YYYY-MM-DD), by reason.
+pub fn get_deferral_summary(
     conn: &Connection,
-    limit: u32,
-    offset: u32,
-) -> SqlResult<Vec<BreakRecord>> {
+    from: &str,
+    to: &str,
+) -> SqlResult<Vec<DeferralSummary>> {
+    let from_date = NaiveDate::parse_from_str(from, "%Y-%m-%d")
+        .map_err(|e| rusqlite::Error::InvalidParameterName(format!("bad from date: {}", e)))?;
+    let to_date = NaiveDate::parse_from_str(to, "%Y-%m-%d")
+        .map_err(|e| rusqlite::Error::InvalidParameterName(format!("bad to date: {}", e)))?;
+
+    let start = from_date
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis();
+    let end = to_date
+        .and_hms_opt(23, 59, 59)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis()
+        + 999;
+
     let mut stmt = conn.prepare(
-        "SELECT id, started_at, duration_seconds, completed, skipped, preceding_work_seconds
-         FROM break_records ORDER BY started_at DESC LIMIT ?1 OFFSET ?2",
+        "SELECT reason, COUNT(*), COALESCE(SUM(deferred_seconds), 0)
+         FROM break_deferrals
+         WHERE started_at >= ?1 AND started_at <= ?2
+         GROUP BY reason
+         ORDER BY COUNT(*) DESC",
     )?;
-    let rows = stmt.query_map(params![limit, offset], |row| {
-        Ok(BreakRecord {
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok(DeferralSummary {
+            reason: row.get(0)?,
+            deferrals: row.get::<_, i64>(1)? as u32,
+            deferred_seconds: row.get::<_, i64>(2)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+/// All break deferrals, oldest first.
+pub fn get_all_break_deferrals(conn: &Connection) -> SqlResult<Vec<BreakDeferral>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, started_at, reason, deferred_seconds
+         FROM break_deferrals ORDER BY started_at ASC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(BreakDeferral {
             id: row.get(0)?,
             started_at: row.get::<_, i64>(1)? as u64,
-            duration_seconds: row.get::<_, i32>(2)? as u32,
-            completed: row.get::<_, i32>(3)? != 0,
-            skipped: row.get::<_, i32>(4)? != 0,
-            preceding_work_seconds: row.get::<_, i32>(5)? as u32,
+            reason: row.get(2)?,
+            deferred_seconds: row.get::<_, i32>(3)? as u32,
         })
     })?;
     rows.collect()
 }
 
+/// Get break records, paginated, newest first.
+pub fn get_break_records(
+    conn: &Connection,
+    limit: u32,
+    offset: u32,
+) -> SqlResult<Vec<BreakRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM break_records ORDER BY started_at DESC LIMIT ?1 OFFSET ?2",
+        BREAK_RECORD_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![limit, offset], break_record_from_row)?;
+    rows.collect()
+}
+
 /// Load settings from the single-row settings table.
 pub fn load_settings(conn: &Connection) -> SqlResult<UserSettings> {
     conn.query_row(
         "SELECT work_interval_minutes, break_duration_seconds, sound_enabled, sound_volume,
                 notification_enabled, overlay_enabled, launch_at_login, daily_goal,
                 idle_pause_minutes, theme,
-                onboarding_completed, onboarding_completed_at, tooltips_seen, first_break_completed
+                onboarding_completed, onboarding_completed_at, tooltips_seen, first_break_completed,
+                idle_resume_policy, media_activity_enabled, media_player_allowlist,
+                meeting_detection_enabled, meeting_max_defer_minutes,
+                backup_daily_keep, backup_weekly_keep,
+                retention_amount, retention_unit,
+                report_auto_enabled, report_format, report_dir, workdays,
+                skip_reasons,
+                daily_summary_enabled, idle_long_away_minutes
          FROM settings WHERE id = 1",
         [],
         |row| {
@@ -162,6 +533,22 @@ pub fn load_settings(conn: &Connection) -> SqlResult<UserSettings> {
                 onboarding_completed_at: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
                 tooltips_seen: row.get(12)?,
                 first_break_completed: row.get::<_, i32>(13)? != 0,
+                idle_resume_policy: row.get(14)?,
+                media_activity_enabled: row.get::<_, i32>(15)? != 0,
+                media_player_allowlist: row.get(16)?,
+                meeting_detection_enabled: row.get::<_, i32>(17)? != 0,
+                meeting_max_defer_minutes: row.get::<_, i32>(18)? as u32,
+                backup_daily_keep: row.get::<_, i32>(19)? as u32,
+                backup_weekly_keep: row.get::<_, i32>(20)? as u32,
+                retention_amount: row.get::<_, i32>(21)? as u32,
+                retention_unit: row.get(22)?,
+                report_auto_enabled: row.get::<_, i32>(23)? != 0,
+                report_format: row.get(24)?,
+                report_dir: row.get(25)?,
+                workdays: row.get(26)?,
+                skip_reasons: row.get(27)?,
+                daily_summary_enabled: row.get::<_, i32>(28)? != 0,
+                idle_long_away_minutes: row.get::<_, i32>(29)? as u32,
             })
         },
     )
@@ -169,6 +556,8 @@ pub fn load_settings(conn: &Connection) -> SqlResult<UserSettings> {
 
 /// Save settings to the single-row settings table.
 pub fn save_settings(conn: &Connection, s: &UserSettings) -> SqlResult<()> {
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    record_settings_snapshot(conn, s, &today)?;
     conn.execute(
         "UPDATE settings SET
             work_interval_minutes = ?1,
@@ -184,7 +573,23 @@ pub fn save_settings(conn: &Connection, s: &UserSettings) -> SqlResult<()> {
             onboarding_completed = ?11,
             onboarding_completed_at = ?12,
             tooltips_seen = ?13,
-            first_break_completed = ?14
+            first_break_completed = ?14,
+            idle_resume_policy = ?15,
+            media_activity_enabled = ?16,
+            media_player_allowlist = ?17,
+            meeting_detection_enabled = ?18,
+            meeting_max_defer_minutes = ?19,
+            backup_daily_keep = ?20,
+            backup_weekly_keep = ?21,
+            retention_amount = ?22,
+            retention_unit = ?23,
+            report_auto_enabled = ?24,
+            report_format = ?25,
+            report_dir = ?26,
+            workdays = ?27,
+            skip_reasons = ?28,
+            daily_summary_enabled = ?29,
+            idle_long_away_minutes = ?30
          WHERE id = 1",
         params![
             s.work_interval_minutes as i32,
@@ -201,14 +606,146 @@ pub fn save_settings(conn: &Connection, s: &UserSettings) -> SqlResult<()> {
             s.onboarding_completed_at.map(|v| v as i64),
             s.tooltips_seen,
             s.first_break_completed as i32,
+            s.idle_resume_policy,
+            s.media_activity_enabled as i32,
+            s.media_player_allowlist,
+            s.meeting_detection_enabled as i32,
+            s.meeting_max_defer_minutes as i32,
+            s.backup_daily_keep as i32,
+            s.backup_weekly_keep as i32,
+            s.retention_amount as i32,
+            s.retention_unit,
+            s.report_auto_enabled as i32,
+            s.report_format,
+            s.report_dir,
+            s.workdays,
+            s.skip_reasons,
+            s.daily_summary_enabled as i32,
+            s.idle_long_away_minutes as i32,
         ],
     )?;
     Ok(())
 }
 
+/// Effective date of the snapshot taken on the first change, covering every
+/// day before it.
+const SETTINGS_HISTORY_START: &str = "1970-01-01";
+
+/// Remember the goal, interval and break length `s` puts in effect from `date`
+/// on. Call before the settings row is overwritten: the first snapshot also
+/// records the stored values for all earlier days.
+fn record_settings_snapshot(conn: &Connection, s: &UserSettings, date: &str) -> SqlResult<()> {
+    conn.execute(
+        "INSERT INTO settings_history
+            (effective_date, daily_goal, work_interval_minutes, break_duration_seconds)
+         SELECT ?1, daily_goal, work_interval_minutes, break_duration_seconds
+         FROM settings
+         WHERE id = 1 AND NOT EXISTS (SELECT 1 FROM settings_history)",
+        params![SETTINGS_HISTORY_START],
+    )?;
+
+    let in_effect = settings_in_effect(conn, date)?;
+    let new = (
+        s.daily_goal,
+        s.work_interval_minutes,
+        s.break_duration_seconds,
+    );
+    if (
+        in_effect.daily_goal,
+        in_effect.work_interval_minutes,
+        in_effect.break_duration_seconds,
+    ) == new
+    {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO settings_history
+            (effective_date, daily_goal, work_interval_minutes, break_duration_seconds)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(effective_date) DO UPDATE SET
+            daily_goal = excluded.daily_goal,
+            work_interval_minutes = excluded.work_interval_minutes,
+            break_duration_seconds = excluded.break_duration_seconds",
+        params![date, new.0 as i32, new.1 as i32, new.2 as i32],
+    )?;
+    Ok(())
+}
+
+/// The goal, interval and break length in effect on `date` (YYYY-MM-DD): the
+/// latest recorded change on or before it, or the stored settings if none.
+pub fn settings_in_effect(conn: &Connection, date: &str) -> SqlResult<SettingsSnapshot> {
+    let snapshot = conn
+        .query_row(
+            "SELECT effective_date, daily_goal, work_interval_minutes, break_duration_seconds
+             FROM settings_history WHERE effective_date <= ?1
+             ORDER BY effective_date DESC LIMIT 1",
+            params![date],
+            snapshot_from_row,
+        )
+        .optional()?;
+    if let Some(snapshot) = snapshot {
+        return Ok(snapshot);
+    }
+    let settings = load_settings(conn)?;
+    Ok(SettingsSnapshot {
+        effective_date: SETTINGS_HISTORY_START.to_string(),
+        daily_goal: settings.daily_goal,
+        work_interval_minutes: settings.work_interval_minutes,
+        break_duration_seconds: settings.break_duration_seconds,
+    })
+}
+
+fn snapshot_from_row(row: &rusqlite::Row) -> SqlResult<SettingsSnapshot> {
+    Ok(SettingsSnapshot {
+        effective_date: row.get(0)?,
+        daily_goal: row.get::<_, i32>(1)? as u32,
+        work_interval_minutes: row.get::<_, i32>(2)? as u32,
+        break_duration_seconds: row.get::<_, i32>(3)? as u32,
+    })
+}
+
+/// Every recorded change to the goal, interval and break length, oldest first.
+pub fn get_settings_history(conn: &Connection) -> SqlResult<Vec<SettingsSnapshot>> {
+    let mut stmt = conn.prepare(
+        "SELECT effective_date, daily_goal, work_interval_minutes, break_duration_seconds
+         FROM settings_history ORDER BY effective_date ASC",
+    )?;
+    let rows = stmt.query_map([], snapshot_from_row)?;
+    rows.collect()
+}
+
 /// Recompute daily stats for a given date (YYYY-MM-DD) from break_records.
 /// Upserts the result into daily_stats_cache and returns the computed stats.
 pub fn recompute_daily_stats(conn: &Connection, date: &str) -> SqlResult<DailyStats> {
+    let stats = compute_daily_stats(conn, date)?;
+
+    // Upsert into cache
+    conn.execute(
+        "INSERT INTO daily_stats_cache (date, breaks_completed, breaks_skipped, total_rest_seconds, longest_streak, compliance_rate)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(date) DO UPDATE SET
+            breaks_completed = excluded.breaks_completed,
+            breaks_skipped = excluded.breaks_skipped,
+            total_rest_seconds = excluded.total_rest_seconds,
+            longest_streak = excluded.longest_streak,
+            compliance_rate = excluded.compliance_rate",
+        params![
+            stats.date,
+            stats.breaks_completed as i32,
+            stats.breaks_skipped as i32,
+            stats.total_rest_seconds as i32,
+            stats.longest_streak as i32,
+            stats.compliance_rate,
+        ],
+    )?;
+
+    Ok(stats)
+}
+
+/// Compute a day's stats from the raw records without touching the cache, so
+/// it works on the read-only connection.
+pub fn compute_daily_stats(conn: &Connection, date: &str) -> SqlResult<DailyStats> {
     let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")
         .map_err(|e| rusqlite::Error::InvalidParameterName(format!("bad date: {}", e)))?;
 
@@ -224,20 +761,25 @@ pub fn recompute_daily_stats(conn: &Connection, date: &str) -> SqlResult<DailySt
         .timestamp_millis() as u64
         + 999; // include the last millisecond
 
+    // Days whose raw records were pruned by retention only exist in the cache
+    if end_of_day < pruned_before(conn)? {
+        return Ok(get_daily_stats_range(conn, date, date)?.remove(0));
+    }
+
     // Fetch all break records for this day
     let mut stmt = conn.prepare(
-        "SELECT completed, skipped, duration_seconds
+        "SELECT outcome, duration_seconds
          FROM break_records
          WHERE started_at >= ?1 AND started_at <= ?2
          ORDER BY started_at ASC",
     )?;
 
-    let records: Vec<(bool, bool, u32)> = stmt
+    let records: Vec<(Option<BreakOutcome>, u32)> = stmt
         .query_map(params![start_of_day as i64, end_of_day as i64], |row| {
             Ok((
-                row.get::<_, i32>(0)? != 0,
-                row.get::<_, i32>(1)? != 0,
-                row.get::<_, i32>(2)? as u32,
+                row.get::<_, Option<String>>(0)?
+                    .and_then(|o| BreakOutcome::parse(&o)),
+                row.get::<_, i32>(1)? as u32,
             ))
         })?
         .collect::<SqlResult<Vec<_>>>()?;
@@ -248,20 +790,23 @@ pub fn recompute_daily_stats(conn: &Connection, date: &str) -> SqlResult<DailySt
     let mut longest_streak: u32 = 0;
     let mut current_streak: u32 = 0;
 
-    for (completed, skipped, duration) in &records {
-        if *completed && !*skipped {
-            breaks_completed += 1;
-            total_rest_seconds += duration;
-            current_streak += 1;
-            if current_streak > longest_streak {
-                longest_streak = current_streak;
+    for (outcome, duration) in &records {
+        match outcome {
+            Some(BreakOutcome::Completed) => {
+                breaks_completed += 1;
+                total_rest_seconds += duration;
+                current_streak += 1;
+                if current_streak > longest_streak {
+                    longest_streak = current_streak;
+                }
             }
-        } else if *skipped {
-            breaks_skipped += 1;
-            current_streak = 0;
-        } else {
-            // in-progress or incomplete — don't count either way
-            current_streak = 0;
+            Some(BreakOutcome::Skipped) => {
+                // Only a deliberate skip counts against the user
+                breaks_skipped += 1;
+                current_streak = 0;
+            }
+            // Resets, interruptions, demos, and in-progress breaks are neutral
+            _ => {}
         }
     }
 
@@ -272,39 +817,17 @@ pub fn recompute_daily_stats(conn: &Connection, date: &str) -> SqlResult<DailySt
         breaks_completed as f64 / total as f64
     };
 
-    let stats = DailyStats {
+    Ok(DailyStats {
         date: date.to_string(),
         breaks_completed,
         breaks_skipped,
         total_rest_seconds,
         longest_streak,
         compliance_rate,
-    };
-
-    // Upsert into cache
-    conn.execute(
-        "INSERT INTO daily_stats_cache (date, breaks_completed, breaks_skipped, total_rest_seconds, longest_streak, compliance_rate)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-         ON CONFLICT(date) DO UPDATE SET
-            breaks_completed = excluded.breaks_completed,
-            breaks_skipped = excluded.breaks_skipped,
-            total_rest_seconds = excluded.total_rest_seconds,
-            longest_streak = excluded.longest_streak,
-            compliance_rate = excluded.compliance_rate",
-        params![
-            stats.date,
-            stats.breaks_completed as i32,
-            stats.breaks_skipped as i32,
-            stats.total_rest_seconds as i32,
-            stats.longest_streak as i32,
-            stats.compliance_rate,
-        ],
-    )?;
-
-    Ok(stats)
+    })
 }
 
-/// Get daily stats for a date range. Always recomputes today's entry.
+/// Get daily stats for a date range. Today's entry is always computed live.
 /// Returns entries for each day in the range, zero-filled for missing days.
 pub fn get_daily_stats_range(
     conn: &Connection,
@@ -318,11 +841,13 @@ pub fn get_daily_stats_range(
 
     let today = Utc::now().format("%Y-%m-%d").to_string();
 
-    // Recompute today if it falls within the range
+    // Today is still in progress, so the cache may be behind
     let today_date = NaiveDate::parse_from_str(&today, "%Y-%m-%d").unwrap();
-    if today_date >= from_date && today_date <= to_date {
-        let _ = recompute_daily_stats(conn, &today);
-    }
+    let today_stats = if today_date >= from_date && today_date <= to_date {
+        Some(compute_daily_stats(conn, &today)?)
+    } else {
+        None
+    };
 
     // Load all cached entries in range
     let mut stmt = conn.prepare(
@@ -346,8 +871,11 @@ pub fn get_daily_stats_range(
         .collect::<SqlResult<Vec<_>>>()?;
 
     // Build a map for O(1) lookup
-    let cache_map: std::collections::HashMap<String, DailyStats> =
+    let mut cache_map: std::collections::HashMap<String, DailyStats> =
         cached.into_iter().map(|s| (s.date.clone(), s)).collect();
+    if let Some(stats) = today_stats {
+        cache_map.insert(today, stats);
+    }
 
     // Fill in every day in the range
     let mut result = Vec::new();
@@ -390,67 +918,112 @@ pub fn count_breaks_today(conn: &Connection) -> SqlResult<u32> {
     Ok(count as u32)
 }
 
+/// Start of the pruned range (ms). Raw break records before this were rolled up
+/// into daily_stats_cache and deleted; 0 if nothing has been pruned.
+pub fn pruned_before(conn: &Connection) -> SqlResult<u64> {
+    conn.query_row(
+        "SELECT pruned_before FROM retention_state WHERE id = 1",
+        [],
+        |row| Ok(row.get::<_, i64>(0)? as u64),
+    )
+}
+
 /// Delete all break records, clear cache, reset settings to defaults.
 pub fn clear_all_data(conn: &Connection) -> SqlResult<()> {
     conn.execute_batch(
         "DELETE FROM break_records;
+         DELETE FROM break_deferrals;
+         DELETE FROM break_record_edits;
          DELETE FROM daily_stats_cache;
+         DELETE FROM period_stats;
+         DELETE FROM phase_log WHERE ended_at IS NOT NULL;
+         UPDATE retention_state SET pruned_before = 0, last_run_at = NULL;
+         DELETE FROM settings_history;
+         DELETE FROM achievements;
+         UPDATE aggregates
+            SET settled_through = NULL, lifetime_breaks = 0, lifetime_rest_seconds = 0,
+                current_run = 0, best_run = 0;
+         DELETE FROM streak_freezes;
+         UPDATE streak_state
+            SET processed_through = NULL, current_run = 0, freezes_available = 0;
+         UPDATE daily_summary_state SET last_summarized = NULL;
          DELETE FROM settings;
          INSERT OR IGNORE INTO settings (id) VALUES (1);",
     )?;
     Ok(())
 }
 
-/// Export all break records as CSV to the user's Downloads folder.
-/// Returns the file path of the created CSV.
-pub fn export_to_csv(conn: &Connection) -> SqlResult<String> {
-    let downloads = dirs::download_dir().unwrap_or_else(|| {
+/// All break records, oldest first.
+pub fn get_all_break_records(conn: &Connection) -> SqlResult<Vec<BreakRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM break_records ORDER BY started_at ASC",
+        BREAK_RECORD_COLUMNS
+    ))?;
+    let rows = stmt.query_map([], break_record_from_row)?;
+    rows.collect()
+}
+
+/// The user's Downloads folder, the default destination for exports.
+pub fn downloads_dir() -> PathBuf {
+    dirs::download_dir().unwrap_or_else(|| {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("Downloads")
-    });
-
-    let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("blinky_export_{}.csv", timestamp);
-    let path = downloads.join(&filename);
+    })
+}
 
-    let mut stmt = conn.prepare(
-        "SELECT id, started_at, duration_seconds, completed, skipped, preceding_work_seconds
-         FROM break_records ORDER BY started_at ASC",
-    )?;
+/// Export break records as CSV, filtered and formatted per `opts`.
+/// Returns the file path of the created CSV.
+pub fn export_to_csv(conn: &Connection, opts: &CsvExportOptions) -> SqlResult<String> {
+    let (start, end) = export::range_ms(opts).map_err(rusqlite::Error::InvalidParameterName)?;
 
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM break_records WHERE started_at >= ?1 AND started_at <= ?2
+         ORDER BY started_at ASC",
+        BREAK_RECORD_COLUMNS
+    ))?;
     let records: Vec<BreakRecord> = stmt
-        .query_map([], |row| {
-            Ok(BreakRecord {
-                id: row.get(0)?,
-                started_at: row.get::<_, i64>(1)? as u64,
-                duration_seconds: row.get::<_, i32>(2)? as u32,
-                completed: row.get::<_, i32>(3)? != 0,
-                skipped: row.get::<_, i32>(4)? != 0,
-                preceding_work_seconds: row.get::<_, i32>(5)? as u32,
-            })
-        })?
+        .query_map(params![start as i64, end as i64], break_record_from_row)?
+        .filter(|r| r.as_ref().map_or(true, |r| opts.status.matches(r)))
         .collect::<SqlResult<Vec<_>>>()?;
 
-    let mut csv =
-        String::from("id,started_at,duration_seconds,completed,skipped,preceding_work_seconds\n");
-    for r in &records {
-        csv.push_str(&format!(
-            "{},{},{},{},{},{}\n",
-            r.id,
-            r.started_at,
-            r.duration_seconds,
-            r.completed,
-            r.skipped,
-            r.preceding_work_seconds
-        ));
-    }
+    let csv =
+        export::break_records_csv(&records, opts).map_err(rusqlite::Error::InvalidParameterName)?;
+    write_export(
+        &export::destination_path(opts, downloads_dir(), "blinky_export"),
+        &csv,
+    )
+}
+
+/// Export the aggregated daily stats as CSV. Without a `from` date the export
+/// starts at the earliest cached day; without a `to` date it ends today.
+pub fn export_daily_stats_csv(conn: &Connection, opts: &CsvExportOptions) -> SqlResult<String> {
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let from = match &opts.from {
+        Some(d) => d.clone(),
+        None => conn
+            .query_row("SELECT MIN(date) FROM daily_stats_cache", [], |row| {
+                row.get::<_, Option<String>>(0)
+            })?
+            .unwrap_or_else(|| today.clone()),
+    };
+    let to = opts.to.clone().unwrap_or(today);
+    let stats = get_daily_stats_range(conn, &from, &to)?;
+
+    let csv =
+        export::daily_stats_csv(&stats, opts).map_err(rusqlite::Error::InvalidParameterName)?;
+    write_export(
+        &export::destination_path(opts, downloads_dir(), "blinky_daily_stats"),
+        &csv,
+    )
+}
 
+fn write_export(path: &std::path::Path, csv: &str) -> SqlResult<String> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
             .map_err(|e| rusqlite::Error::InvalidParameterName(format!("dir error: {}", e)))?;
     }
-    std::fs::write(&path, csv)
+    std::fs::write(path, csv)
         .map_err(|e| rusqlite::Error::InvalidParameterName(format!("write error: {}", e)))?;
 
     Ok(path.to_string_lossy().to_string())
@@ -487,23 +1060,62 @@ mod tests {
         assert_eq!(settings.launch_at_login, defaults.launch_at_login);
         assert_eq!(settings.daily_goal, defaults.daily_goal);
         assert_eq!(settings.idle_pause_minutes, defaults.idle_pause_minutes);
+        assert_eq!(settings.idle_resume_policy, defaults.idle_resume_policy);
+        assert_eq!(
+            settings.media_activity_enabled,
+            defaults.media_activity_enabled
+        );
+        assert_eq!(
+            settings.media_player_allowlist,
+            defaults.media_player_allowlist
+        );
+        assert_eq!(
+            settings.meeting_detection_enabled,
+            defaults.meeting_detection_enabled
+        );
+        assert_eq!(
+            settings.meeting_max_defer_minutes,
+            defaults.meeting_max_defer_minutes
+        );
+        assert_eq!(settings.backup_daily_keep, defaults.backup_daily_keep);
+        assert_eq!(settings.backup_weekly_keep, defaults.backup_weekly_keep);
+        assert_eq!(settings.retention_amount, defaults.retention_amount);
+        assert_eq!(settings.retention_unit, defaults.retention_unit);
+        assert_eq!(settings.report_auto_enabled, defaults.report_auto_enabled);
+        assert_eq!(settings.report_format, defaults.report_format);
+        assert_eq!(settings.report_dir, defaults.report_dir);
         assert_eq!(settings.theme, defaults.theme);
     }
 
     #[test]
     fn test_save_load_settings_roundtrip() {
         let conn = setup_test_db();
-        let mut settings = UserSettings::default();
-        settings.work_interval_minutes = 15;
-        settings.break_duration_seconds = 30;
-        settings.sound_enabled = false;
-        settings.sound_volume = 0.5;
-        settings.notification_enabled = false;
-        settings.overlay_enabled = false;
-        settings.launch_at_login = true;
-        settings.daily_goal = 12;
-        settings.idle_pause_minutes = 10;
-        settings.theme = "dark".to_string();
+        let settings = UserSettings {
+            work_interval_minutes: 15,
+            break_duration_seconds: 30,
+            sound_enabled: false,
+            sound_volume: 0.5,
+            notification_enabled: false,
+            overlay_enabled: false,
+            launch_at_login: true,
+            daily_goal: 12,
+            idle_pause_minutes: 10,
+            idle_resume_policy: "resume".to_string(),
+            idle_long_away_minutes: 30,
+            media_activity_enabled: false,
+            media_player_allowlist: r#"["vlc","mpv"]"#.to_string(),
+            meeting_detection_enabled: false,
+            meeting_max_defer_minutes: 30,
+            backup_daily_keep: 3,
+            backup_weekly_keep: 0,
+            retention_amount: 6,
+            retention_unit: "months".to_string(),
+            report_auto_enabled: true,
+            report_format: "markdown".to_string(),
+            report_dir: "/home/me/reports".to_string(),
+            theme: "dark".to_string(),
+            ..UserSettings::default()
+        };
 
         save_settings(&conn, &settings).unwrap();
         let loaded = load_settings(&conn).unwrap();
@@ -517,9 +1129,68 @@ mod tests {
         assert!(loaded.launch_at_login);
         assert_eq!(loaded.daily_goal, 12);
         assert_eq!(loaded.idle_pause_minutes, 10);
+        assert_eq!(loaded.idle_resume_policy, "resume");
+        assert_eq!(loaded.idle_long_away_minutes, 30);
+        assert!(!loaded.media_activity_enabled);
+        assert_eq!(loaded.media_player_allowlist, r#"["vlc","mpv"]"#);
+        assert!(!loaded.meeting_detection_enabled);
+        assert_eq!(loaded.meeting_max_defer_minutes, 30);
+        assert_eq!(loaded.backup_daily_keep, 3);
+        assert_eq!(loaded.backup_weekly_keep, 0);
+        assert_eq!(loaded.retention_amount, 6);
+        assert_eq!(loaded.retention_unit, "months");
+        assert!(loaded.report_auto_enabled);
+        assert_eq!(loaded.report_format, "markdown");
+        assert_eq!(loaded.report_dir, "/home/me/reports");
         assert_eq!(loaded.theme, "dark");
     }
 
+    #[test]
+    fn test_settings_history_records_changes() {
+        let conn = setup_test_db();
+        assert!(get_settings_history(&conn).unwrap().is_empty());
+        let defaults = UserSettings::default();
+
+        // Saving unchanged goal values doesn't add a snapshot beyond the seed
+        let themed = UserSettings {
+            theme: "dark".to_string(),
+            ..UserSettings::default()
+        };
+        record_settings_snapshot(&conn, &themed, "2024-01-10").unwrap();
+        save_settings(&conn, &themed).unwrap();
+        let history = get_settings_history(&conn).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].effective_date, SETTINGS_HISTORY_START);
+        assert_eq!(history[0].daily_goal, defaults.daily_goal);
+
+        let raised = UserSettings {
+            daily_goal: 20,
+            ..UserSettings::default()
+        };
+        record_settings_snapshot(&conn, &raised, "2024-01-15").unwrap();
+        // A second change the same day replaces that day's snapshot
+        let tweaked = UserSettings {
+            daily_goal: 20,
+            break_duration_seconds: 30,
+            ..UserSettings::default()
+        };
+        record_settings_snapshot(&conn, &tweaked, "2024-01-15").unwrap();
+
+        let history = get_settings_history(&conn).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].effective_date, "2024-01-15");
+        assert_eq!(history[1].daily_goal, 20);
+        assert_eq!(history[1].break_duration_seconds, 30);
+        assert_eq!(
+            settings_in_effect(&conn, "2024-01-14").unwrap().daily_goal,
+            defaults.daily_goal
+        );
+        assert_eq!(
+            settings_in_effect(&conn, "2024-02-01").unwrap().daily_goal,
+            20
+        );
+    }
+
     #[test]
     fn test_insert_and_query_break_records() {
         let conn = setup_test_db();
@@ -529,9 +1200,9 @@ mod tests {
         for i in 0..10 {
             let id = insert_break_record(&conn, now + i * 1000, 1200).unwrap();
             if i % 3 == 0 {
-                update_break_completion(&conn, id, 20, true, false).unwrap();
+                finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
             } else if i % 3 == 1 {
-                update_break_completion(&conn, id, 5, false, true).unwrap();
+                finish_break_record(&conn, id, 5, BreakOutcome::Skipped, None, None).unwrap();
             }
             // i % 3 == 2: leave as in-progress (default)
         }
@@ -564,16 +1235,16 @@ mod tests {
 
         // 3 completed, 1 skipped
         let id1 = insert_break_record(&conn, base, 1200).unwrap();
-        update_break_completion(&conn, id1, 20, true, false).unwrap();
+        finish_break_record(&conn, id1, 20, BreakOutcome::Completed, None, None).unwrap();
 
-        let id2 = insert_break_record(&conn, base + 1200_000, 1200).unwrap();
-        update_break_completion(&conn, id2, 20, true, false).unwrap();
+        let id2 = insert_break_record(&conn, base + 1_200_000, 1200).unwrap();
+        finish_break_record(&conn, id2, 20, BreakOutcome::Completed, None, None).unwrap();
 
-        let id3 = insert_break_record(&conn, base + 2400_000, 1200).unwrap();
-        update_break_completion(&conn, id3, 20, true, false).unwrap();
+        let id3 = insert_break_record(&conn, base + 2_400_000, 1200).unwrap();
+        finish_break_record(&conn, id3, 20, BreakOutcome::Completed, None, None).unwrap();
 
-        let id4 = insert_break_record(&conn, base + 3600_000, 1200).unwrap();
-        update_break_completion(&conn, id4, 5, false, true).unwrap();
+        let id4 = insert_break_record(&conn, base + 3_600_000, 1200).unwrap();
+        finish_break_record(&conn, id4, 5, BreakOutcome::Skipped, None, None).unwrap();
 
         let stats = recompute_daily_stats(&conn, &today).unwrap();
         assert_eq!(stats.breaks_completed, 3);
@@ -597,7 +1268,7 @@ mod tests {
         // All completed: streak = 5
         for i in 0..5 {
             let id = insert_break_record(&conn, base + i * 60_000, 1200).unwrap();
-            update_break_completion(&conn, id, 20, true, false).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
         }
         let stats = recompute_daily_stats(&conn, &today).unwrap();
         assert_eq!(stats.longest_streak, 5);
@@ -607,7 +1278,7 @@ mod tests {
             .unwrap();
         for i in 0..3 {
             let id = insert_break_record(&conn, base + i * 60_000, 1200).unwrap();
-            update_break_completion(&conn, id, 5, false, true).unwrap();
+            finish_break_record(&conn, id, 5, BreakOutcome::Skipped, None, None).unwrap();
         }
         let stats = recompute_daily_stats(&conn, &today).unwrap();
         assert_eq!(stats.longest_streak, 0);
@@ -618,15 +1289,83 @@ mod tests {
         for i in 0..6 {
             let id = insert_break_record(&conn, base + i * 60_000, 1200).unwrap();
             if i % 2 == 0 {
-                update_break_completion(&conn, id, 20, true, false).unwrap();
+                finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
             } else {
-                update_break_completion(&conn, id, 5, false, true).unwrap();
+                finish_break_record(&conn, id, 5, BreakOutcome::Skipped, None, None).unwrap();
             }
         }
         let stats = recompute_daily_stats(&conn, &today).unwrap();
         assert_eq!(stats.longest_streak, 1);
     }
 
+    #[test]
+    fn test_only_user_skips_lower_compliance() {
+        let conn = setup_test_db();
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let parsed = NaiveDate::parse_from_str(&today, "%Y-%m-%d").unwrap();
+        let base = parsed
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis() as u64;
+
+        let outcomes = [
+            BreakOutcome::Completed,
+            BreakOutcome::Reset,
+            BreakOutcome::Completed,
+            BreakOutcome::Snoozed,
+            BreakOutcome::Interrupted,
+            BreakOutcome::Demo,
+            BreakOutcome::Completed,
+        ];
+        for (i, outcome) in outcomes.iter().enumerate() {
+            let id = insert_break_record(&conn, base + i as u64 * 60_000, 1200).unwrap();
+            finish_break_record(&conn, id, 20, *outcome, Some(BreakSource::Tray), None).unwrap();
+        }
+
+        let stats = recompute_daily_stats(&conn, &today).unwrap();
+        assert_eq!(stats.breaks_completed, 3);
+        assert_eq!(stats.breaks_skipped, 0);
+        assert_eq!(stats.longest_streak, 3);
+        assert!((stats.compliance_rate - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_finish_break_record_sets_outcome_source_and_reason() {
+        let conn = setup_test_db();
+        let id = insert_break_record(&conn, 1_000_000, 1200).unwrap();
+        finish_break_record(
+            &conn,
+            id,
+            3,
+            BreakOutcome::Skipped,
+            Some(BreakSource::Overlay),
+            Some("Deep focus"),
+        )
+        .unwrap();
+
+        let r = &get_break_records(&conn, 10, 0).unwrap()[0];
+        assert_eq!(r.outcome, Some(BreakOutcome::Skipped));
+        assert_eq!(r.source, Some(BreakSource::Overlay));
+        assert_eq!(r.skip_reason.as_deref(), Some("Deep focus"));
+        assert!(r.skipped && !r.completed);
+        assert!(r.ended_at.is_some());
+    }
+
+    #[test]
+    fn test_mark_interrupted_breaks() {
+        let conn = setup_test_db();
+        let open = insert_break_record(&conn, 1_000_000, 1200).unwrap();
+        let done = insert_break_record(&conn, 2_000_000, 1200).unwrap();
+        finish_break_record(&conn, done, 20, BreakOutcome::Completed, None, None).unwrap();
+
+        assert_eq!(mark_interrupted_breaks(&conn).unwrap(), 1);
+        let records = get_all_break_records(&conn).unwrap();
+        assert_eq!(records[0].id, open);
+        assert_eq!(records[0].outcome, Some(BreakOutcome::Interrupted));
+        assert_eq!(records[1].outcome, Some(BreakOutcome::Completed));
+    }
+
     #[test]
     fn test_compliance_rate_zero_division() {
         let conn = setup_test_db();
@@ -644,9 +1383,12 @@ mod tests {
 
         // Insert some data
         insert_break_record(&conn, now, 1200).unwrap();
-        let mut settings = UserSettings::default();
-        settings.work_interval_minutes = 10;
+        let settings = UserSettings {
+            work_interval_minutes: 10,
+            ..UserSettings::default()
+        };
         save_settings(&conn, &settings).unwrap();
+        crate::insights::mark_summarized(&conn, "2024-01-15").unwrap();
 
         // Clear
         clear_all_data(&conn).unwrap();
@@ -658,6 +1400,16 @@ mod tests {
         // Verify settings reset to defaults
         let loaded = load_settings(&conn).unwrap();
         assert_eq!(loaded.work_interval_minutes, 20); // back to default
+
+        // The next daily summary isn't held back by the cleared history
+        let last: Option<String> = conn
+            .query_row(
+                "SELECT last_summarized FROM daily_summary_state WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(last, None);
     }
 
     #[test]
@@ -693,11 +1445,11 @@ mod tests {
         assert_eq!(count_breaks_today(&conn).unwrap(), 0);
 
         let id1 = insert_break_record(&conn, base, 1200).unwrap();
-        update_break_completion(&conn, id1, 20, true, false).unwrap();
+        finish_break_record(&conn, id1, 20, BreakOutcome::Completed, None, None).unwrap();
         let id2 = insert_break_record(&conn, base + 60_000, 1200).unwrap();
-        update_break_completion(&conn, id2, 5, false, true).unwrap(); // skipped, not counted
+        finish_break_record(&conn, id2, 5, BreakOutcome::Skipped, None, None).unwrap(); // skipped, not counted
         let id3 = insert_break_record(&conn, base + 120_000, 1200).unwrap();
-        update_break_completion(&conn, id3, 20, true, false).unwrap();
+        finish_break_record(&conn, id3, 20, BreakOutcome::Completed, None, None).unwrap();
 
         assert_eq!(count_breaks_today(&conn).unwrap(), 2);
     }
@@ -708,11 +1460,17 @@ mod tests {
         let now = Utc::now().timestamp_millis() as u64;
 
         let id1 = insert_break_record(&conn, now, 1200).unwrap();
-        update_break_completion(&conn, id1, 20, true, false).unwrap();
+        finish_break_record(&conn, id1, 20, BreakOutcome::Completed, None, None).unwrap();
         let id2 = insert_break_record(&conn, now + 60_000, 1200).unwrap();
-        update_break_completion(&conn, id2, 5, false, true).unwrap();
-
-        let path = export_to_csv(&conn).unwrap();
+        finish_break_record(&conn, id2, 5, BreakOutcome::Skipped, None, None).unwrap();
+
+        let dest =
+            std::env::temp_dir().join(format!("blinky_export_test_{}.csv", std::process::id()));
+        let opts = CsvExportOptions {
+            destination: Some(dest.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let path = export_to_csv(&conn, &opts).unwrap();
         assert!(std::path::Path::new(&path).exists());
 
         let contents = std::fs::read_to_string(&path).unwrap();
@@ -726,6 +1484,107 @@ mod tests {
         std::fs::remove_file(&path).unwrap();
     }
 
+    #[test]
+    fn test_export_to_csv_filters() {
+        let conn = setup_test_db();
+        // 2024-01-15 and 2024-01-16, 10:00 UTC
+        let day1 = 1_705_312_800_000u64;
+        let day2 = day1 + 86_400_000;
+        for (ts, completed) in [(day1, true), (day1 + 60_000, false), (day2, true)] {
+            let id = insert_break_record(&conn, ts, 1200).unwrap();
+            finish_break_record(
+                &conn,
+                id,
+                20,
+                if completed {
+                    BreakOutcome::Completed
+                } else {
+                    BreakOutcome::Skipped
+                },
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        let dest =
+            std::env::temp_dir().join(format!("blinky_export_filter_{}.csv", std::process::id()));
+        let opts = CsvExportOptions {
+            from: Some("2024-01-15".to_string()),
+            to: Some("2024-01-15".to_string()),
+            status: export::StatusFilter::Completed,
+            destination: Some(dest.to_string_lossy().to_string()),
+            timestamp_format: export::TimestampFormat::IsoUtc,
+            columns: vec!["started_at".to_string()],
+        };
+        let path = export_to_csv(&conn, &opts).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines, vec!["started_at", "2024-01-15T10:00:00Z"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export_daily_stats_csv() {
+        let conn = setup_test_db();
+        let day1 = 1_705_312_800_000u64;
+        let id = insert_break_record(&conn, day1, 1200).unwrap();
+        finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+        recompute_daily_stats(&conn, "2024-01-15").unwrap();
+
+        let dest =
+            std::env::temp_dir().join(format!("blinky_daily_export_{}.csv", std::process::id()));
+        let opts = CsvExportOptions {
+            from: Some("2024-01-14".to_string()),
+            to: Some("2024-01-15".to_string()),
+            destination: Some(dest.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let path = export_daily_stats_csv(&conn, &opts).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3); // header + 2 days, the empty day zero-filled
+        assert!(lines[1].starts_with("2024-01-14,0,0,"));
+        assert!(lines[2].starts_with("2024-01-15,1,0,20,"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_deferral_summary_groups_by_reason() {
+        let conn = setup_test_db();
+        let today = Utc::now().date_naive();
+        let base = today
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis() as u64;
+
+        let id1 = insert_break_deferral(&conn, base, "camera").unwrap();
+        finish_break_deferral(&conn, id1, 600).unwrap();
+        let id2 = insert_break_deferral(&conn, base + 3_600_000, "camera").unwrap();
+        finish_break_deferral(&conn, id2, 300).unwrap();
+        let id3 = insert_break_deferral(&conn, base + 7_200_000, "microphone").unwrap();
+        finish_break_deferral(&conn, id3, 120).unwrap();
+
+        // Outside the range: ignored
+        let old = (today - chrono::Duration::days(10))
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis() as u64;
+        insert_break_deferral(&conn, old, "camera").unwrap();
+
+        let day = today.format("%Y-%m-%d").to_string();
+        let summary = get_deferral_summary(&conn, &day, &day).unwrap();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].reason, "camera");
+        assert_eq!(summary[0].deferrals, 2);
+        assert_eq!(summary[0].deferred_seconds, 900);
+        assert_eq!(summary[1].reason, "microphone");
+        assert_eq!(summary[1].deferrals, 1);
+        assert_eq!(summary[1].deferred_seconds, 120);
+    }
+
     #[test]
     fn test_migration_is_idempotent() {
         let conn = setup_test_db();
@@ -736,6 +1595,118 @@ mod tests {
         assert_eq!(settings.work_interval_minutes, 20);
     }
 
+    #[test]
+    fn test_fresh_db_is_at_latest_schema_version() {
+        let conn = setup_test_db();
+        let latest = MIGRATIONS.last().unwrap().version;
+        assert_eq!(schema_version(&conn).unwrap(), latest);
+
+        let logged: i64 = conn
+            .query_row("SELECT COUNT(*) FROM _migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(logged as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (i, m) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(m.version as usize, i + 1, "{} is out of order", m.name);
+        }
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let conn = setup_test_db();
+        conn.pragma_update(None, "user_version", 999).unwrap();
+
+        let err = run_migrations(&conn).unwrap_err();
+        assert!(
+            err.to_string().contains("newer"),
+            "unexpected error: {}",
+            err
+        );
+        // Left untouched
+        assert_eq!(schema_version(&conn).unwrap(), 999);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                name: "001_initial",
+                sql: MIGRATION_001_SQL,
+                post: None,
+            },
+            Migration {
+                version: 2,
+                name: "002_broken",
+                sql: "ALTER TABLE settings ADD COLUMN half_applied INTEGER;
+                      THIS IS NOT SQL;",
+                post: None,
+            },
+        ];
+
+        assert!(apply_migrations(&conn, &migrations).is_err());
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+
+        // The first statement of the broken migration must not have stuck
+        let has_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('settings') WHERE name = 'half_applied'")
+            .unwrap()
+            .exists([])
+            .unwrap();
+        assert!(!has_column);
+    }
+
+    #[test]
+    fn test_legacy_migrations_log_sets_user_version() {
+        // A database migrated by the old name-based runner up to 002
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATION_001_SQL).unwrap();
+        conn.execute_batch(MIGRATION_002_SQL).unwrap();
+        conn.execute_batch(
+            "INSERT INTO _migrations (name) VALUES ('001_initial');
+             INSERT INTO _migrations (name) VALUES ('002_onboarding');",
+        )
+        .unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        // Must not try to re-add the 002 columns
+        run_migrations(&conn).unwrap();
+        assert_eq!(
+            schema_version(&conn).unwrap(),
+            MIGRATIONS.last().unwrap().version
+        );
+        load_settings(&conn).unwrap();
+    }
+
+    #[test]
+    fn test_break_outcome_backfill() {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, &MIGRATIONS[..7]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO break_records (started_at, duration_seconds, completed, skipped, preceding_work_seconds)
+             VALUES (1000, 20, 1, 0, 1200), (2000, 4, 0, 1, 1200), (3000, 0, 0, 0, 1200);",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+        let records = get_all_break_records(&conn).unwrap();
+        let outcomes: Vec<_> = records.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Some(BreakOutcome::Completed),
+                Some(BreakOutcome::Skipped),
+                Some(BreakOutcome::Interrupted),
+            ]
+        );
+        assert_eq!(records[0].ended_at, Some(21_000));
+        assert!(records.iter().all(|r| r.source.is_none()));
+    }
+
     #[test]
     fn test_onboarding_defaults_on_fresh_db() {
         let conn = setup_test_db();
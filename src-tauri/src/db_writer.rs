@@ -0,0 +1,112 @@
+use rusqlite::Connection;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Owns the only writable connection on a dedicated thread. Jobs run one at a
+/// time in the order they were sent, so a write queued after another always
+/// sees its effects.
+pub struct DbWriter {
+    tx: Sender<Job>,
+}
+
+impl DbWriter {
+    pub fn spawn(mut conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("db-writer".to_string())
+            .spawn(move || {
+                for job in rx {
+                    // A panicking job must not take every later write down with it
+                    if panic::catch_unwind(AssertUnwindSafe(|| job(&mut conn))).is_err() {
+                        eprintln!("[db] Write job panicked");
+                    }
+                }
+            })
+            .expect("failed to spawn database writer thread");
+        DbWriter { tx }
+    }
+
+    /// Queue a write without waiting for it. Never blocks.
+    pub fn send(&self, job: impl FnOnce(&mut Connection) + Send + 'static) {
+        if self.tx.send(Box::new(job)).is_err() {
+            eprintln!("[db] Writer thread has stopped; dropping write");
+        }
+    }
+
+    /// Queue a write and wait for its result.
+    pub fn call<R: Send + 'static>(
+        &self,
+        job: impl FnOnce(&mut Connection) -> R + Send + 'static,
+    ) -> Result<R, String> {
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.send(move |conn| {
+            let _ = tx.send(job(conn));
+        });
+        rx.recv()
+            .map_err(|_| "database writer is not running".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use std::path::PathBuf;
+
+    fn temp_db(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "blinky-writer-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("blinky.db")
+    }
+
+    #[test]
+    fn test_writes_run_in_order_and_are_visible_to_reader() {
+        let path = temp_db("order");
+        let writer = DbWriter::spawn(db::init_db(path.to_str().unwrap()).unwrap());
+        let reader = db::open_reader(path.to_str().unwrap()).unwrap();
+
+        for i in 0..10u64 {
+            writer.send(move |conn| {
+                db::insert_break_record(conn, 1_000 + i, 1200).unwrap();
+            });
+        }
+        // call() waits behind everything queued before it
+        let count = writer
+            .call(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM break_records", [], |r| {
+                    r.get::<_, u32>(0)
+                })
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(count, 10);
+
+        let records = db::get_all_break_records(&reader).unwrap();
+        let starts: Vec<u64> = records.iter().map(|r| r.started_at).collect();
+        assert_eq!(starts, (1_000..1_010).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_reader_is_read_only() {
+        let path = temp_db("readonly");
+        let _writer = DbWriter::spawn(db::init_db(path.to_str().unwrap()).unwrap());
+        let reader = db::open_reader(path.to_str().unwrap()).unwrap();
+        assert!(db::insert_break_record(&reader, 1_000, 1200).is_err());
+    }
+
+    #[test]
+    fn test_panicking_job_does_not_stop_writer() {
+        let path = temp_db("panic");
+        let writer = DbWriter::spawn(db::init_db(path.to_str().unwrap()).unwrap());
+        writer.send(|_| panic!("boom"));
+        assert_eq!(writer.call(|_| 42).unwrap(), 42);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::state::{BreakOutcome, BreakRecord, BreakSource, DailyStats};
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+/// Columns available in the break records export, in default order.
+pub const BREAK_COLUMNS: &[&str] = &[
+    "id",
+    "started_at",
+    "duration_seconds",
+    "completed",
+    "skipped",
+    "preceding_work_seconds",
+    "ended_at",
+    "outcome",
+    "source",
+    "note",
+    "skip_reason",
+];
+
+/// Columns available in the daily stats export, in default order.
+pub const DAILY_COLUMNS: &[&str] = &[
+    "date",
+    "breaks_completed",
+    "breaks_skipped",
+    "total_rest_seconds",
+    "longest_streak",
+    "compliance_rate",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusFilter {
+    #[default]
+    All,
+    Completed,
+    Skipped,
+}
+
+impl StatusFilter {
+    pub fn matches(self, r: &BreakRecord) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Completed => r.completed,
+            StatusFilter::Skipped => r.skipped,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// Milliseconds since the Unix epoch, as stored.
+    #[default]
+    EpochMs,
+    /// ISO 8601 in UTC, e.g. `2024-01-15T10:00:00Z`.
+    IsoUtc,
+    /// ISO 8601 in the local timezone with offset, e.g. `2024-01-15T11:00:00+01:00`.
+    IsoLocal,
+}
+
+/// What to export and where. Every field is optional so the frontend can
+/// send only what the user changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvExportOptions {
+    /// First day to include (YYYY-MM-DD, UTC), inclusive.
+    pub from: Option<String>,
+    /// Last day to include (YYYY-MM-DD, UTC), inclusive.
+    pub to: Option<String>,
+    /// Only applies to break records.
+    pub status: StatusFilter,
+    /// Full file path. Defaults to a timestamped file in Downloads.
+    pub destination: Option<String>,
+    pub timestamp_format: TimestampFormat,
+    /// Columns to write, in order. Empty means all.
+    pub columns: Vec<String>,
+}
+
+/// Millisecond bounds for the options' day range. Open ends are unbounded.
+pub fn range_ms(opts: &CsvExportOptions) -> Result<(u64, u64), String> {
+    day_range_ms(opts.from.as_deref(), opts.to.as_deref())
+        .map_err(|e| format!("export range {}", e))
+}
+
+/// Millisecond bounds for an inclusive range of UTC days (YYYY-MM-DD). Open
+/// ends are unbounded.
+pub fn day_range_ms(from: Option<&str>, to: Option<&str>) -> Result<(u64, u64), String> {
+    let start = match from {
+        Some(d) => day_start_ms(parse_day(d)?),
+        None => 0,
+    };
+    let end = match to {
+        Some(d) => day_start_ms(parse_day(d)?) + 86_400_000 - 1,
+        None => i64::MAX as u64,
+    };
+    if start > end {
+        return Err("'from' is after 'to'".to_string());
+    }
+    Ok((start, end))
+}
+
+fn day_start_ms(day: NaiveDate) -> u64 {
+    day.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis() as u64
+}
+
+fn parse_day(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("bad date '{}': {}", s, e))
+}
+
+/// Resolve requested columns against what's available, rejecting unknown names.
+fn resolve_columns<'a>(
+    requested: &'a [String],
+    available: &[&'a str],
+) -> Result<Vec<&'a str>, String> {
+    if requested.is_empty() {
+        return Ok(available.to_vec());
+    }
+    requested
+        .iter()
+        .map(|c| {
+            if available.contains(&c.as_str()) {
+                Ok(c.as_str())
+            } else {
+                Err(format!("unknown export column '{}'", c))
+            }
+        })
+        .collect()
+}
+
+/// Quote a field per RFC 4180 when it contains a delimiter, quote, or line break.
+/// Free text (notes, skip reasons) that a spreadsheet would run as a formula
+/// gets a leading `'`, so it opens as the text it is.
+pub fn escape_field(field: &str) -> Cow<'_, str> {
+    let field: Cow<str> = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        Cow::Owned(format!("'{}", field))
+    } else {
+        Cow::Borrowed(field)
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        field
+    }
+}
+
+pub fn format_timestamp(ms: u64, format: TimestampFormat) -> String {
+    let Some(utc) = DateTime::<Utc>::from_timestamp_millis(ms as i64) else {
+        return ms.to_string();
+    };
+    match format {
+        TimestampFormat::EpochMs => ms.to_string(),
+        TimestampFormat::IsoUtc => utc.to_rfc3339_opts(SecondsFormat::Secs, true),
+        TimestampFormat::IsoLocal => utc
+            .with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::Secs, false),
+    }
+}
+
+fn write_rows<'a>(columns: &[&str], rows: impl Iterator<Item = Vec<Cow<'a, str>>>) -> String {
+    let mut csv = columns.join(",");
+    csv.push_str("\r\n");
+    for row in rows {
+        let fields: Vec<Cow<str>> = row.iter().map(|f| escape_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Render break records as CSV. Filtering is the caller's job.
+pub fn break_records_csv(
+    records: &[BreakRecord],
+    opts: &CsvExportOptions,
+) -> Result<String, String> {
+    let columns = resolve_columns(&opts.columns, BREAK_COLUMNS)?;
+    let rows = records.iter().map(|r| {
+        columns
+            .iter()
+            .map(|c| -> Cow<str> {
+                match *c {
+                    "id" => r.id.to_string().into(),
+                    "started_at" => format_timestamp(r.started_at, opts.timestamp_format).into(),
+                    "duration_seconds" => r.duration_seconds.to_string().into(),
+                    "completed" => r.completed.to_string().into(),
+                    "skipped" => r.skipped.to_string().into(),
+                    "preceding_work_seconds" => r.preceding_work_seconds.to_string().into(),
+                    "ended_at" => r
+                        .ended_at
+                        .map(|ms| format_timestamp(ms, opts.timestamp_format))
+                        .unwrap_or_default()
+                        .into(),
+                    "outcome" => r.outcome.map(BreakOutcome::as_str).unwrap_or("").into(),
+                    "source" => r.source.map(BreakSource::as_str).unwrap_or("").into(),
+                    "note" => r.note.as_deref().unwrap_or("").into(),
+                    "skip_reason" => r.skip_reason.as_deref().unwrap_or("").into(),
+                    _ => unreachable!("column validated by resolve_columns"),
+                }
+            })
+            .collect()
+    });
+    Ok(write_rows(&columns, rows))
+}
+
+/// Render aggregated daily stats as CSV.
+pub fn daily_stats_csv(stats: &[DailyStats], opts: &CsvExportOptions) -> Result<String, String> {
+    let columns = resolve_columns(&opts.columns, DAILY_COLUMNS)?;
+    let rows = stats.iter().map(|s| {
+        columns
+            .iter()
+            .map(|c| -> Cow<str> {
+                match *c {
+                    "date" => s.date.as_str().into(),
+                    "breaks_completed" => s.breaks_completed.to_string().into(),
+                    "breaks_skipped" => s.breaks_skipped.to_string().into(),
+                    "total_rest_seconds" => s.total_rest_seconds.to_string().into(),
+                    "longest_streak" => s.longest_streak.to_string().into(),
+                    "compliance_rate" => format!("{:.4}", s.compliance_rate).into(),
+                    _ => unreachable!("column validated by resolve_columns"),
+                }
+            })
+            .collect()
+    });
+    Ok(write_rows(&columns, rows))
+}
+
+/// Where to write an export: the requested destination, or a timestamped
+/// file named after `prefix` in Downloads.
+pub fn destination_path(opts: &CsvExportOptions, downloads: PathBuf, prefix: &str) -> PathBuf {
+    match &opts.destination {
+        Some(dest) if !dest.trim().is_empty() => PathBuf::from(dest),
+        _ => downloads.join(format!(
+            "{}_{}.csv",
+            prefix,
+            Utc::now().format("%Y%m%d_%H%M%S")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: i64, started_at: u64, completed: bool) -> BreakRecord {
+        BreakRecord {
+            id,
+            started_at,
+            duration_seconds: if completed { 20 } else { 3 },
+            completed,
+            skipped: !completed,
+            preceding_work_seconds: 1200,
+            ended_at: None,
+            outcome: Some(BreakOutcome::from_flags(completed, !completed)),
+            source: None,
+            note: None,
+            skip_reason: None,
+        }
+    }
+
+    // 2024-01-15T10:00:00Z
+    const TS: u64 = 1_705_312_800_000;
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("plain"), "plain");
+        assert_eq!(escape_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_formulas_are_neutralized() {
+        assert_eq!(
+            escape_field("=HYPERLINK(\"x\")"),
+            "\"'=HYPERLINK(\"\"x\"\")\""
+        );
+        assert_eq!(escape_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_field("+1"), "'+1");
+        assert_eq!(escape_field("-2"), "'-2");
+        assert_eq!(escape_field("\tindent"), "'\tindent");
+        assert_eq!(escape_field("\rline"), "\"'\rline\"");
+        assert_eq!(escape_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        assert_eq!(
+            format_timestamp(TS, TimestampFormat::EpochMs),
+            TS.to_string()
+        );
+        assert_eq!(
+            format_timestamp(TS, TimestampFormat::IsoUtc),
+            "2024-01-15T10:00:00Z"
+        );
+        let local = format_timestamp(TS, TimestampFormat::IsoLocal);
+        let parsed = DateTime::parse_from_rfc3339(&local).unwrap();
+        assert_eq!(parsed.timestamp_millis() as u64, TS);
+    }
+
+    #[test]
+    fn test_default_columns_and_header() {
+        let csv = break_records_csv(&[record(1, TS, true)], &CsvExportOptions::default()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "id,started_at,duration_seconds,completed,skipped,preceding_work_seconds,ended_at,outcome,source,note,skip_reason"
+        );
+        assert_eq!(
+            lines[1],
+            format!("1,{},20,true,false,1200,,completed,,,", TS)
+        );
+    }
+
+    #[test]
+    fn test_selected_columns_in_requested_order() {
+        let opts = CsvExportOptions {
+            columns: vec!["completed".to_string(), "started_at".to_string()],
+            timestamp_format: TimestampFormat::IsoUtc,
+            ..Default::default()
+        };
+        let csv = break_records_csv(&[record(1, TS, false)], &opts).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "completed,started_at");
+        assert_eq!(lines[1], "false,2024-01-15T10:00:00Z");
+    }
+
+    #[test]
+    fn test_unknown_column_rejected() {
+        let opts = CsvExportOptions {
+            columns: vec!["password".to_string()],
+            ..Default::default()
+        };
+        assert!(break_records_csv(&[], &opts)
+            .unwrap_err()
+            .contains("password"));
+        // Break columns aren't valid for the daily export
+        let opts = CsvExportOptions {
+            columns: vec!["started_at".to_string()],
+            ..Default::default()
+        };
+        assert!(daily_stats_csv(&[], &opts).is_err());
+    }
+
+    #[test]
+    fn test_status_filter() {
+        let done = record(1, TS, true);
+        let skipped = record(2, TS, false);
+        assert!(StatusFilter::All.matches(&done) && StatusFilter::All.matches(&skipped));
+        assert!(StatusFilter::Completed.matches(&done));
+        assert!(!StatusFilter::Completed.matches(&skipped));
+        assert!(StatusFilter::Skipped.matches(&skipped));
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let opts = CsvExportOptions {
+            from: Some("2024-01-15".to_string()),
+            to: Some("2024-01-15".to_string()),
+            ..Default::default()
+        };
+        let (start, end) = range_ms(&opts).unwrap();
+        assert!(start <= TS && TS <= end);
+        assert_eq!(end - start, 86_400_000 - 1);
+
+        let open = range_ms(&CsvExportOptions::default()).unwrap();
+        assert_eq!(open.0, 0);
+
+        let backwards = CsvExportOptions {
+            from: Some("2024-02-01".to_string()),
+            to: Some("2024-01-01".to_string()),
+            ..Default::default()
+        };
+        assert!(range_ms(&backwards).is_err());
+    }
+
+    #[test]
+    fn test_daily_stats_csv() {
+        let stats = DailyStats {
+            date: "2024-01-15".to_string(),
+            breaks_completed: 9,
+            breaks_skipped: 1,
+            total_rest_seconds: 180,
+            longest_streak: 6,
+            compliance_rate: 0.9,
+        };
+        let csv = daily_stats_csv(&[stats], &CsvExportOptions::default()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], DAILY_COLUMNS.join(","));
+        assert_eq!(lines[1], "2024-01-15,9,1,180,6,0.9000");
+    }
+}
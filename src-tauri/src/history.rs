@@ -0,0 +1,575 @@
+use crate::aggregates;
+use crate::db;
+use crate::export;
+use crate::state::{BreakOutcome, BreakRecord};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+
+/// Largest page a single query returns.
+pub const MAX_PAGE_SIZE: u32 = 500;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Position after the last record of a page. Records are ordered newest
+/// first, ties broken by id, so (started_at, id) is unique.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistoryCursor {
+    pub started_at: u64,
+    pub id: i64,
+}
+
+/// Filters and paging for the break history. Every field is optional. There is
+/// no profile filter: Blinky keeps a single profile, and break records don't
+/// belong to one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// First day to include (YYYY-MM-DD, UTC), inclusive.
+    pub from: Option<String>,
+    /// Last day to include (YYYY-MM-DD, UTC), inclusive.
+    pub to: Option<String>,
+    /// Only these outcomes. Empty means any, including breaks in progress.
+    pub outcomes: Vec<BreakOutcome>,
+    pub min_duration_seconds: Option<u32>,
+    /// Continue after this record; None starts at the newest.
+    pub cursor: Option<HistoryCursor>,
+    pub limit: u32,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        HistoryQuery {
+            from: None,
+            to: None,
+            outcomes: Vec::new(),
+            min_duration_seconds: None,
+            cursor: None,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// Totals over the records on one page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageAggregates {
+    pub completed: u32,
+    pub skipped: u32,
+    /// Reset, interrupted, demo, and so on.
+    pub other: u32,
+    pub total_duration_seconds: u64,
+    /// Rest time from completed breaks only.
+    pub rest_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub records: Vec<BreakRecord>,
+    /// Pass back as `cursor` for the next page; None on the last page.
+    pub next_cursor: Option<HistoryCursor>,
+    /// Records matching the filters across all pages.
+    pub total_count: u32,
+    pub page: PageAggregates,
+}
+
+/// One page of break history, newest first.
+pub fn query_history(conn: &Connection, query: &HistoryQuery) -> SqlResult<HistoryPage> {
+    let (filter, mut params) = filter_clause(query)?;
+
+    let total_count: u32 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM break_records WHERE {}", filter),
+        params_from_iter(params.iter()),
+        |row| row.get(0),
+    )?;
+
+    let mut sql = format!(
+        "SELECT {} FROM break_records WHERE {}",
+        db::BREAK_RECORD_COLUMNS,
+        filter
+    );
+    if let Some(cursor) = query.cursor {
+        sql.push_str(" AND (started_at < ? OR (started_at = ? AND id < ?))");
+        params.push(Value::Integer(cursor.started_at as i64));
+        params.push(Value::Integer(cursor.started_at as i64));
+        params.push(Value::Integer(cursor.id));
+    }
+    // Fetch one extra row to learn whether there's another page
+    let limit = query.limit.clamp(1, MAX_PAGE_SIZE);
+    sql.push_str(" ORDER BY started_at DESC, id DESC LIMIT ?");
+    params.push(Value::Integer(limit as i64 + 1));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let mut records = stmt
+        .query_map(params_from_iter(params.iter()), db::break_record_from_row)?
+        .collect::<SqlResult<Vec<_>>>()?;
+
+    let next_cursor = if records.len() > limit as usize {
+        records.truncate(limit as usize);
+        records.last().map(|r| HistoryCursor {
+            started_at: r.started_at,
+            id: r.id,
+        })
+    } else {
+        None
+    };
+
+    let page = aggregate(&records);
+    Ok(HistoryPage {
+        records,
+        next_cursor,
+        total_count,
+        page,
+    })
+}
+
+/// WHERE clause and parameters for the query's filters (not the cursor).
+fn filter_clause(query: &HistoryQuery) -> SqlResult<(String, Vec<Value>)> {
+    let (start, end) = export::day_range_ms(query.from.as_deref(), query.to.as_deref())
+        .map_err(|e| rusqlite::Error::InvalidParameterName(format!("history range {}", e)))?;
+
+    let mut clauses = vec!["started_at >= ? AND started_at <= ?".to_string()];
+    let mut params = vec![Value::Integer(start as i64), Value::Integer(end as i64)];
+
+    if !query.outcomes.is_empty() {
+        let placeholders = vec!["?"; query.outcomes.len()].join(", ");
+        clauses.push(format!("outcome IN ({})", placeholders));
+        params.extend(
+            query
+                .outcomes
+                .iter()
+                .map(|o| Value::Text(o.as_str().to_string())),
+        );
+    }
+    if let Some(min) = query.min_duration_seconds {
+        clauses.push("duration_seconds >= ?".to_string());
+        params.push(Value::Integer(min as i64));
+    }
+
+    Ok((clauses.join(" AND "), params))
+}
+
+fn aggregate(records: &[BreakRecord]) -> PageAggregates {
+    let mut page = PageAggregates::default();
+    for r in records {
+        page.total_duration_seconds += r.duration_seconds as u64;
+        match r.outcome {
+            Some(BreakOutcome::Completed) => {
+                page.completed += 1;
+                page.rest_seconds += r.duration_seconds as u64;
+            }
+            Some(BreakOutcome::Skipped) => page.skipped += 1,
+            _ => page.other += 1,
+        }
+    }
+    page
+}
+
+/// A correction to one break record. Fields left as None are unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakEdit {
+    pub outcome: Option<BreakOutcome>,
+    pub duration_seconds: Option<u32>,
+    /// An empty note removes the existing one.
+    pub note: Option<String>,
+}
+
+/// One row of the audit trail: a record's values before and after a change.
+/// The `new_*` fields are None for a deletion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakRecordEdit {
+    pub id: i64,
+    pub break_id: i64,
+    pub break_started_at: u64,
+    /// "edit" or "delete"
+    pub action: String,
+    pub edited_at: u64,
+    pub old_outcome: Option<BreakOutcome>,
+    pub new_outcome: Option<BreakOutcome>,
+    pub old_duration_seconds: Option<u32>,
+    pub new_duration_seconds: Option<u32>,
+    pub old_note: Option<String>,
+    pub new_note: Option<String>,
+}
+
+/// Apply `edit` to a finished break, log it, and recompute that day's stats.
+/// A break that's no longer skipped loses its skip reason. Returns the updated
+/// record.
+pub fn edit_record(conn: &Connection, id: i64, edit: &BreakEdit) -> SqlResult<BreakRecord> {
+    let old = finished_record(conn, id)?;
+    let outcome = edit.outcome.or(old.outcome);
+    let duration_seconds = edit.duration_seconds.unwrap_or(old.duration_seconds);
+    let note = match &edit.note {
+        Some(n) if n.trim().is_empty() => None,
+        Some(n) => Some(n.trim().to_string()),
+        None => old.note.clone(),
+    };
+    if outcome == old.outcome && duration_seconds == old.duration_seconds && note == old.note {
+        return Ok(old);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE break_records
+         SET outcome = ?1, completed = ?2, skipped = ?3, duration_seconds = ?4, note = ?5,
+             skip_reason = CASE WHEN ?3 = 1 THEN skip_reason END
+         WHERE id = ?6",
+        params![
+            outcome.map(BreakOutcome::as_str),
+            (outcome == Some(BreakOutcome::Completed)) as i32,
+            (outcome == Some(BreakOutcome::Skipped)) as i32,
+            duration_seconds,
+            note,
+            id
+        ],
+    )?;
+    log_edit(
+        &tx,
+        &old,
+        "edit",
+        outcome,
+        Some(duration_seconds),
+        note.as_deref(),
+    )?;
+    db::recompute_daily_stats(&tx, &utc_day(old.started_at))?;
+    aggregates::invalidate(&tx)?;
+    tx.commit()?;
+
+    finished_record(conn, id)
+}
+
+/// Delete a finished break, log it, and recompute that day's stats.
+pub fn delete_record(conn: &Connection, id: i64) -> SqlResult<()> {
+    let old = finished_record(conn, id)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM break_records WHERE id = ?1", params![id])?;
+    log_edit(&tx, &old, "delete", None, None, None)?;
+    db::recompute_daily_stats(&tx, &utc_day(old.started_at))?;
+    aggregates::invalidate(&tx)?;
+    tx.commit()
+}
+
+/// The audit trail, newest first; only `break_id`'s entries if given.
+pub fn get_edits(conn: &Connection, break_id: Option<i64>) -> SqlResult<Vec<BreakRecordEdit>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, break_id, break_started_at, action, edited_at, old_outcome, new_outcome,
+                old_duration_seconds, new_duration_seconds, old_note, new_note
+         FROM break_record_edits
+         WHERE ?1 IS NULL OR break_id = ?1
+         ORDER BY id DESC",
+    )?;
+    let outcome = |v: Option<String>| v.and_then(|o| BreakOutcome::parse(&o));
+    let rows = stmt.query_map(params![break_id], |row| {
+        Ok(BreakRecordEdit {
+            id: row.get(0)?,
+            break_id: row.get(1)?,
+            break_started_at: row.get::<_, i64>(2)? as u64,
+            action: row.get(3)?,
+            edited_at: row.get::<_, i64>(4)? as u64,
+            old_outcome: outcome(row.get(5)?),
+            new_outcome: outcome(row.get(6)?),
+            old_duration_seconds: row.get(7)?,
+            new_duration_seconds: row.get(8)?,
+            old_note: row.get(9)?,
+            new_note: row.get(10)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The record with `id`, refusing breaks that are still running: the timer
+/// finalizes those itself and would overwrite the correction.
+fn finished_record(conn: &Connection, id: i64) -> SqlResult<BreakRecord> {
+    let record = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM break_records WHERE id = ?1",
+                db::BREAK_RECORD_COLUMNS
+            ),
+            params![id],
+            db::break_record_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| {
+            rusqlite::Error::InvalidParameterName(format!("no break record with id {}", id))
+        })?;
+    if record.outcome.is_none() {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "break {} is still in progress",
+            id
+        )));
+    }
+    Ok(record)
+}
+
+fn log_edit(
+    conn: &Connection,
+    old: &BreakRecord,
+    action: &str,
+    outcome: Option<BreakOutcome>,
+    duration_seconds: Option<u32>,
+    note: Option<&str>,
+) -> SqlResult<()> {
+    conn.execute(
+        "INSERT INTO break_record_edits
+            (break_id, break_started_at, action, edited_at, old_outcome, new_outcome,
+             old_duration_seconds, new_duration_seconds, old_note, new_note)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            old.id,
+            old.started_at as i64,
+            action,
+            Utc::now().timestamp_millis(),
+            old.outcome.map(BreakOutcome::as_str),
+            outcome.map(BreakOutcome::as_str),
+            old.duration_seconds,
+            duration_seconds,
+            old.note,
+            note
+        ],
+    )?;
+    Ok(())
+}
+
+fn utc_day(ms: u64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ms as i64)
+        .unwrap_or_default()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaks::Workdays;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    // 2024-01-15T00:00:00Z
+    const DAY: u64 = 1_705_276_800_000;
+    const HOUR: u64 = 3_600_000;
+
+    fn add(conn: &Connection, started_at: u64, duration: u32, outcome: BreakOutcome) -> i64 {
+        let id = db::insert_break_record(conn, started_at, 1200).unwrap();
+        db::finish_break_record(conn, id, duration, outcome, None, None).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_pages_walk_the_whole_history_without_gaps() {
+        let conn = setup_test_db();
+        for i in 0..7 {
+            add(&conn, DAY + i * HOUR, 20, BreakOutcome::Completed);
+        }
+        // Same start time as another record: the id breaks the tie
+        add(&conn, DAY + 3 * HOUR, 20, BreakOutcome::Skipped);
+
+        let mut query = HistoryQuery {
+            limit: 3,
+            ..Default::default()
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = query_history(&conn, &query).unwrap();
+            assert_eq!(page.total_count, 8);
+            seen.extend(page.records.iter().map(|r| r.id));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen.len(), 8);
+        let mut unique = seen.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 8);
+    }
+
+    #[test]
+    fn test_filters_and_page_aggregates() {
+        let conn = setup_test_db();
+        add(&conn, DAY + HOUR, 20, BreakOutcome::Completed);
+        add(&conn, DAY + 2 * HOUR, 3, BreakOutcome::Skipped);
+        add(&conn, DAY + 3 * HOUR, 8, BreakOutcome::Reset);
+        add(&conn, DAY + 4 * HOUR, 20, BreakOutcome::Completed);
+        // Next day
+        add(&conn, DAY + 25 * HOUR, 20, BreakOutcome::Completed);
+
+        let day_only = HistoryQuery {
+            from: Some("2024-01-15".to_string()),
+            to: Some("2024-01-15".to_string()),
+            ..Default::default()
+        };
+        let page = query_history(&conn, &day_only).unwrap();
+        assert_eq!(page.total_count, 4);
+        assert_eq!(
+            page.page,
+            PageAggregates {
+                completed: 2,
+                skipped: 1,
+                other: 1,
+                total_duration_seconds: 51,
+                rest_seconds: 40,
+            }
+        );
+
+        let not_completed = HistoryQuery {
+            outcomes: vec![BreakOutcome::Skipped, BreakOutcome::Reset],
+            ..Default::default()
+        };
+        assert_eq!(query_history(&conn, &not_completed).unwrap().total_count, 2);
+
+        let long = HistoryQuery {
+            min_duration_seconds: Some(10),
+            ..Default::default()
+        };
+        let page = query_history(&conn, &long).unwrap();
+        assert_eq!(page.total_count, 3);
+        assert!(page.records.iter().all(|r| r.duration_seconds >= 10));
+    }
+
+    #[test]
+    fn test_limit_is_clamped() {
+        let conn = setup_test_db();
+        add(&conn, DAY, 20, BreakOutcome::Completed);
+        add(&conn, DAY + HOUR, 20, BreakOutcome::Completed);
+        let query = HistoryQuery {
+            limit: 0,
+            ..Default::default()
+        };
+        let page = query_history(&conn, &query).unwrap();
+        assert_eq!(page.records.len(), 1);
+        assert!(page.next_cursor.is_some());
+    }
+
+    #[test]
+    fn test_history_queries_use_indexes() {
+        let conn = setup_test_db();
+        let query = HistoryQuery {
+            from: Some("2024-01-15".to_string()),
+            min_duration_seconds: Some(10),
+            ..Default::default()
+        };
+        let (filter, params) = filter_clause(&query).unwrap();
+        let plan: Vec<String> = conn
+            .prepare(&format!(
+                "EXPLAIN QUERY PLAN SELECT COUNT(*) FROM break_records WHERE {}",
+                filter
+            ))
+            .unwrap()
+            .query_map(params_from_iter(params.iter()), |row| row.get(3))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap();
+        assert!(
+            plan.iter().any(|step| step.contains("COVERING INDEX")),
+            "{:?}",
+            plan
+        );
+    }
+
+    #[test]
+    fn test_correcting_a_skip_restores_the_streak() {
+        let conn = setup_test_db();
+        const DAY_MS: u64 = 24 * HOUR;
+        add(&conn, DAY + HOUR, 20, BreakOutcome::Completed);
+        let accidental = add(&conn, DAY + DAY_MS + HOUR, 1, BreakOutcome::Skipped);
+        add(&conn, DAY + 2 * DAY_MS + HOUR, 20, BreakOutcome::Completed);
+        for day in ["2024-01-15", "2024-01-16", "2024-01-17"] {
+            db::recompute_daily_stats(&conn, day).unwrap();
+        }
+        let best = |conn: &Connection| {
+            crate::analytics::build_analytics_summary(conn, 1, &Workdays::ALL)
+                .unwrap()
+                .best_day_streak
+        };
+        assert_eq!(best(&conn), 1);
+
+        let edit = BreakEdit {
+            outcome: Some(BreakOutcome::Completed),
+            duration_seconds: Some(20),
+            note: Some("clicked skip by accident".to_string()),
+        };
+        let record = edit_record(&conn, accidental, &edit).unwrap();
+        assert!(record.completed && !record.skipped);
+        assert_eq!(record.note.as_deref(), Some("clicked skip by accident"));
+
+        let day = db::get_daily_stats_range(&conn, "2024-01-16", "2024-01-16").unwrap();
+        assert_eq!((day[0].breaks_completed, day[0].breaks_skipped), (1, 0));
+        assert_eq!(best(&conn), 3);
+
+        let edits = get_edits(&conn, Some(accidental)).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].action, "edit");
+        assert_eq!(edits[0].old_outcome, Some(BreakOutcome::Skipped));
+        assert_eq!(edits[0].new_outcome, Some(BreakOutcome::Completed));
+        assert_eq!(
+            (edits[0].old_duration_seconds, edits[0].new_duration_seconds),
+            (Some(1), Some(20))
+        );
+    }
+
+    #[test]
+    fn test_notes_and_no_op_edits() {
+        let conn = setup_test_db();
+        let id = add(&conn, DAY, 20, BreakOutcome::Completed);
+
+        let unchanged = edit_record(&conn, id, &BreakEdit::default()).unwrap();
+        assert_eq!(unchanged.note, None);
+        assert!(get_edits(&conn, None).unwrap().is_empty());
+
+        let annotate = BreakEdit {
+            note: Some("  stretched  ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            edit_record(&conn, id, &annotate).unwrap().note.as_deref(),
+            Some("stretched")
+        );
+        let clear = BreakEdit {
+            note: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(edit_record(&conn, id, &clear).unwrap().note, None);
+        assert_eq!(get_edits(&conn, Some(id)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_delete_recomputes_and_is_audited() {
+        let conn = setup_test_db();
+        let keep = add(&conn, DAY + HOUR, 20, BreakOutcome::Completed);
+        let wrong = add(&conn, DAY + 2 * HOUR, 20, BreakOutcome::Completed);
+        db::recompute_daily_stats(&conn, "2024-01-15").unwrap();
+
+        delete_record(&conn, wrong).unwrap();
+        let ids: Vec<i64> = db::get_all_break_records(&conn)
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![keep]);
+        let day = db::get_daily_stats_range(&conn, "2024-01-15", "2024-01-15").unwrap();
+        assert_eq!(day[0].breaks_completed, 1);
+
+        let edits = get_edits(&conn, None).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].action, "delete");
+        assert_eq!(edits[0].break_started_at, DAY + 2 * HOUR);
+        assert_eq!(edits[0].new_outcome, None);
+    }
+
+    #[test]
+    fn test_running_and_missing_breaks_cannot_be_changed() {
+        let conn = setup_test_db();
+        let running = db::insert_break_record(&conn, DAY, 1200).unwrap();
+        assert!(edit_record(&conn, running, &BreakEdit::default()).is_err());
+        assert!(delete_record(&conn, running).is_err());
+        assert!(delete_record(&conn, 999).is_err());
+        assert!(get_edits(&conn, None).unwrap().is_empty());
+    }
+}
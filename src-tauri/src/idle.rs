@@ -4,6 +4,112 @@ pub fn get_idle_seconds() -> Option<u64> {
     platform::get_idle_seconds()
 }
 
+/// Input must keep arriving for this long before a suspended timer resumes,
+/// so a single bumped mouse doesn't flap between Suspended and Working.
+pub const RESUME_HYSTERESIS_SECS: u64 = 5;
+
+/// Poll interval while idle — short, so a returning user is noticed quickly.
+const IDLE_POLL_SECS: u64 = 1;
+
+/// Upper bound on the poll interval while active or when detection is disabled.
+pub const MAX_POLL_SECS: u64 = 30;
+
+/// Idle edges reported by the idle monitor to the timer loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdleEvent {
+    /// No input for at least the configured threshold.
+    /// `idle_secs` is the idle time when the edge was detected.
+    BecameIdle { idle_secs: u64 },
+    /// Input has been arriving steadily for `RESUME_HYSTERESIS_SECS`.
+    BecameActive,
+}
+
+/// Turns raw idle-time samples into debounced `IdleEvent`s.
+#[derive(Debug, Default)]
+pub struct IdleTracker {
+    is_idle: bool,
+    /// Wall-clock ms of the first input seen since going idle.
+    active_since: Option<u64>,
+}
+
+impl IdleTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one idle-time sample. Returns an event only on a state change.
+    pub fn observe(
+        &mut self,
+        threshold_secs: u64,
+        idle_secs: u64,
+        now_ms: u64,
+    ) -> Option<IdleEvent> {
+        if !self.is_idle {
+            if idle_secs >= threshold_secs {
+                self.is_idle = true;
+                self.active_since = None;
+                return Some(IdleEvent::BecameIdle { idle_secs });
+            }
+            return None;
+        }
+
+        // Input arriving recently means the user may be back; anything older
+        // was a blip followed by silence, so start over.
+        if idle_secs >= RESUME_HYSTERESIS_SECS {
+            self.active_since = None;
+            return None;
+        }
+
+        let last_input_ms = now_ms.saturating_sub(idle_secs * 1000);
+        let active_since = *self.active_since.get_or_insert(last_input_ms);
+        if now_ms.saturating_sub(active_since) >= RESUME_HYSTERESIS_SECS * 1000 {
+            self.is_idle = false;
+            self.active_since = None;
+            return Some(IdleEvent::BecameActive);
+        }
+        None
+    }
+
+    /// Idle detection was turned off or is unavailable. Releases any suspension.
+    pub fn disable(&mut self) -> Option<IdleEvent> {
+        let was_idle = self.is_idle;
+        *self = Self::default();
+        was_idle.then_some(IdleEvent::BecameActive)
+    }
+
+    /// Seconds until the next sample is worth taking. While active, idle time
+    /// cannot reach the threshold any sooner than `threshold - idle`.
+    pub fn next_poll_secs(&self, threshold_secs: u64, idle_secs: u64) -> u64 {
+        if self.is_idle {
+            IDLE_POLL_SECS
+        } else {
+            threshold_secs
+                .saturating_sub(idle_secs)
+                .clamp(IDLE_POLL_SECS, MAX_POLL_SECS)
+        }
+    }
+}
+
+/// Work seconds to run after returning from an idle suspension.
+///
+/// `seconds_remaining` is what was left of the interval at the last input,
+/// `away_secs` is how long the user was gone, counted from that input. Every
+/// suspension is at least the idle threshold long, so "restart_if_long"
+/// compares against its own `long_away_secs` rather than the break length.
+pub fn work_seconds_after_idle(
+    policy: &str,
+    seconds_remaining: u64,
+    work_duration: u64,
+    away_secs: u64,
+    long_away_secs: u64,
+) -> u64 {
+    match policy {
+        "resume" => seconds_remaining,
+        "restart_if_long" if away_secs < long_away_secs => seconds_remaining,
+        _ => work_duration,
+    }
+}
+
 // ---- Linux: dynamically load libX11 + libXss to avoid compile-time dependencies ----
 
 #[cfg(target_os = "linux")]
@@ -106,6 +212,8 @@ mod platform {
             }
 
             let status = (fns.query_info)(display, root, info);
+            // c_ulong is only 32 bits on some targets
+            #[allow(clippy::unnecessary_cast)]
             let idle_ms = if status != 0 {
                 Some((*info).idle as u64)
             } else {
@@ -194,3 +302,110 @@ mod platform {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: u64 = 300;
+
+    /// Drive a tracker that has already gone idle.
+    fn idle_tracker() -> IdleTracker {
+        let mut tracker = IdleTracker::new();
+        tracker.observe(THRESHOLD, THRESHOLD, 0);
+        tracker
+    }
+
+    #[test]
+    fn test_becomes_idle_at_threshold() {
+        let mut tracker = IdleTracker::new();
+        assert_eq!(tracker.observe(THRESHOLD, THRESHOLD - 1, 0), None);
+        assert_eq!(
+            tracker.observe(THRESHOLD, THRESHOLD, 1000),
+            Some(IdleEvent::BecameIdle {
+                idle_secs: THRESHOLD
+            })
+        );
+        // Already idle — no repeated edge
+        assert_eq!(tracker.observe(THRESHOLD, THRESHOLD + 10, 11_000), None);
+    }
+
+    #[test]
+    fn test_single_blip_does_not_resume() {
+        let mut tracker = idle_tracker();
+        let t0 = 1_000_000;
+
+        // One mouse bump at t0, then silence
+        assert_eq!(tracker.observe(THRESHOLD, 0, t0), None);
+        assert_eq!(tracker.observe(THRESHOLD, 2, t0 + 2000), None);
+        assert_eq!(tracker.observe(THRESHOLD, 4, t0 + 4000), None);
+        assert_eq!(tracker.observe(THRESHOLD, 6, t0 + 6000), None);
+        assert_eq!(tracker.observe(THRESHOLD, 8, t0 + 8000), None);
+    }
+
+    #[test]
+    fn test_sustained_input_resumes() {
+        let mut tracker = idle_tracker();
+        let t0 = 1_000_000;
+
+        for i in 0..RESUME_HYSTERESIS_SECS {
+            assert_eq!(tracker.observe(THRESHOLD, 0, t0 + i * 1000), None);
+        }
+        assert_eq!(
+            tracker.observe(THRESHOLD, 0, t0 + RESUME_HYSTERESIS_SECS * 1000),
+            Some(IdleEvent::BecameActive)
+        );
+    }
+
+    #[test]
+    fn test_disable_releases_suspension() {
+        let mut tracker = idle_tracker();
+        assert_eq!(tracker.disable(), Some(IdleEvent::BecameActive));
+        assert_eq!(tracker.disable(), None);
+    }
+
+    #[test]
+    fn test_next_poll_secs() {
+        let mut tracker = IdleTracker::new();
+        // Far from threshold: capped
+        assert_eq!(tracker.next_poll_secs(THRESHOLD, 0), MAX_POLL_SECS);
+        // Close to threshold: sleep exactly until it could be reached
+        assert_eq!(tracker.next_poll_secs(THRESHOLD, THRESHOLD - 7), 7);
+        tracker.observe(THRESHOLD, THRESHOLD, 0);
+        assert_eq!(tracker.next_poll_secs(THRESHOLD, THRESHOLD), IDLE_POLL_SECS);
+    }
+
+    #[test]
+    fn test_resume_policies() {
+        let defaults = crate::state::UserSettings::default();
+        let work = defaults.work_interval_minutes as u64 * 60;
+        let remaining = 900;
+        let threshold = defaults.idle_pause_minutes as u64 * 60;
+        let long_away = defaults.idle_long_away_minutes as u64 * 60;
+
+        assert_eq!(
+            work_seconds_after_idle("resume", remaining, work, 3600, long_away),
+            900
+        );
+        assert_eq!(
+            work_seconds_after_idle("restart", remaining, work, threshold, long_away),
+            work
+        );
+        // Just past the idle threshold: a coffee refill, not a rest
+        assert_eq!(
+            work_seconds_after_idle(
+                "restart_if_long",
+                remaining,
+                work,
+                threshold + 30,
+                long_away
+            ),
+            900
+        );
+        // Away for the long-away time: counts as rest
+        assert_eq!(
+            work_seconds_after_idle("restart_if_long", remaining, work, long_away, long_away),
+            work
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use crate::analytics::local_midnight;
+use crate::db;
+use crate::state::{DailySummary, Insight, InsightEvidence};
+use crate::streaks::{self, GoalHistory, StreakRun, Workdays};
+use crate::trends;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use rusqlite::{params, Connection, Result as SqlResult};
+
+/// Whole local days of break records the hour and weekday rules look at.
+const INSIGHT_WINDOW_DAYS: i64 = 28;
+
+/// Fewer breaks than this in a bucket and a rate says nothing.
+const MIN_SAMPLE: u32 = 10;
+
+/// Breaks in a bucket at which a finding counts at full strength.
+const CONFIDENT_SAMPLE: u32 = 30;
+
+/// A streak ending on a day with fewer skips than this isn't worth a mention.
+const STREAK_BREAK_MIN_SKIPS: u32 = 3;
+
+const MAX_INSIGHTS: usize = 5;
+
+const WEEKDAYS: [&str; 7] = [
+    "Mondays",
+    "Tuesdays",
+    "Wednesdays",
+    "Thursdays",
+    "Fridays",
+    "Saturdays",
+    "Sundays",
+];
+
+/// Findings from every rule, strongest first.
+pub fn build_insights(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+) -> SqlResult<Vec<Insight>> {
+    build_insights_at(conn, daily_goal, workdays, &Local::now())
+}
+
+pub fn build_insights_at<Tz: TimeZone>(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    now: &DateTime<Tz>,
+) -> SqlResult<Vec<Insight>> {
+    let tz = now.timezone();
+    let today = now.date_naive();
+    let breaks = local_breaks(
+        conn,
+        &tz,
+        local_midnight(&tz, today - Duration::days(INSIGHT_WINDOW_DAYS)),
+        local_midnight(&tz, today),
+    )?;
+    // daily_stats_cache is keyed by UTC day
+    let utc_today = now.with_timezone(&Utc).date_naive();
+
+    let mut insights: Vec<Insight> = [
+        skip_window(&breaks),
+        weekday_dip(&breaks),
+        weekly_change(conn, utc_today)?,
+        streak_break(conn, daily_goal, workdays, utc_today)?,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    insights.sort_by(|a, b| b.score.total_cmp(&a.score));
+    insights.truncate(MAX_INSIGHTS);
+    Ok(insights)
+}
+
+/// Yesterday's totals and the current insights: None if that day was already
+/// summarized or had no breaks at all. Only reads; `mark_summarized` records
+/// the summary once it's out.
+pub fn daily_summary<Tz: TimeZone>(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    now: &DateTime<Tz>,
+) -> SqlResult<Option<DailySummary>> {
+    let day = now.with_timezone(&Utc).date_naive() - Duration::days(1);
+    let date = day.format("%Y-%m-%d").to_string();
+    let last: Option<String> = conn.query_row(
+        "SELECT last_summarized FROM daily_summary_state WHERE id = 1",
+        [],
+        |row| row.get(0),
+    )?;
+    if last.is_some_and(|last| last >= date) {
+        return Ok(None);
+    }
+
+    let Some(stats) = db::get_daily_stats_range(conn, &date, &date)?.pop() else {
+        return Ok(None);
+    };
+    if stats.breaks_completed + stats.breaks_skipped == 0 {
+        return Ok(None);
+    }
+    Ok(Some(DailySummary {
+        date,
+        breaks_completed: stats.breaks_completed,
+        breaks_skipped: stats.breaks_skipped,
+        insights: build_insights_at(conn, daily_goal, workdays, now)?,
+    }))
+}
+
+/// Record that the summary for `date` went out, so it isn't sent again.
+pub fn mark_summarized(conn: &Connection, date: &str) -> SqlResult<()> {
+    conn.execute(
+        "UPDATE daily_summary_state SET last_summarized = ?1
+         WHERE id = 1 AND (last_summarized IS NULL OR last_summarized < ?1)",
+        params![date],
+    )?;
+    Ok(())
+}
+
+/// Completed and skipped breaks started in `[from_ms, to_ms)`, by local start
+/// time; true for skipped.
+fn local_breaks<Tz: TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    from_ms: i64,
+    to_ms: i64,
+) -> SqlResult<Vec<(NaiveDateTime, bool)>> {
+    let mut stmt = conn.prepare(
+        "SELECT started_at, outcome = 'skipped' FROM break_records
+         WHERE started_at >= ?1 AND started_at < ?2 AND outcome IN ('completed', 'skipped')",
+    )?;
+    let rows = stmt.query_map(params![from_ms, to_ms], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?))
+    })?;
+    let mut breaks = Vec::new();
+    for row in rows {
+        let (started_at, skipped) = row?;
+        if let Some(local) = tz.timestamp_millis_opt(started_at).single() {
+            breaks.push((local.naive_local(), skipped));
+        }
+    }
+    Ok(breaks)
+}
+
+/// The two-hour window where breaks get skipped far more than at other times.
+fn skip_window(breaks: &[(NaiveDateTime, bool)]) -> Option<Insight> {
+    // (breaks, skipped) per local hour
+    let mut hours = [(0u32, 0u32); 24];
+    for (at, skipped) in breaks {
+        let hour = &mut hours[at.hour() as usize];
+        hour.0 += 1;
+        hour.1 += *skipped as u32;
+    }
+    let (total, total_skipped) = hours
+        .iter()
+        .fold((0, 0), |(n, s), (hn, hs)| (n + hn, s + hs));
+
+    let mut best: Option<Insight> = None;
+    for start in 0..23 {
+        let n = hours[start].0 + hours[start + 1].0;
+        let skipped = hours[start].1 + hours[start + 1].1;
+        let (other_n, other_skipped) = (total - n, total_skipped - skipped);
+        if n < MIN_SAMPLE || other_n == 0 {
+            continue;
+        }
+        let rate = skipped as f64 / n as f64;
+        let other_rate = other_skipped as f64 / other_n as f64;
+        if rate < 0.5 || rate - other_rate < 0.2 {
+            continue;
+        }
+        let score = (rate - other_rate) * confidence(n);
+        if best.as_ref().is_some_and(|b| b.score >= score) {
+            continue;
+        }
+        best = Some(Insight {
+            rule: "skip_window".to_string(),
+            message: format!(
+                "You skip {} of breaks between {:02}:00–{:02}:00",
+                percent(rate),
+                start,
+                (start + 2) % 24
+            ),
+            evidence: vec![
+                evidence("Skipped in this window", format!("{} of {}", skipped, n)),
+                evidence("Skip rate at other times", percent(other_rate)),
+            ],
+            score,
+        });
+    }
+    best
+}
+
+/// The local weekday where compliance falls well below the rest of the week.
+fn weekday_dip(breaks: &[(NaiveDateTime, bool)]) -> Option<Insight> {
+    // (breaks, completed) per weekday, Monday first
+    let mut days = [(0u32, 0u32); 7];
+    for (at, skipped) in breaks {
+        let day = &mut days[at.weekday().num_days_from_monday() as usize];
+        day.0 += 1;
+        day.1 += !*skipped as u32;
+    }
+    let (total, total_completed) = days
+        .iter()
+        .fold((0, 0), |(n, c), (dn, dc)| (n + dn, c + dc));
+
+    let mut best: Option<Insight> = None;
+    for (weekday, &(n, completed)) in days.iter().enumerate() {
+        let (other_n, other_completed) = (total - n, total_completed - completed);
+        if n < MIN_SAMPLE || other_n < MIN_SAMPLE {
+            continue;
+        }
+        let rate = completed as f64 / n as f64;
+        let other_rate = other_completed as f64 / other_n as f64;
+        if other_rate - rate < 0.15 {
+            continue;
+        }
+        let score = (other_rate - rate) * confidence(n);
+        if best.as_ref().is_some_and(|b| b.score >= score) {
+            continue;
+        }
+        best = Some(Insight {
+            rule: "weekday_dip".to_string(),
+            message: format!(
+                "Compliance drops on {}: {} vs {} on other days",
+                WEEKDAYS[weekday],
+                percent(rate),
+                percent(other_rate)
+            ),
+            evidence: vec![
+                evidence(
+                    &format!("Completed on {}", WEEKDAYS[weekday]),
+                    format!("{} of {}", completed, n),
+                ),
+                evidence(
+                    "Completed on other days",
+                    format!("{} of {}", other_completed, other_n),
+                ),
+            ],
+            score,
+        });
+    }
+    best
+}
+
+/// A big swing in completed breaks over the last 7 whole days against the 7
+/// before.
+fn weekly_change(conn: &Connection, today: NaiveDate) -> SqlResult<Option<Insight>> {
+    let days = db::get_daily_stats_range(
+        conn,
+        &(today - Duration::days(14)).format("%Y-%m-%d").to_string(),
+        &(today - Duration::days(1)).format("%Y-%m-%d").to_string(),
+    )?;
+    let (previous, current) = days.split_at(days.len().saturating_sub(7));
+    let sum = |days: &[crate::state::DailyStats]| days.iter().map(|d| d.breaks_completed).sum();
+    let delta = trends::delta(sum(current), sum(previous));
+
+    let Some(rate) = delta.change_rate else {
+        return Ok(None);
+    };
+    if delta.previous < MIN_SAMPLE || rate.abs() < 0.25 {
+        return Ok(None);
+    }
+    Ok(Some(Insight {
+        rule: "weekly_change".to_string(),
+        message: format!(
+            "You took {} {} breaks in the last 7 days than the week before",
+            percent(rate.abs()),
+            if rate < 0.0 { "fewer" } else { "more" }
+        ),
+        evidence: vec![
+            evidence("Last 7 days", delta.current.to_string()),
+            evidence("The 7 days before", delta.previous.to_string()),
+        ],
+        score: rate.abs().min(1.0),
+    }))
+}
+
+/// Whether the longest streak that has ended did so on a day full of skips.
+fn streak_break(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    today: NaiveDate,
+) -> SqlResult<Option<Insight>> {
+    let Some(first) = streaks::first_day(conn)? else {
+        return Ok(None);
+    };
+    let yesterday = today - Duration::days(1);
+    let goals = GoalHistory::load(conn, daily_goal)?;
+    let completed = streaks::completed_between(conn, first, yesterday)?;
+    let frozen = streaks::frozen_days(conn)?;
+
+    // (length, day it ended) of the longest run that has ended, latest on ties
+    let mut longest_ended: Option<(u32, NaiveDate)> = None;
+    let mut run = StreakRun::default();
+    let mut day = first;
+    while day <= yesterday {
+        let before = run.current;
+        run.advance(&goals, workdays, &completed, &frozen, day, day);
+        if before > 0 && run.current == 0 && longest_ended.is_none_or(|(len, _)| before >= len) {
+            longest_ended = Some((before, day));
+        }
+        day += Duration::days(1);
+    }
+
+    // The longest streak is still going
+    let Some((length, ended)) = longest_ended.filter(|(len, _)| *len > run.current) else {
+        return Ok(None);
+    };
+    let date = ended.format("%Y-%m-%d").to_string();
+    let Some(stats) = db::get_daily_stats_range(conn, &date, &date)?.pop() else {
+        return Ok(None);
+    };
+    if length < 2 || stats.breaks_skipped < STREAK_BREAK_MIN_SKIPS {
+        return Ok(None);
+    }
+    Ok(Some(Insight {
+        rule: "streak_break".to_string(),
+        message: format!(
+            "Your longest streak ({} days) ended on {} with {} skips",
+            length,
+            ended.format("%a %-d %b"),
+            stats.breaks_skipped
+        ),
+        evidence: vec![
+            evidence("Streak length", format!("{} days", length)),
+            evidence("Completed that day", stats.breaks_completed.to_string()),
+            evidence("Goal that day", goals.goal_on(ended).to_string()),
+            evidence("Skipped that day", stats.breaks_skipped.to_string()),
+        ],
+        score: (stats.breaks_skipped as f64 / 10.0).min(1.0),
+    }))
+}
+
+fn confidence(n: u32) -> f64 {
+    (n as f64 / CONFIDENT_SAMPLE as f64).min(1.0)
+}
+
+fn percent(rate: f64) -> String {
+    format!("{}%", (rate * 100.0).round())
+}
+
+fn evidence(label: &str, value: String) -> InsightEvidence {
+    InsightEvidence {
+        label: label.to_string(),
+        value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{finish_break_record, init_db_conn, insert_break_record};
+    use crate::state::BreakOutcome;
+    use chrono::FixedOffset;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn tz() -> FixedOffset {
+        FixedOffset::east_opt(2 * 3600).unwrap()
+    }
+
+    fn add(conn: &Connection, day: u32, hour: u32, minute: u32, outcome: BreakOutcome) {
+        let at = tz()
+            .with_ymd_and_hms(2024, 4, day, hour, minute, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        let id = insert_break_record(conn, at, 1200).unwrap();
+        finish_break_record(conn, id, 20, outcome, None, None).unwrap();
+    }
+
+    fn recompute(conn: &Connection, days: std::ops::RangeInclusive<u32>) {
+        for day in days {
+            db::recompute_daily_stats(conn, &format!("2024-04-{:02}", day)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_finds_the_afternoon_skip_window() {
+        let conn = setup_test_db();
+        // Ten weekdays: mornings completed, 14:00–16:00 mostly skipped
+        for day in 1..=10 {
+            add(&conn, day, 9, 0, BreakOutcome::Completed);
+            add(&conn, day, 10, 0, BreakOutcome::Completed);
+            add(&conn, day, 14, 0, BreakOutcome::Skipped);
+            let afternoon = if day % 3 == 0 {
+                BreakOutcome::Completed
+            } else {
+                BreakOutcome::Skipped
+            };
+            add(&conn, day, 15, 30, afternoon);
+        }
+        recompute(&conn, 1..=10);
+
+        let now = tz().with_ymd_and_hms(2024, 4, 11, 12, 0, 0).unwrap();
+        let insights = build_insights_at(&conn, 4, &Workdays::ALL, &now).unwrap();
+        let window = insights.iter().find(|i| i.rule == "skip_window").unwrap();
+        assert_eq!(window.message, "You skip 85% of breaks between 14:00–16:00");
+        assert_eq!(window.evidence[0].value, "17 of 20");
+        assert_eq!(window.evidence[1].value, "0%");
+        assert!(insights.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_finds_the_weekday_dip() {
+        let conn = setup_test_db();
+        // 2024-04-01 is a Monday. Four weeks: Mondays half skipped
+        for week in 0..4 {
+            for weekday in 0..5 {
+                let day = 1 + week * 7 + weekday;
+                for hour in [9, 11, 13, 15] {
+                    let outcome = if weekday == 0 && hour >= 13 {
+                        BreakOutcome::Skipped
+                    } else {
+                        BreakOutcome::Completed
+                    };
+                    add(&conn, day, hour, 0, outcome);
+                }
+            }
+        }
+
+        let now = tz().with_ymd_and_hms(2024, 4, 29, 8, 0, 0).unwrap();
+        let insights = build_insights_at(&conn, 4, &Workdays::ALL, &now).unwrap();
+        let dip = insights.iter().find(|i| i.rule == "weekday_dip").unwrap();
+        assert_eq!(
+            dip.message,
+            "Compliance drops on Mondays: 50% vs 100% on other days"
+        );
+        assert_eq!(dip.evidence[0].value, "8 of 16");
+    }
+
+    #[test]
+    fn test_longest_streak_ending_with_skips() {
+        let conn = setup_test_db();
+        // Met the goal of 2 on Apr 1–3, then 1 completed and 4 skipped on the 4th
+        for day in 1..=3 {
+            add(&conn, day, 9, 0, BreakOutcome::Completed);
+            add(&conn, day, 10, 0, BreakOutcome::Completed);
+        }
+        add(&conn, 4, 9, 0, BreakOutcome::Completed);
+        for hour in 10..14 {
+            add(&conn, 4, hour, 0, BreakOutcome::Skipped);
+        }
+        recompute(&conn, 1..=4);
+
+        let insight = streak_break(
+            &conn,
+            2,
+            &Workdays::ALL,
+            NaiveDate::from_ymd_opt(2024, 4, 6).unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            insight.message,
+            "Your longest streak (3 days) ended on Thu 4 Apr with 4 skips"
+        );
+
+        // Still going: nothing to say
+        for day in 5..=8 {
+            add(&conn, day, 9, 0, BreakOutcome::Completed);
+            add(&conn, day, 10, 0, BreakOutcome::Completed);
+        }
+        recompute(&conn, 5..=8);
+        let later = NaiveDate::from_ymd_opt(2024, 4, 9).unwrap();
+        assert_eq!(streak_break(&conn, 2, &Workdays::ALL, later).unwrap(), None);
+    }
+
+    #[test]
+    fn test_daily_summary_is_sent_once_per_day() {
+        let conn = setup_test_db();
+        add(&conn, 10, 9, 0, BreakOutcome::Completed);
+        add(&conn, 10, 10, 0, BreakOutcome::Skipped);
+        recompute(&conn, 10..=10);
+
+        let now = tz().with_ymd_and_hms(2024, 4, 11, 9, 0, 0).unwrap();
+        let summary = daily_summary(&conn, 4, &Workdays::ALL, &now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.date, "2024-04-10");
+        assert_eq!((summary.breaks_completed, summary.breaks_skipped), (1, 1));
+        // Not done until it's marked, e.g. if sending it never happened
+        assert!(daily_summary(&conn, 4, &Workdays::ALL, &now)
+            .unwrap()
+            .is_some());
+        mark_summarized(&conn, &summary.date).unwrap();
+        assert_eq!(daily_summary(&conn, 4, &Workdays::ALL, &now).unwrap(), None);
+
+        // A day without breaks isn't announced
+        let next_day = tz().with_ymd_and_hms(2024, 4, 13, 9, 0, 0).unwrap();
+        assert_eq!(
+            daily_summary(&conn, 4, &Workdays::ALL, &next_day).unwrap(),
+            None
+        );
+    }
+}
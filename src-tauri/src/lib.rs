@@ -1,18 +1,39 @@
+mod achievements;
+mod aggregates;
 mod analytics;
+mod archive;
 mod audio;
 mod autostart;
+mod backup;
 mod commands;
+mod coverage;
+mod data_dir;
 mod db;
+mod db_writer;
+mod export;
+mod history;
 mod idle;
+mod insights;
+mod maintenance;
+mod media;
+mod meeting;
 mod notifications;
 mod onboarding;
 mod overlay;
+mod recovery;
+mod report;
+mod retention;
 mod settings;
 pub mod state;
+mod streaks;
+mod timeline;
 mod timer;
 mod tray;
+mod trends;
 
-use state::{AppState, DbConnection, TimerInternalState, TimerPhase, TimerState};
+use db_writer::DbWriter;
+use state::{AppState, DataLocation, DbConnection, TimerInternalState, TimerPhase, TimerState};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{Manager, WindowEvent};
 
@@ -26,23 +47,27 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .setup(|app| {
             // Initialize database
-            let db_dir = db::get_db_dir();
-            std::fs::create_dir_all(&db_dir).expect("Failed to create DB directory");
+            let location = resolve_data_location();
+            let db_dir = PathBuf::from(&location.dir);
+            if let Err(e) = std::fs::create_dir_all(&db_dir) {
+                eprintln!("[setup] Failed to create DB directory: {}", e);
+            }
             let db_path = db_dir.join("blinky.db");
             let db_path_str = db_path.to_string_lossy().to_string();
-            let db_mutex = db::init_db(&db_path_str).expect("Failed to initialize database");
+            let (conn, recovery_report) = recovery::open_database(&db_path);
+            let db_is_temporary = recovery_report
+                .as_ref()
+                .is_some_and(|r| r.outcome == "temporary");
 
-            // Load settings from DB
-            let settings = {
-                let conn = db_mutex.lock().unwrap();
-                db::load_settings(&conn).unwrap_or_default()
-            };
+            // Close out any break the last session never finished, then load
+            // settings from DB
+            if let Err(e) = db::mark_interrupted_breaks(&conn) {
+                eprintln!("[setup] Failed to close out interrupted breaks: {}", e);
+            }
+            let settings = db::load_settings(&conn).unwrap_or_default();
 
             // Count today's completed breaks for initial state
-            let breaks_today = {
-                let conn = db_mutex.lock().unwrap();
-                db::count_breaks_today(&conn).unwrap_or(0)
-            };
+            let breaks_today = db::count_breaks_today(&conn).unwrap_or(0);
 
             let now_ms = chrono::Utc::now().timestamp_millis() as u64;
             let work_duration = settings.work_interval_minutes as u64 * 60;
@@ -54,6 +79,22 @@ pub fn run() {
                 TimerPhase::Paused
             };
 
+            let logged = timeline::close_dangling(&conn)
+                .and_then(|_| timeline::record_phase(&conn, &initial_phase, now_ms));
+            if let Err(e) = logged {
+                eprintln!("[setup] Failed to start phase timeline: {}", e);
+            }
+
+            // From here on the write connection belongs to the writer thread;
+            // queries use a separate read-only connection
+            let reader_path = if db_is_temporary {
+                recovery::TEMPORARY_DB_URI.to_string()
+            } else {
+                db_path_str.clone()
+            };
+            let reader = db::open_reader(&reader_path)?;
+            let writer = DbWriter::spawn(conn);
+
             let timer_state = TimerState {
                 phase: initial_phase,
                 seconds_remaining: work_duration,
@@ -64,19 +105,27 @@ pub fn run() {
 
             let timer_internal = TimerInternalState {
                 phase_before_pause: TimerPhase::Working,
-                current_break_record_id: None,
+                current_break_started_at: None,
                 work_started_at: now_ms,
+                idle_since: 0,
+                away: false,
+                deferral: None,
+                phase_seen_at: now_ms,
             };
 
             let app_state = AppState {
                 timer: Mutex::new(timer_state),
                 settings: Mutex::new(settings),
                 db_path: db_path_str,
+                data_location: location,
                 timer_internal: Mutex::new(timer_internal),
+                recovery: Mutex::new(recovery_report),
+                db_is_temporary,
             };
 
             app.manage(app_state);
-            app.manage(DbConnection(db_mutex));
+            app.manage(DbConnection(Mutex::new(reader)));
+            app.manage(writer);
 
             // Create the system tray
             let tray_state = tray::create_tray(app.handle()).expect("Failed to create system tray");
@@ -85,6 +134,9 @@ pub fn run() {
             // Start the background timer loop
             timer::start_timer_loop(app.handle());
 
+            // Start housekeeping (scheduled backups)
+            maintenance::start_maintenance_loop(app.handle());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -92,14 +144,39 @@ pub fn run() {
             commands::pause_timer,
             commands::resume_timer,
             commands::skip_break,
+            commands::snooze_break,
             commands::reset_timer,
             commands::get_settings,
+            commands::get_settings_history,
             commands::update_settings,
             commands::get_analytics_summary,
+            commands::get_achievements,
+            commands::get_coverage,
+            commands::get_hourly_heatmap,
+            commands::get_insights,
+            commands::get_skip_reasons,
+            commands::generate_report,
             commands::get_break_history,
+            commands::query_break_history,
+            commands::edit_break_record,
+            commands::delete_break_record,
+            commands::get_break_record_edits,
             commands::get_daily_stats_range,
+            commands::get_deferral_summary,
             commands::export_data_csv,
+            commands::export_daily_stats_csv,
+            commands::export_archive,
+            commands::import_archive,
+            commands::compact_now,
+            commands::get_period_stats,
+            commands::get_timeline,
+            commands::get_data_location,
             commands::clear_all_data,
+            commands::rebuild_aggregates,
+            commands::get_recovery_report,
+            commands::dismiss_recovery_report,
+            commands::list_backups,
+            commands::restore_backup,
             commands::get_onboarding_state,
             commands::complete_onboarding,
             commands::mark_tooltip_seen,
@@ -116,3 +193,60 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Work out where this session's data lives. An explicitly chosen directory
+/// (`--data-dir` or `BLINKY_DATA_DIR`) takes the data along from wherever it
+/// lived before and is remembered for later launches. If that move fails, the
+/// old directory stays in use. Portable mode stays self-contained and leaves
+/// the host untouched.
+fn resolve_data_location() -> DataLocation {
+    let default = data_dir::default_dir();
+    let cli = data_dir::parse_args(&std::env::args().collect::<Vec<_>>());
+    let env = std::env::var(data_dir::DATA_DIR_ENV).ok();
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let remembered = data_dir::read_remembered(&default);
+
+    let location = data_dir::resolve(
+        &cli,
+        env.as_deref(),
+        exe_dir.as_deref(),
+        remembered.as_deref(),
+        &default,
+    );
+
+    if location.source == "cli" || location.source == "env" {
+        let dir = PathBuf::from(&location.dir);
+        let previous = remembered
+            .filter(|d| d.is_dir())
+            .unwrap_or_else(|| default.clone());
+        match data_dir::migrate(&previous, &dir) {
+            Ok(true) => eprintln!(
+                "[setup] Moved data from {} to {}",
+                previous.display(),
+                dir.display()
+            ),
+            Ok(false) => {}
+            Err(e) => {
+                // The old directory is still whole; stay there rather than
+                // start over with an empty database
+                eprintln!("[setup] Failed to move data to {}: {}", dir.display(), e);
+                let source = if previous == default {
+                    "default"
+                } else {
+                    "remembered"
+                };
+                return DataLocation {
+                    dir: previous.to_string_lossy().to_string(),
+                    source: source.to_string(),
+                };
+            }
+        }
+        if let Err(e) = data_dir::remember(&default, &dir) {
+            eprintln!("[setup] Failed to remember data directory: {}", e);
+        }
+    }
+
+    location
+}
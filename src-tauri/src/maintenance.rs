@@ -0,0 +1,166 @@
+use crate::aggregates;
+use crate::db_writer::DbWriter;
+use crate::report::{ReportFormat, ReportPeriod};
+use crate::state::{AppState, DbConnection};
+use crate::streaks::Workdays;
+use chrono::{Local, Utc};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often housekeeping runs. Every job is idempotent within its own period,
+/// so running more often than needed is harmless.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Spawn the housekeeping loop. Runs once at startup, then hourly.
+/// Call this from `.setup()` in lib.rs.
+pub fn start_maintenance_loop(app: &AppHandle) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // Snapshotting or pruning a stand-in database would clobber real backups
+        if handle.state::<AppState>().db_is_temporary {
+            return;
+        }
+        loop {
+            run_backups(&handle).await;
+            run_retention(&handle);
+            run_rollover(&handle);
+            // Picks up unlocks that only settle once a day is over
+            crate::timer::check_achievements(&handle);
+            run_reports(&handle).await;
+            run_daily_summary(&handle).await;
+            tokio::time::sleep(MAINTENANCE_INTERVAL).await;
+        }
+    });
+}
+
+/// Take any due daily/weekly snapshots and apply retention.
+async fn run_backups(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().unwrap().clone();
+    let db_path = state.db_path.clone();
+    let dir = crate::backup::backups_dir(Path::new(&db_path));
+
+    // Snapshots only read, so they get a read connection of their own and
+    // never hold up queued writes or queries; the copy blocks, so it runs
+    // off the runtime
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let conn = crate::db::open_reader(&db_path)?;
+        crate::backup::run_scheduled_backups(
+            &conn,
+            &dir,
+            Utc::now().date_naive(),
+            settings.backup_daily_keep,
+            settings.backup_weekly_keep,
+        )
+    })
+    .await;
+    match result {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => eprintln!("[maintenance] Scheduled backup failed: {}", e),
+        Err(e) => eprintln!("[maintenance] Scheduled backup task failed: {}", e),
+    }
+}
+
+/// Roll up and prune raw break records older than the retention setting.
+/// Backups run first, so the latest snapshot still has the raw rows.
+fn run_retention(app: &AppHandle) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let Some(cutoff) = crate::retention::cutoff_date(
+        Utc::now().date_naive(),
+        settings.retention_amount,
+        &settings.retention_unit,
+    ) else {
+        return;
+    };
+
+    app.state::<DbWriter>().send(
+        move |conn| match crate::retention::prune_before(conn, cutoff) {
+            Ok(r) if r.records_pruned > 0 => eprintln!(
+                "[maintenance] Pruned {} break records across {} days before {}",
+                r.records_pruned, r.days_rolled_up, cutoff
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!("[maintenance] Retention failed: {}", e),
+        },
+    );
+}
+
+/// Settle the days that ended since the last run: earn and spend streak
+/// freezes, then fold the days into the stored totals and streaks.
+pub fn run_rollover(app: &AppHandle) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let workdays = Workdays::from_settings(&settings);
+    app.state::<DbWriter>().send(move |conn| {
+        if let Err(e) = aggregates::settle(
+            conn,
+            settings.daily_goal,
+            &workdays,
+            Utc::now().date_naive(),
+        ) {
+            eprintln!("[maintenance] Settling past days failed: {}", e);
+        }
+    });
+}
+
+/// Write last week's report if automatic reports are on and it isn't there
+/// yet. Runs on Monday, or on the first launch after it if Blinky was closed.
+async fn run_reports(app: &AppHandle) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    if !settings.report_auto_enabled {
+        return;
+    }
+    let format = ReportFormat::parse(&settings.report_format).unwrap_or(ReportFormat::Html);
+    let dir = crate::report::report_dir(&settings);
+    let today = Utc::now().date_naive();
+    if crate::report::report_path(&dir, ReportPeriod::Week, format, today).exists() {
+        return;
+    }
+
+    // Reads a week of history and writes a file, so like backups it uses its
+    // own read connection off the runtime
+    let db_path = app.state::<AppState>().db_path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let conn = crate::db::open_reader(&db_path).map_err(|e| e.to_string())?;
+        crate::report::generate(&conn, ReportPeriod::Week, format, today, &settings, &dir)
+    })
+    .await;
+    match result {
+        Ok(Ok(path)) => eprintln!("[maintenance] Wrote weekly report to {}", path.display()),
+        Ok(Err(e)) => eprintln!("[maintenance] Weekly report failed: {}", e),
+        Err(e) => eprintln!("[maintenance] Weekly report task failed: {}", e),
+    }
+}
+
+/// Send yesterday's summary with the current insights, once per day. Runs
+/// after the rollover, so the streak rules see yesterday settled.
+async fn run_daily_summary(app: &AppHandle) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    if !settings.daily_summary_enabled || !settings.notification_enabled {
+        return;
+    }
+    let workdays = Workdays::from_settings(&settings);
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        // Let the rollover queued ahead of this land before reading
+        handle.state::<DbWriter>().call(|_| ())?;
+        let db = handle.state::<DbConnection>();
+        let conn = db.0.lock().unwrap();
+        crate::insights::daily_summary(&conn, settings.daily_goal, &workdays, &Local::now())
+            .map_err(|e| e.to_string())
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(summary))) => {
+            crate::notifications::send_daily_summary_notification(app, &summary);
+            app.state::<DbWriter>().send(move |conn| {
+                if let Err(e) = crate::insights::mark_summarized(conn, &summary.date) {
+                    eprintln!("[maintenance] Failed to record daily summary: {}", e);
+                }
+            });
+        }
+        Ok(Ok(None)) => {}
+        Ok(Err(e)) => eprintln!("[maintenance] Daily summary failed: {}", e),
+        Err(e) => eprintln!("[maintenance] Daily summary task failed: {}", e),
+    }
+}
@@ -0,0 +1,221 @@
+/// MPRIS bus-name prefix every compliant media player registers under.
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// Object path and interface carrying `PlaybackStatus`.
+#[cfg(target_os = "linux")]
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+#[cfg(target_os = "linux")]
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// The slice of D-Bus that media detection needs. Lets tests substitute a fake bus.
+pub trait MediaBus {
+    /// All well-known names currently on the bus.
+    fn list_names(&self) -> Option<Vec<String>>;
+    /// `PlaybackStatus` of the MPRIS player at `bus_name` ("Playing", "Paused", "Stopped").
+    fn playback_status(&self, bus_name: &str) -> Option<String>;
+}
+
+/// Returns true if any allowed MPRIS player is currently playing.
+///
+/// `allowlist` holds player ids (the part after `org.mpris.MediaPlayer2.`,
+/// e.g. "vlc" or "firefox"). An empty allowlist allows every player.
+pub fn is_media_playing(bus: &dyn MediaBus, allowlist: &[String]) -> bool {
+    let Some(names) = bus.list_names() else {
+        return false;
+    };
+
+    names
+        .iter()
+        .filter_map(|name| name.strip_prefix(MPRIS_PREFIX).map(|id| (name, id)))
+        .filter(|(_, id)| allowlist.is_empty() || allowlist.iter().any(|a| player_matches(id, a)))
+        .any(|(name, _)| bus.playback_status(name).as_deref() == Some("Playing"))
+}
+
+/// Parse the `media_player_allowlist` setting (a JSON array of player ids).
+pub fn parse_allowlist(json: &str) -> Vec<String> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+/// Match a player id against an allowlist entry. Browsers and some players
+/// append an instance suffix (`firefox.instance_1_42`), so "firefox" matches it.
+fn player_matches(player_id: &str, entry: &str) -> bool {
+    player_id == entry
+        || player_id
+            .strip_prefix(entry)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Query the session bus for MPRIS players. Keeps one connection open for the
+/// lifetime of the monitor and reconnects if the bus goes away.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+pub struct SessionBus {
+    conn: std::sync::Mutex<Option<zbus::blocking::Connection>>,
+}
+
+#[cfg(target_os = "linux")]
+impl SessionBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn connection(&self) -> Option<zbus::blocking::Connection> {
+        let mut conn = self.conn.lock().unwrap();
+        if conn.is_none() {
+            match zbus::blocking::Connection::session() {
+                Ok(c) => *conn = Some(c),
+                Err(e) => {
+                    eprintln!("[media] Failed to connect to session bus: {}", e);
+                    return None;
+                }
+            }
+        }
+        conn.clone()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl MediaBus for SessionBus {
+    fn list_names(&self) -> Option<Vec<String>> {
+        let conn = self.connection()?;
+        let names = zbus::blocking::fdo::DBusProxy::new(&conn)
+            .ok()
+            .and_then(|proxy| proxy.list_names().ok());
+        match names {
+            Some(names) => Some(names.into_iter().map(|n| n.to_string()).collect()),
+            None => {
+                // Drop the connection so the next poll reconnects
+                *self.conn.lock().unwrap() = None;
+                None
+            }
+        }
+    }
+
+    fn playback_status(&self, bus_name: &str) -> Option<String> {
+        let conn = self.connection()?;
+        let proxy = zbus::blocking::Proxy::new(
+            &conn,
+            bus_name.to_string(),
+            MPRIS_PATH,
+            MPRIS_PLAYER_INTERFACE,
+        )
+        .ok()?;
+        proxy.get_property::<String>("PlaybackStatus").ok()
+    }
+}
+
+/// MPRIS is a Linux/freedesktop interface; elsewhere there are no players to find.
+#[cfg(not(target_os = "linux"))]
+#[derive(Default)]
+pub struct SessionBus;
+
+#[cfg(not(target_os = "linux"))]
+impl SessionBus {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl MediaBus for SessionBus {
+    fn list_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn playback_status(&self, _bus_name: &str) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// In-memory stand-in for the session bus.
+    struct FakeBus {
+        players: HashMap<String, String>,
+        other_names: Vec<String>,
+    }
+
+    impl FakeBus {
+        fn new(players: &[(&str, &str)]) -> Self {
+            Self {
+                players: players
+                    .iter()
+                    .map(|(id, status)| (format!("{}{}", MPRIS_PREFIX, id), status.to_string()))
+                    .collect(),
+                other_names: vec![
+                    "org.freedesktop.DBus".to_string(),
+                    "org.freedesktop.Notifications".to_string(),
+                ],
+            }
+        }
+    }
+
+    impl MediaBus for FakeBus {
+        fn list_names(&self) -> Option<Vec<String>> {
+            let mut names = self.other_names.clone();
+            names.extend(self.players.keys().cloned());
+            Some(names)
+        }
+
+        fn playback_status(&self, bus_name: &str) -> Option<String> {
+            self.players.get(bus_name).cloned()
+        }
+    }
+
+    struct NoBus;
+
+    impl MediaBus for NoBus {
+        fn list_names(&self) -> Option<Vec<String>> {
+            None
+        }
+
+        fn playback_status(&self, _bus_name: &str) -> Option<String> {
+            None
+        }
+    }
+
+    fn allow(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_players() {
+        let bus = FakeBus::new(&[]);
+        assert!(!is_media_playing(&bus, &[]));
+    }
+
+    #[test]
+    fn test_bus_unavailable() {
+        assert!(!is_media_playing(&NoBus, &[]));
+    }
+
+    #[test]
+    fn test_playing_player_with_empty_allowlist() {
+        let bus = FakeBus::new(&[("vlc", "Playing")]);
+        assert!(is_media_playing(&bus, &[]));
+    }
+
+    #[test]
+    fn test_paused_and_stopped_players_are_idle() {
+        let bus = FakeBus::new(&[("vlc", "Paused"), ("mpv", "Stopped")]);
+        assert!(!is_media_playing(&bus, &[]));
+    }
+
+    #[test]
+    fn test_allowlist_excludes_other_players() {
+        let bus = FakeBus::new(&[("spotify", "Playing"), ("vlc", "Paused")]);
+        assert!(!is_media_playing(&bus, &allow(&["vlc", "mpv"])));
+        assert!(is_media_playing(&bus, &allow(&["spotify"])));
+    }
+
+    #[test]
+    fn test_allowlist_matches_instance_suffix() {
+        let bus = FakeBus::new(&[("firefox.instance_1_42", "Playing")]);
+        assert!(is_media_playing(&bus, &allow(&["firefox"])));
+        // A prefix that isn't a whole id segment must not match
+        assert!(!is_media_playing(&bus, &allow(&["fire"])));
+    }
+}
@@ -0,0 +1,187 @@
+/// How long a deferred break waits before checking for the meeting again.
+pub const DEFER_RECHECK_SECS: u64 = 60;
+
+/// Which capture devices are in use right now.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeetingStatus {
+    pub camera: bool,
+    pub microphone: bool,
+}
+
+impl MeetingStatus {
+    /// Reason string stored with a deferral, or None if not in a meeting.
+    pub fn reason(&self) -> Option<&'static str> {
+        match (self.camera, self.microphone) {
+            (true, true) => Some("camera_and_microphone"),
+            (true, false) => Some("camera"),
+            (false, true) => Some("microphone"),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Probe camera and microphone usage. Only implemented on Linux for now;
+/// other platforms never report a meeting.
+pub fn detect() -> MeetingStatus {
+    platform::detect()
+}
+
+/// Whether a break that has been deferred since `deferred_since` may be deferred again.
+/// `max_defer_minutes` caps the total deferral so a marathon call can't swallow every break.
+pub fn within_defer_cap(deferred_since: Option<u64>, now_ms: u64, max_defer_minutes: u32) -> bool {
+    match deferred_since {
+        None => max_defer_minutes > 0,
+        Some(since) => now_ms.saturating_sub(since) < max_defer_minutes as u64 * 60_000,
+    }
+}
+
+/// True if any process under `proc_root` holds an open fd on a `/dev/video*` device.
+/// Processes we can't inspect (other users) are skipped.
+#[cfg(any(target_os = "linux", test))]
+fn camera_in_use(proc_root: &std::path::Path) -> bool {
+    let Ok(procs) = std::fs::read_dir(proc_root) else {
+        return false;
+    };
+
+    procs
+        .flatten()
+        .filter(|p| {
+            p.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .filter_map(|p| std::fs::read_dir(p.path().join("fd")).ok())
+        .flat_map(|fds| fds.flatten())
+        .filter_map(|fd| std::fs::read_link(fd.path()).ok())
+        .any(|target| target.to_string_lossy().starts_with("/dev/video"))
+}
+
+/// Parse `pactl list source-outputs short`: one line per active recording stream.
+#[cfg(any(target_os = "linux", test))]
+fn has_capture_streams(pactl_output: &str) -> bool {
+    pactl_output.lines().any(|line| !line.trim().is_empty())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::MeetingStatus;
+    use std::path::Path;
+    use std::process::Command;
+
+    pub fn detect() -> MeetingStatus {
+        MeetingStatus {
+            camera: super::camera_in_use(Path::new("/proc")),
+            microphone: microphone_in_use(),
+        }
+    }
+
+    /// Ask the PulseAudio server (or pipewire-pulse) for active capture streams.
+    fn microphone_in_use() -> bool {
+        match Command::new("pactl")
+            .args(["list", "source-outputs", "short"])
+            .output()
+        {
+            Ok(out) if out.status.success() => {
+                super::has_capture_streams(&String::from_utf8_lossy(&out.stdout))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::MeetingStatus;
+
+    pub fn detect() -> MeetingStatus {
+        MeetingStatus::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Build a throwaway /proc-like tree: pid → list of fd link targets.
+    fn fake_proc(name: &str, procs: &[(&str, &[&str])]) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("blinky_proc_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (pid, targets) in procs {
+            let fd_dir = root.join(pid).join("fd");
+            std::fs::create_dir_all(&fd_dir).unwrap();
+            for (i, target) in targets.iter().enumerate() {
+                std::os::unix::fs::symlink(target, fd_dir.join(i.to_string())).unwrap();
+            }
+        }
+        root
+    }
+
+    #[test]
+    fn test_reason() {
+        assert_eq!(MeetingStatus::default().reason(), None);
+        let cam = MeetingStatus {
+            camera: true,
+            microphone: false,
+        };
+        assert_eq!(cam.reason(), Some("camera"));
+        let both = MeetingStatus {
+            camera: true,
+            microphone: true,
+        };
+        assert_eq!(both.reason(), Some("camera_and_microphone"));
+    }
+
+    #[test]
+    fn test_camera_detected_from_fd() {
+        let root = fake_proc(
+            "cam",
+            &[
+                ("1", &["/dev/null", "socket:[1234]"]),
+                ("4242", &["/dev/pts/0", "/dev/video0"]),
+            ],
+        );
+        assert!(camera_in_use(&root));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_no_camera() {
+        let root = fake_proc(
+            "nocam",
+            &[("1", &["/dev/null"]), ("self-not-a-pid", &["/dev/video0"])],
+        );
+        assert!(!camera_in_use(&root));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_missing_proc_root() {
+        assert!(!camera_in_use(std::path::Path::new(
+            "/nonexistent/blinky/proc"
+        )));
+    }
+
+    #[test]
+    fn test_pactl_parsing() {
+        assert!(!has_capture_streams(""));
+        assert!(!has_capture_streams("\n"));
+        assert!(has_capture_streams(
+            "42\t1\t87\tprotocol-native.c\tfloat32le 1ch 48000Hz\n"
+        ));
+    }
+
+    #[test]
+    fn test_defer_cap() {
+        let now = 10_000_000;
+        // Not deferred yet: allowed unless deferral is disabled
+        assert!(within_defer_cap(None, now, 60));
+        assert!(!within_defer_cap(None, now, 0));
+        // 59 minutes in: still allowed
+        assert!(within_defer_cap(Some(now - 59 * 60_000), now, 60));
+        // Cap reached
+        assert!(!within_defer_cap(Some(now - 60 * 60_000), now, 60));
+    }
+}
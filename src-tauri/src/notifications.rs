@@ -1,3 +1,4 @@
+use crate::state::{Achievement, DailySummary};
 use tauri::AppHandle;
 use tauri_plugin_notification::NotificationExt;
 
@@ -13,3 +14,49 @@ pub fn send_break_notification(app: &AppHandle) {
         eprintln!("[notifications] Failed to send break notification: {}", e);
     }
 }
+
+/// Send a notification when an achievement unlocks.
+pub fn send_achievement_notification(app: &AppHandle, achievement: &Achievement) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(format!("Achievement unlocked: {} 🏆", achievement.title))
+        .body(&achievement.description)
+        .show()
+    {
+        eprintln!(
+            "[notifications] Failed to send achievement notification: {}",
+            e
+        );
+    }
+}
+
+/// Send the once-a-day summary: yesterday's totals and the top insights.
+pub fn send_daily_summary_notification(app: &AppHandle, summary: &DailySummary) {
+    let handled = summary.breaks_completed + summary.breaks_skipped;
+    let mut body = format!(
+        "{} of {} breaks completed ({}%).",
+        summary.breaks_completed,
+        handled,
+        (summary.breaks_completed * 100)
+            .checked_div(handled)
+            .unwrap_or(0)
+    );
+    for insight in summary.insights.iter().take(2) {
+        body.push_str("\n• ");
+        body.push_str(&insight.message);
+    }
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("Yesterday's breaks 📊")
+        .body(body)
+        .show()
+    {
+        eprintln!(
+            "[notifications] Failed to send daily summary notification: {}",
+            e
+        );
+    }
+}
@@ -0,0 +1,477 @@
+use crate::state::RecoveryReport;
+use crate::streaks::Workdays;
+use crate::{aggregates, backup, db, settings};
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::{Connection, ErrorCode, OpenFlags, Result as SqlResult};
+use std::path::{Path, PathBuf};
+
+/// Sub-directory of the data directory that holds damaged databases.
+const QUARANTINE_DIR_NAME: &str = "quarantine";
+
+/// Tables copied out of a damaged database, in dependency order. Single-row
+/// tables are replaced; the rest keep whatever the fresh database already has.
+const SALVAGE_TABLES: &[(&str, bool)] = &[
+    ("settings", true),
+    ("retention_state", true),
+    ("streak_state", true),
+    ("daily_summary_state", true),
+    ("break_records", false),
+    ("daily_stats_cache", false),
+    ("period_stats", false),
+    ("break_deferrals", false),
+    ("phase_log", false),
+    ("break_record_edits", false),
+    ("streak_freezes", false),
+    ("settings_history", false),
+    ("achievements", false),
+];
+
+/// Why the database couldn't be opened.
+#[derive(Debug, PartialEq)]
+enum Problem {
+    /// The file is damaged or isn't a usable Blinky database.
+    Corrupt,
+    /// Anything else: the file may well be fine (locked by another process,
+    /// written by a newer Blinky, a migration that failed), so leave it alone.
+    Unavailable,
+}
+
+/// Only SQLite saying the file is damaged, or `check_integrity` failing (which
+/// reports SQLITE_CORRUPT), is worth moving the user's database aside for.
+fn classify(e: &rusqlite::Error) -> Problem {
+    if db::is_schema_too_new(e) {
+        return Problem::Unavailable;
+    }
+    match e.sqlite_error_code() {
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => Problem::Corrupt,
+        _ => Problem::Unavailable,
+    }
+}
+
+/// Open the database at `db_path`, recovering from corruption instead of failing.
+///
+/// A healthy database opens as usual with no report. A damaged one is moved
+/// into `quarantine/`, a fresh database is created in its place, and as many
+/// rows as can still be read are copied across; if nothing could be salvaged
+/// the newest usable backup is restored instead. A database that is merely
+/// unavailable (locked, or from a newer version) is left untouched and the
+/// app runs on an in-memory database for the session.
+pub fn open_database(db_path: &Path) -> (Connection, Option<RecoveryReport>) {
+    let err = match db::init_db(&db_path.to_string_lossy()) {
+        Ok(conn) => return (conn, None),
+        Err(e) => e,
+    };
+    eprintln!("[recovery] Failed to open database: {}", err);
+
+    let mut report = RecoveryReport {
+        outcome: "temporary".to_string(),
+        problem: err.to_string(),
+        quarantined_path: None,
+        backup_used: None,
+        rows_salvaged: 0,
+        occurred_at: Utc::now().timestamp_millis() as u64,
+    };
+
+    if classify(&err) == Problem::Corrupt {
+        match recover(db_path, &mut report) {
+            Ok(conn) => return (conn, Some(report)),
+            Err(e) => eprintln!("[recovery] Recovery failed: {}", e),
+        }
+    }
+
+    report.outcome = "temporary".to_string();
+    (temporary_database(), Some(report))
+}
+
+fn recover(db_path: &Path, report: &mut RecoveryReport) -> SqlResult<Connection> {
+    let quarantined = quarantine(db_path)?;
+    report.quarantined_path = Some(quarantined.to_string_lossy().to_string());
+
+    let mut conn = db::init_db(&db_path.to_string_lossy())?;
+
+    match salvage(&conn, &quarantined) {
+        Ok(rows) if rows > 0 => {
+            report.outcome = "salvaged".to_string();
+            report.rows_salvaged = rows;
+            return Ok(conn);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("[recovery] Salvage failed: {}", e),
+    }
+
+    let backups = backup::backups_dir(db_path);
+    match backup::restore_latest(&mut conn, &backups) {
+        Ok(Some(file_name)) => {
+            report.outcome = "restored_backup".to_string();
+            report.backup_used = Some(file_name);
+        }
+        Ok(None) => report.outcome = "fresh".to_string(),
+        Err(e) => {
+            eprintln!("[recovery] Backup restore failed: {}", e);
+            // The restore may have left the fresh file half-written; start over
+            drop(conn);
+            let _ = std::fs::remove_file(db_path);
+            conn = db::init_db(&db_path.to_string_lossy())?;
+            report.outcome = "fresh".to_string();
+        }
+    }
+    Ok(conn)
+}
+
+/// Move the database and its WAL/SHM side files into the quarantine directory.
+/// Returns the new path of the main file.
+fn quarantine(db_path: &Path) -> SqlResult<PathBuf> {
+    let dir = db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(QUARANTINE_DIR_NAME);
+    std::fs::create_dir_all(&dir).map_err(io_err)?;
+
+    let dest = dir.join(format!(
+        "blinky-{}.db",
+        Utc::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+    std::fs::rename(db_path, &dest).map_err(io_err)?;
+    for suffix in ["-wal", "-shm"] {
+        let side = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if side.exists() {
+            let _ = std::fs::rename(&side, format!("{}{}", dest.display(), suffix));
+        }
+    }
+    Ok(dest)
+}
+
+/// Copy every readable row of the known tables from `damaged` into `conn`.
+/// Reading a table stops at the first error, keeping the rows before it.
+/// Returns the number of history rows (break records and daily stats) recovered.
+fn salvage(conn: &Connection, damaged: &Path) -> SqlResult<u32> {
+    let old = Connection::open_with_flags(damaged, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let tx = conn.unchecked_transaction()?;
+    let mut history_rows = 0;
+
+    for &(table, replace) in SALVAGE_TABLES {
+        let columns = match shared_columns(&tx, &old, table) {
+            Ok(columns) if !columns.is_empty() => columns,
+            _ => continue,
+        };
+        let copied = copy_rows(&tx, &old, table, &columns, replace);
+        if matches!(table, "break_records" | "daily_stats_cache") {
+            history_rows += copied;
+        }
+    }
+
+    // Salvaged settings may be half-garbage; fall back to defaults if so
+    let settings_ok = db::load_settings(&tx)
+        .ok()
+        .is_some_and(|s| settings::validate_settings(&s).is_ok());
+    if !settings_ok {
+        tx.execute_batch("DELETE FROM settings; INSERT INTO settings (id) VALUES (1);")?;
+    }
+
+    // The stats cache may have been lost even where records survived
+    let mut stmt = tx.prepare(
+        "SELECT DISTINCT date(started_at / 1000, 'unixepoch') FROM break_records
+         WHERE date(started_at / 1000, 'unixepoch') NOT IN (SELECT date FROM daily_stats_cache)",
+    )?;
+    let missing: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<SqlResult<_>>()?;
+    drop(stmt);
+    for day in &missing {
+        db::recompute_daily_stats(&tx, day)?;
+    }
+
+    tx.commit()?;
+
+    // Totals and streaks are derived, so recompute them from what survived
+    let settings = db::load_settings(conn)?;
+    aggregates::rebuild(
+        conn,
+        settings.daily_goal,
+        &Workdays::from_settings(&settings),
+        Utc::now().date_naive(),
+    )?;
+    Ok(history_rows)
+}
+
+/// Columns present in `table` in both databases, so older schemas salvage too.
+fn shared_columns(new: &Connection, old: &Connection, table: &str) -> SqlResult<Vec<String>> {
+    let old_columns = table_columns(old, table)?;
+    Ok(table_columns(new, table)?
+        .into_iter()
+        .filter(|c| old_columns.contains(c))
+        .collect())
+}
+
+fn table_columns(conn: &Connection, table: &str) -> SqlResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqlResult<_>>();
+    columns
+}
+
+fn copy_rows(
+    tx: &Connection,
+    old: &Connection,
+    table: &str,
+    columns: &[String],
+    replace: bool,
+) -> u32 {
+    let column_list = columns.join(", ");
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let insert = format!(
+        "INSERT OR {} INTO {} ({}) VALUES ({})",
+        if replace { "REPLACE" } else { "IGNORE" },
+        table,
+        column_list,
+        placeholders
+    );
+
+    let Ok(mut select) = old.prepare(&format!("SELECT {} FROM {}", column_list, table)) else {
+        return 0;
+    };
+    let Ok(mut rows) = select.query([]) else {
+        return 0;
+    };
+
+    let mut copied = 0;
+    while let Ok(Some(row)) = rows.next() {
+        let values: SqlResult<Vec<Value>> = (0..columns.len()).map(|i| row.get(i)).collect();
+        let Ok(values) = values else {
+            continue;
+        };
+        if let Ok(n) = tx.execute(&insert, rusqlite::params_from_iter(values)) {
+            copied += n as u32;
+        }
+    }
+    copied
+}
+
+/// Where the temporary database lives. A named shared-cache memory database,
+/// so the read connection can open it too.
+pub const TEMPORARY_DB_URI: &str = "file:blinky-temporary?mode=memory&cache=shared";
+
+/// A throwaway database so the app can run when the real one is unavailable.
+fn temporary_database() -> Connection {
+    db::init_db(TEMPORARY_DB_URI).unwrap_or_else(|e| {
+        eprintln!("[recovery] Failed to initialize in-memory database: {}", e);
+        Connection::open_in_memory().expect("SQLite in-memory database unavailable")
+    })
+}
+
+fn io_err(e: std::io::Error) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(format!("recovery io error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{BreakOutcome, UserSettings};
+
+    /// Tables derived from the salvaged ones, rebuilt after a salvage instead
+    /// of copied.
+    const REBUILT_TABLES: &[&str] = &["aggregates"];
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("blinky_recovery_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add_completed_break(conn: &Connection, started_at: u64) {
+        let id = db::insert_break_record(conn, started_at, 1200).unwrap();
+        db::finish_break_record(conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+    }
+
+    fn break_count(conn: &Connection) -> u32 {
+        conn.query_row("SELECT COUNT(*) FROM break_records", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_healthy_database_opens_without_report() {
+        let dir = temp_dir("healthy");
+        let path = dir.join("blinky.db");
+        let (conn, report) = open_database(&path);
+        assert!(report.is_none());
+        add_completed_break(&conn, 1_705_312_800_000);
+        drop(conn);
+
+        let (conn, report) = open_database(&path);
+        assert!(report.is_none());
+        assert_eq!(break_count(&conn), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_garbage_file_is_quarantined_and_replaced() {
+        let dir = temp_dir("garbage");
+        let path = dir.join("blinky.db");
+        std::fs::write(&path, b"this is definitely not an sqlite database file").unwrap();
+
+        let (conn, report) = open_database(&path);
+        let report = report.unwrap();
+        assert_eq!(report.outcome, "fresh");
+        let quarantined = PathBuf::from(report.quarantined_path.unwrap());
+        assert!(quarantined.starts_with(dir.join(QUARANTINE_DIR_NAME)));
+        assert!(quarantined.exists());
+
+        // The replacement is a working database at the original path
+        assert_eq!(break_count(&conn), 0);
+        assert!(path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_falls_back_to_latest_backup() {
+        let dir = temp_dir("fallback");
+        let path = dir.join("blinky.db");
+        {
+            let (conn, _) = open_database(&path);
+            add_completed_break(&conn, 1_705_312_800_000);
+            add_completed_break(&conn, 1_705_314_000_000);
+            let backups = backup::backups_dir(&path);
+            backup::snapshot(&conn, &backups.join("blinky-daily-20240115.db")).unwrap();
+        }
+        std::fs::remove_file(format!("{}-wal", path.display())).ok();
+        std::fs::remove_file(format!("{}-shm", path.display())).ok();
+        std::fs::write(&path, vec![0xABu8; 8192]).unwrap();
+
+        let (conn, report) = open_database(&path);
+        let report = report.unwrap();
+        assert_eq!(report.outcome, "restored_backup");
+        assert_eq!(
+            report.backup_used.as_deref(),
+            Some("blinky-daily-20240115.db")
+        );
+        assert_eq!(break_count(&conn), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_salvage_copies_rows_and_rebuilds_cache() {
+        let dir = temp_dir("salvage");
+        let old_path = dir.join("old.db");
+        {
+            let old = db::init_db(&old_path.to_string_lossy()).unwrap();
+            add_completed_break(&old, 1_705_312_800_000);
+            add_completed_break(&old, 1_705_400_000_000);
+            db::save_settings(
+                &old,
+                &UserSettings {
+                    daily_goal: 9,
+                    ..UserSettings::default()
+                },
+            )
+            .unwrap();
+            old.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+                .unwrap();
+        }
+
+        let fresh = db::init_db(&dir.join("new.db").to_string_lossy()).unwrap();
+        let rows = salvage(&fresh, &old_path).unwrap();
+        assert_eq!(rows, 2);
+        assert_eq!(break_count(&fresh), 2);
+        assert_eq!(db::load_settings(&fresh).unwrap().daily_goal, 9);
+
+        let stats = db::get_daily_stats_range(&fresh, "2024-01-15", "2024-01-16").unwrap();
+        assert_eq!(stats[0].breaks_completed, 1);
+        assert_eq!(stats[1].breaks_completed, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_salvage_resets_invalid_settings() {
+        let dir = temp_dir("badsettings");
+        let old_path = dir.join("old.db");
+        {
+            let old = db::init_db(&old_path.to_string_lossy()).unwrap();
+            old.execute("UPDATE settings SET work_interval_minutes = 0", [])
+                .unwrap();
+            old.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+                .unwrap();
+        }
+
+        let fresh = db::init_db(&dir.join("new.db").to_string_lossy()).unwrap();
+        salvage(&fresh, &old_path).unwrap();
+        assert_eq!(
+            db::load_settings(&fresh).unwrap().work_interval_minutes,
+            UserSettings::default().work_interval_minutes
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_salvage_covers_every_table() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name != '_migrations' AND name NOT LIKE 'sqlite_%'",
+            )
+            .unwrap();
+        let tables: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<SqlResult<_>>()
+            .unwrap();
+        for table in tables {
+            assert!(
+                SALVAGE_TABLES.iter().any(|(t, _)| *t == table)
+                    || REBUILT_TABLES.contains(&table.as_str()),
+                "{} is neither salvaged nor rebuilt",
+                table
+            );
+        }
+    }
+
+    #[test]
+    fn test_failing_migration_leaves_a_sound_file_alone() {
+        let dir = temp_dir("badmigration");
+        let path = dir.join("blinky.db");
+        {
+            let (conn, _) = open_database(&path);
+            add_completed_break(&conn, 1_705_312_800_000);
+            // Pretend the last migration hasn't run, so re-running it fails
+            // on a column that already exists
+            let version = db::schema_version(&conn).unwrap();
+            conn.pragma_update(None, "user_version", version - 1)
+                .unwrap();
+        }
+
+        let (_conn, report) = open_database(&path);
+        let report = report.unwrap();
+        assert_eq!(report.outcome, "temporary");
+        assert!(report.quarantined_path.is_none());
+        assert!(!dir.join(QUARANTINE_DIR_NAME).exists());
+
+        let old = Connection::open(&path).unwrap();
+        assert_eq!(break_count(&old), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_newer_schema_is_left_untouched() {
+        let dir = temp_dir("newer");
+        let path = dir.join("blinky.db");
+        {
+            let (conn, _) = open_database(&path);
+            conn.pragma_update(None, "user_version", 9999).unwrap();
+        }
+
+        let (_conn, report) = open_database(&path);
+        let report = report.unwrap();
+        assert_eq!(report.outcome, "temporary");
+        assert!(report.quarantined_path.is_none());
+        // The read connection has to be able to open the stand-in too
+        let reader = db::open_reader(TEMPORARY_DB_URI).unwrap();
+        db::load_settings(&reader).unwrap();
+        assert!(path.exists());
+        assert!(!dir.join(QUARANTINE_DIR_NAME).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
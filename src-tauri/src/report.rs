@@ -0,0 +1,564 @@
+use crate::state::{DailyStats, HeatmapCell, UserSettings};
+use crate::streaks::Workdays;
+use crate::{analytics, db};
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{Connection, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// How many (weekday, hour) slots the skip patterns section lists.
+const SKIP_HOTSPOTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPeriod {
+    Week,
+    Month,
+}
+
+impl ReportPeriod {
+    fn noun(self) -> &'static str {
+        match self {
+            ReportPeriod::Week => "week",
+            ReportPeriod::Month => "month",
+        }
+    }
+
+    fn adjective(self) -> &'static str {
+        match self {
+            ReportPeriod::Week => "weekly",
+            ReportPeriod::Month => "monthly",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Parse the `report_format` setting.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "html" => Some(ReportFormat::Html),
+            "markdown" => Some(ReportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Everything a report shows, for the last complete week or month.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub period: ReportPeriod,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: Vec<DailyStats>,
+    pub totals: Totals,
+    pub previous: Totals,
+    /// Highest compliance among days with any breaks.
+    pub best_day: Option<DailyStats>,
+    /// Lowest compliance among days with any breaks.
+    pub worst_day: Option<DailyStats>,
+    pub current_day_streak: u32,
+    pub best_day_streak: u32,
+    pub daily_goal: u32,
+    /// Busiest skip slots, most skips first. Empty if nothing was skipped.
+    pub skip_hotspots: Vec<HeatmapCell>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Totals {
+    pub breaks_completed: u32,
+    pub breaks_skipped: u32,
+    pub total_rest_seconds: u32,
+    pub compliance_rate: f64,
+}
+
+impl Totals {
+    fn sum(days: &[DailyStats]) -> Self {
+        let mut t = Totals::default();
+        for d in days {
+            t.breaks_completed += d.breaks_completed;
+            t.breaks_skipped += d.breaks_skipped;
+            t.total_rest_seconds += d.total_rest_seconds;
+        }
+        let total = t.breaks_completed + t.breaks_skipped;
+        if total > 0 {
+            t.compliance_rate = t.breaks_completed as f64 / total as f64;
+        }
+        t
+    }
+}
+
+/// First and last day of the most recent period that ended before `today`:
+/// Monday to Sunday of last week, or last calendar month.
+pub fn period_bounds(period: ReportPeriod, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match period {
+        ReportPeriod::Week => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            (monday - Duration::days(7), monday - Duration::days(1))
+        }
+        ReportPeriod::Month => {
+            let to = today.with_day(1).unwrap() - Duration::days(1);
+            (to.with_day(1).unwrap(), to)
+        }
+    }
+}
+
+/// Gather the report for the last complete `period` before `today`.
+pub fn build_report(
+    conn: &Connection,
+    period: ReportPeriod,
+    today: NaiveDate,
+    settings: &UserSettings,
+) -> SqlResult<Report> {
+    let (from, to) = period_bounds(period, today);
+    let (prev_from, prev_to) = period_bounds(period, from);
+    let day = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+
+    let days = db::get_daily_stats_range(conn, &day(from), &day(to))?;
+    let previous = db::get_daily_stats_range(conn, &day(prev_from), &day(prev_to))?;
+    let summary = analytics::build_analytics_summary(
+        conn,
+        settings.daily_goal,
+        &Workdays::from_settings(settings),
+    )?;
+
+    let active = || {
+        days.iter()
+            .filter(|d| d.breaks_completed + d.breaks_skipped > 0)
+    };
+    let rank = |d: &&DailyStats| (d.compliance_rate, d.breaks_completed);
+    let best_day = active()
+        .max_by(|a, b| rank(a).partial_cmp(&rank(b)).unwrap())
+        .cloned();
+    let worst_day = active()
+        .min_by(|a, b| rank(a).partial_cmp(&rank(b)).unwrap())
+        .cloned();
+
+    let mut skip_hotspots: Vec<HeatmapCell> =
+        analytics::build_hourly_heatmap(conn, &day(from), &day(to))?
+            .cells
+            .into_iter()
+            .filter(|c| c.breaks_skipped > 0)
+            .collect();
+    skip_hotspots.sort_by_key(|c| std::cmp::Reverse(c.breaks_skipped));
+    skip_hotspots.truncate(SKIP_HOTSPOTS);
+
+    Ok(Report {
+        period,
+        from,
+        to,
+        totals: Totals::sum(&days),
+        previous: Totals::sum(&previous),
+        days,
+        best_day,
+        worst_day,
+        current_day_streak: summary.current_day_streak,
+        best_day_streak: summary.best_day_streak,
+        daily_goal: settings.daily_goal,
+        skip_hotspots,
+    })
+}
+
+/// The configured report folder, or Downloads if none is set.
+pub fn report_dir(settings: &UserSettings) -> PathBuf {
+    if settings.report_dir.trim().is_empty() {
+        db::downloads_dir()
+    } else {
+        PathBuf::from(settings.report_dir.trim())
+    }
+}
+
+/// Where the report for the period before `today` goes in `dir`.
+pub fn report_path(
+    dir: &Path,
+    period: ReportPeriod,
+    format: ReportFormat,
+    today: NaiveDate,
+) -> PathBuf {
+    let (from, _) = period_bounds(period, today);
+    dir.join(format!(
+        "blinky-{}-report-{}.{}",
+        period.adjective(),
+        from.format("%Y-%m-%d"),
+        format.extension()
+    ))
+}
+
+/// Build, render and write a report into `dir`. Returns the file path.
+pub fn generate(
+    conn: &Connection,
+    period: ReportPeriod,
+    format: ReportFormat,
+    today: NaiveDate,
+    settings: &UserSettings,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    let report = build_report(conn, period, today, settings).map_err(|e| e.to_string())?;
+    let contents = match format {
+        ReportFormat::Html => render_html(&report),
+        ReportFormat::Markdown => render_markdown(&report),
+    };
+    let path = report_path(dir, period, format, today);
+    std::fs::create_dir_all(dir).map_err(|e| format!("dir error: {}", e))?;
+    std::fs::write(&path, contents).map_err(|e| format!("write error: {}", e))?;
+    Ok(path)
+}
+
+pub fn render_markdown(r: &Report) -> String {
+    let noun = r.period.noun();
+    let mut out = String::new();
+    let _ = writeln!(out, "# Blinky {} report\n", r.period.adjective());
+    let _ = writeln!(out, "{} to {}\n", r.from, r.to);
+
+    let _ = writeln!(out, "## Summary\n");
+    let _ = writeln!(out, "- Breaks completed: {}", r.totals.breaks_completed);
+    let _ = writeln!(out, "- Breaks skipped: {}", r.totals.breaks_skipped);
+    let _ = writeln!(
+        out,
+        "- Rest time: {}",
+        format_duration(r.totals.total_rest_seconds)
+    );
+    let _ = writeln!(
+        out,
+        "- Compliance: {} ({})\n",
+        percent(r.totals.compliance_rate),
+        trend(&r.totals, &r.previous, noun)
+    );
+
+    let _ = writeln!(out, "## Days\n");
+    let _ = writeln!(out, "| Date | Completed | Skipped | Compliance |");
+    let _ = writeln!(out, "|---|---:|---:|---:|");
+    for d in &r.days {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            d.date,
+            d.breaks_completed,
+            d.breaks_skipped,
+            percent(d.compliance_rate)
+        );
+    }
+    out.push('\n');
+
+    let _ = writeln!(out, "## Best and worst days\n");
+    match (&r.best_day, &r.worst_day) {
+        (Some(best), Some(worst)) => {
+            let _ = writeln!(out, "- Best: {}", day_summary(best));
+            let _ = writeln!(out, "- Worst: {}\n", day_summary(worst));
+        }
+        _ => {
+            let _ = writeln!(out, "No breaks this {}.\n", noun);
+        }
+    }
+
+    let _ = writeln!(out, "## Streaks\n");
+    let _ = writeln!(out, "{}\n", streaks(r));
+
+    let _ = writeln!(out, "## Skip patterns\n");
+    if r.skip_hotspots.is_empty() {
+        let _ = writeln!(out, "No breaks skipped this {}.", noun);
+    } else {
+        for c in &r.skip_hotspots {
+            let _ = writeln!(out, "- {}", hotspot(c));
+        }
+    }
+    out
+}
+
+/// A standalone page with inline styles, so the file can be mailed or opened
+/// anywhere.
+pub fn render_html(r: &Report) -> String {
+    let noun = r.period.noun();
+    let title = format!(
+        "Blinky {} report, {} to {}",
+        r.period.adjective(),
+        r.from,
+        r.to
+    );
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>\n\
+         body {{ font-family: system-ui, sans-serif; max-width: 42rem; margin: 2rem auto; \
+         padding: 0 1rem; color: #1f2937; }}\n\
+         h1 {{ font-size: 1.5rem; }} h2 {{ font-size: 1.1rem; margin-top: 2rem; }}\n\
+         table {{ border-collapse: collapse; width: 100%; }}\n\
+         th, td {{ padding: 0.3rem 0.6rem; border-bottom: 1px solid #e5e7eb; }}\n\
+         td.n, th.n {{ text-align: right; }}\n\
+         .muted {{ color: #6b7280; }}\n\
+         </style>\n</head>\n<body>\n<h1>Blinky {} report</h1>\n\
+         <p class=\"muted\">{} to {}</p>\n",
+        r.period.adjective(),
+        r.from,
+        r.to
+    );
+
+    let _ = write!(
+        out,
+        "<h2>Summary</h2>\n<ul>\n<li>Breaks completed: {}</li>\n\
+         <li>Breaks skipped: {}</li>\n<li>Rest time: {}</li>\n\
+         <li>Compliance: {} ({})</li>\n</ul>\n",
+        r.totals.breaks_completed,
+        r.totals.breaks_skipped,
+        format_duration(r.totals.total_rest_seconds),
+        percent(r.totals.compliance_rate),
+        trend(&r.totals, &r.previous, noun)
+    );
+
+    out.push_str(
+        "<h2>Days</h2>\n<table>\n<tr><th>Date</th><th class=\"n\">Completed</th>\
+         <th class=\"n\">Skipped</th><th class=\"n\">Compliance</th></tr>\n",
+    );
+    for d in &r.days {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td>\
+             <td class=\"n\">{}</td></tr>",
+            d.date,
+            d.breaks_completed,
+            d.breaks_skipped,
+            percent(d.compliance_rate)
+        );
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Best and worst days</h2>\n");
+    match (&r.best_day, &r.worst_day) {
+        (Some(best), Some(worst)) => {
+            let _ = write!(
+                out,
+                "<ul>\n<li>Best: {}</li>\n<li>Worst: {}</li>\n</ul>\n",
+                day_summary(best),
+                day_summary(worst)
+            );
+        }
+        _ => {
+            let _ = writeln!(out, "<p>No breaks this {}.</p>", noun);
+        }
+    }
+
+    let _ = writeln!(out, "<h2>Streaks</h2>\n<p>{}</p>", streaks(r));
+
+    out.push_str("<h2>Skip patterns</h2>\n");
+    if r.skip_hotspots.is_empty() {
+        let _ = writeln!(out, "<p>No breaks skipped this {}.</p>", noun);
+    } else {
+        out.push_str("<ul>\n");
+        for c in &r.skip_hotspots {
+            let _ = writeln!(out, "<li>{}</li>", hotspot(c));
+        }
+        out.push_str("</ul>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.0}%", rate * 100.0)
+}
+
+fn format_duration(seconds: u32) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{}m {}s", minutes, seconds % 60)
+    } else {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Compliance change against the previous period, in percentage points.
+fn trend(current: &Totals, previous: &Totals, noun: &str) -> String {
+    if previous.breaks_completed + previous.breaks_skipped == 0 {
+        return format!("no data for the previous {}", noun);
+    }
+    let points = ((current.compliance_rate - previous.compliance_rate) * 100.0).round() as i64;
+    let direction = match points {
+        p if p > 0 => format!("up {} points", p),
+        p if p < 0 => format!("down {} points", -p),
+        _ => "unchanged".to_string(),
+    };
+    format!(
+        "{} from {} the previous {}",
+        direction,
+        percent(previous.compliance_rate),
+        noun
+    )
+}
+
+fn day_summary(d: &DailyStats) -> String {
+    format!(
+        "{}, {} completed, {} skipped ({})",
+        d.date,
+        d.breaks_completed,
+        d.breaks_skipped,
+        percent(d.compliance_rate)
+    )
+}
+
+fn streaks(r: &Report) -> String {
+    format!(
+        "Current streak: {} days. Best streak: {} days. A day counts when {} breaks are completed.",
+        r.current_day_streak, r.best_day_streak, r.daily_goal
+    )
+}
+
+fn hotspot(c: &HeatmapCell) -> String {
+    format!(
+        "{} {:02}:00, {} skipped, {} completed",
+        WEEKDAYS[c.weekday as usize], c.hour, c.breaks_skipped, c.breaks_completed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{finish_break_record, init_db_conn, insert_break_record};
+    use crate::state::BreakOutcome;
+    use chrono::Local;
+    use chrono::TimeZone;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn goal(daily_goal: u32) -> UserSettings {
+        UserSettings {
+            daily_goal,
+            ..UserSettings::default()
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// A break at local noon on `day`, with that day's stats cached.
+    fn add(conn: &Connection, day: NaiveDate, outcome: BreakOutcome) {
+        let at = Local
+            .from_local_datetime(&day.and_hms_opt(12, 0, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .timestamp_millis() as u64;
+        let id = insert_break_record(conn, at, 1200).unwrap();
+        finish_break_record(conn, id, 20, outcome, None, None).unwrap();
+        let utc_day = chrono::DateTime::<chrono::Utc>::from_timestamp_millis(at as i64)
+            .unwrap()
+            .format("%Y-%m-%d")
+            .to_string();
+        db::recompute_daily_stats(conn, &utc_day).unwrap();
+    }
+
+    #[test]
+    fn test_period_bounds() {
+        // Wednesday 2024-01-17
+        let today = date(2024, 1, 17);
+        assert_eq!(
+            period_bounds(ReportPeriod::Week, today),
+            (date(2024, 1, 8), date(2024, 1, 14))
+        );
+        // On a Monday the report covers the week that just ended
+        assert_eq!(
+            period_bounds(ReportPeriod::Week, date(2024, 1, 15)),
+            (date(2024, 1, 8), date(2024, 1, 14))
+        );
+        assert_eq!(
+            period_bounds(ReportPeriod::Month, today),
+            (date(2023, 12, 1), date(2023, 12, 31))
+        );
+        assert_eq!(
+            period_bounds(ReportPeriod::Month, date(2024, 3, 1)),
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+    }
+
+    #[test]
+    fn test_weekly_report_contents() {
+        let conn = setup_test_db();
+        // Previous week: 1 of 2
+        add(&conn, date(2024, 1, 2), BreakOutcome::Completed);
+        add(&conn, date(2024, 1, 2), BreakOutcome::Skipped);
+        // Report week: 3 of 4, Tuesday perfect, Wednesday half
+        add(&conn, date(2024, 1, 9), BreakOutcome::Completed);
+        add(&conn, date(2024, 1, 9), BreakOutcome::Completed);
+        add(&conn, date(2024, 1, 10), BreakOutcome::Completed);
+        add(&conn, date(2024, 1, 10), BreakOutcome::Skipped);
+
+        let report = build_report(&conn, ReportPeriod::Week, date(2024, 1, 15), &goal(2)).unwrap();
+        assert_eq!(report.days.len(), 7);
+        assert_eq!(report.totals.breaks_completed, 3);
+        assert_eq!(report.totals.compliance_rate, 0.75);
+        assert_eq!(report.previous.compliance_rate, 0.5);
+        assert_eq!(report.best_day.as_ref().unwrap().compliance_rate, 1.0);
+        assert_eq!(report.worst_day.as_ref().unwrap().compliance_rate, 0.5);
+        assert_eq!(report.skip_hotspots.len(), 1);
+        assert_eq!(report.skip_hotspots[0].weekday, 2);
+
+        let md = render_markdown(&report);
+        assert!(md.starts_with("# Blinky weekly report"));
+        assert!(md.contains("2024-01-08 to 2024-01-14"));
+        assert!(md.contains("- Compliance: 75% (up 25 points from 50% the previous week)"));
+        assert!(md.contains("Wed 12:00, 1 skipped, 1 completed"));
+
+        let html = render_html(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<li>Compliance: 75% (up 25 points from 50% the previous week)</li>"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_empty_period() {
+        let conn = setup_test_db();
+        let report = build_report(&conn, ReportPeriod::Month, date(2024, 2, 10), &goal(8)).unwrap();
+        assert_eq!(report.days.len(), 31);
+        assert!(report.best_day.is_none());
+        let md = render_markdown(&report);
+        assert!(md.contains("no data for the previous month"));
+        assert!(md.contains("No breaks this month."));
+        assert!(md.contains("No breaks skipped this month."));
+    }
+
+    #[test]
+    fn test_generate_writes_named_file() {
+        let conn = setup_test_db();
+        let dir = std::env::temp_dir().join(format!("blinky-report-test-{}", std::process::id()));
+        let today = date(2024, 1, 15);
+        let path = generate(
+            &conn,
+            ReportPeriod::Week,
+            ReportFormat::Markdown,
+            today,
+            &UserSettings::default(),
+            &dir,
+        )
+        .unwrap();
+        assert_eq!(path, dir.join("blinky-weekly-report-2024-01-08.md"));
+        assert_eq!(
+            path,
+            report_path(&dir, ReportPeriod::Week, ReportFormat::Markdown, today)
+        );
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("Blinky weekly report"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+use crate::db;
+use crate::state::{PeriodStats, RetentionResult};
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
+use std::collections::BTreeSet;
+
+/// First day whose raw records are kept, or None if retention is off.
+/// `unit` is "days" or "months"; anything else is treated as days.
+pub fn cutoff_date(today: NaiveDate, amount: u32, unit: &str) -> Option<NaiveDate> {
+    if amount == 0 {
+        return None;
+    }
+    match unit {
+        "months" => today.checked_sub_months(Months::new(amount)),
+        _ => today.checked_sub_signed(chrono::Duration::days(amount as i64)),
+    }
+}
+
+/// Roll up and delete raw break records that started before `cutoff`.
+///
+/// Every affected day is recomputed into daily_stats_cache first, and the
+/// weeks and months containing those days are rebuilt in period_stats, all in
+/// one transaction with the delete. Afterwards those days are frozen: the
+/// cache is their only source of truth.
+pub fn prune_before(conn: &Connection, cutoff: NaiveDate) -> SqlResult<RetentionResult> {
+    let cutoff_ms = cutoff
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis() as u64;
+
+    let tx = conn.unchecked_transaction()?;
+
+    let days = days_with_records_before(&tx, cutoff_ms)?;
+    for day in &days {
+        db::recompute_daily_stats(&tx, &day.format("%Y-%m-%d").to_string())?;
+    }
+    for day in &days {
+        refresh_periods_containing(&tx, *day)?;
+    }
+
+    let pruned = tx.execute(
+        "DELETE FROM break_records WHERE started_at < ?1",
+        params![cutoff_ms as i64],
+    )?;
+    tx.execute(
+        "DELETE FROM phase_log WHERE ended_at < ?1",
+        params![cutoff_ms as i64],
+    )?;
+    tx.execute(
+        "DELETE FROM break_record_edits WHERE break_started_at < ?1",
+        params![cutoff_ms as i64],
+    )?;
+
+    // Never move the watermark backwards: a shorter retention later doesn't
+    // bring deleted rows back.
+    tx.execute(
+        "UPDATE retention_state
+         SET pruned_before = MAX(pruned_before, ?1), last_run_at = ?2
+         WHERE id = 1",
+        params![cutoff_ms as i64, Utc::now().timestamp_millis()],
+    )?;
+
+    tx.commit()?;
+
+    Ok(RetentionResult {
+        records_pruned: pruned as u32,
+        days_rolled_up: days.len() as u32,
+        cutoff: Some(cutoff.format("%Y-%m-%d").to_string()),
+        vacuumed: false,
+    })
+}
+
+/// Apply the retention setting (if any), then VACUUM to return the freed pages
+/// to the filesystem. Must not be called inside a transaction.
+pub fn compact(conn: &Connection, cutoff: Option<NaiveDate>) -> SqlResult<RetentionResult> {
+    let mut result = match cutoff {
+        Some(cutoff) => prune_before(conn, cutoff)?,
+        None => RetentionResult {
+            records_pruned: 0,
+            days_rolled_up: 0,
+            cutoff: None,
+            vacuumed: false,
+        },
+    };
+    conn.execute_batch("VACUUM")?;
+    result.vacuumed = true;
+    Ok(result)
+}
+
+fn days_with_records_before(conn: &Connection, cutoff_ms: u64) -> SqlResult<Vec<NaiveDate>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT date(started_at / 1000, 'unixepoch')
+         FROM break_records WHERE started_at < ?1",
+    )?;
+    let days: BTreeSet<NaiveDate> = stmt
+        .query_map(params![cutoff_ms as i64], |row| row.get::<_, String>(0))?
+        .filter_map(|r| {
+            r.ok()
+                .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+        })
+        .collect();
+    Ok(days.into_iter().collect())
+}
+
+/// ISO week key, e.g. "2024-W03".
+pub fn week_key(day: NaiveDate) -> String {
+    let week = day.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+/// Calendar month key, e.g. "2024-01".
+pub fn month_key(day: NaiveDate) -> String {
+    day.format("%Y-%m").to_string()
+}
+
+/// Rebuild the week and month rows containing `day` from daily_stats_cache.
+pub fn refresh_periods_containing(conn: &Connection, day: NaiveDate) -> SqlResult<()> {
+    let week_start = day - chrono::Duration::days(day.weekday().num_days_from_monday() as i64);
+    let week_end = week_start + chrono::Duration::days(6);
+    upsert_period(conn, &week_key(day), "week", week_start, week_end)?;
+
+    let month_start = day.with_day(1).unwrap();
+    let month_end = month_start
+        .checked_add_months(Months::new(1))
+        .unwrap()
+        .pred_opt()
+        .unwrap();
+    upsert_period(conn, &month_key(day), "month", month_start, month_end)
+}
+
+fn upsert_period(
+    conn: &Connection,
+    period: &str,
+    kind: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> SqlResult<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO period_stats
+            (period, kind, breaks_completed, breaks_skipped, total_rest_seconds, days_tracked)
+         SELECT ?1, ?2, COALESCE(SUM(breaks_completed), 0), COALESCE(SUM(breaks_skipped), 0),
+                COALESCE(SUM(total_rest_seconds), 0), COUNT(*)
+         FROM daily_stats_cache WHERE date >= ?3 AND date <= ?4",
+        params![
+            period,
+            kind,
+            from.format("%Y-%m-%d").to_string(),
+            to.format("%Y-%m-%d").to_string()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Weekly or monthly roll-ups, oldest first.
+pub fn get_period_stats(conn: &Connection, kind: &str) -> SqlResult<Vec<PeriodStats>> {
+    let mut stmt = conn.prepare(
+        "SELECT period, kind, breaks_completed, breaks_skipped, total_rest_seconds, days_tracked
+         FROM period_stats WHERE kind = ?1 ORDER BY period ASC",
+    )?;
+    let rows = stmt.query_map(params![kind], |row| {
+        Ok(PeriodStats {
+            period: row.get(0)?,
+            kind: row.get(1)?,
+            breaks_completed: row.get::<_, i64>(2)? as u32,
+            breaks_skipped: row.get::<_, i64>(3)? as u32,
+            total_rest_seconds: row.get::<_, i64>(4)? as u64,
+            days_tracked: row.get::<_, i64>(5)? as u32,
+        })
+    })?;
+    rows.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics;
+    use crate::state::BreakOutcome;
+    use crate::streaks::Workdays;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn add_breaks(conn: &Connection, day: NaiveDate, completed: u32, skipped: u32) {
+        let base = day
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis() as u64;
+        for i in 0..(completed + skipped) {
+            let id = db::insert_break_record(conn, base + i as u64 * 1_200_000, 1200).unwrap();
+            let done = i < completed;
+            db::finish_break_record(
+                conn,
+                id,
+                20,
+                if done {
+                    BreakOutcome::Completed
+                } else {
+                    BreakOutcome::Skipped
+                },
+                None,
+                None,
+            )
+            .unwrap();
+        }
+    }
+
+    fn raw_count(conn: &Connection) -> u32 {
+        conn.query_row("SELECT COUNT(*) FROM break_records", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cutoff_date() {
+        let today = date("2024-03-31");
+        assert_eq!(cutoff_date(today, 0, "days"), None);
+        assert_eq!(cutoff_date(today, 30, "days"), Some(date("2024-03-01")));
+        // Month arithmetic clamps to the end of shorter months
+        assert_eq!(cutoff_date(today, 1, "months"), Some(date("2024-02-29")));
+    }
+
+    #[test]
+    fn test_prune_rolls_up_before_deleting() {
+        let conn = setup_test_db();
+        add_breaks(&conn, date("2024-01-15"), 3, 1);
+        add_breaks(&conn, date("2024-01-16"), 2, 0);
+        add_breaks(&conn, date("2024-02-01"), 1, 0);
+
+        let result = prune_before(&conn, date("2024-02-01")).unwrap();
+        assert_eq!(result.records_pruned, 6);
+        assert_eq!(result.days_rolled_up, 2);
+        assert_eq!(raw_count(&conn), 1);
+
+        let stats = db::get_daily_stats_range(&conn, "2024-01-15", "2024-01-16").unwrap();
+        assert_eq!(stats[0].breaks_completed, 3);
+        assert_eq!(stats[0].breaks_skipped, 1);
+        assert_eq!(stats[1].breaks_completed, 2);
+
+        let months = get_period_stats(&conn, "month").unwrap();
+        assert_eq!(months.len(), 1);
+        assert_eq!(months[0].period, "2024-01");
+        assert_eq!(months[0].breaks_completed, 5);
+        assert_eq!(months[0].days_tracked, 2);
+
+        let weeks = get_period_stats(&conn, "week").unwrap();
+        assert_eq!(weeks.len(), 1);
+        assert_eq!(weeks[0].period, "2024-W03");
+        assert_eq!(weeks[0].breaks_skipped, 1);
+    }
+
+    #[test]
+    fn test_pruned_days_are_not_recomputed_to_zero() {
+        let conn = setup_test_db();
+        add_breaks(&conn, date("2024-01-15"), 4, 0);
+        prune_before(&conn, date("2024-01-20")).unwrap();
+
+        let stats = db::recompute_daily_stats(&conn, "2024-01-15").unwrap();
+        assert_eq!(stats.breaks_completed, 4);
+    }
+
+    #[test]
+    fn test_lifetime_totals_and_streaks_survive_pruning() {
+        let conn = setup_test_db();
+        let today = Utc::now().date_naive();
+        for offset in 1..=5 {
+            let day = today - chrono::Duration::days(offset);
+            add_breaks(&conn, day, 2, 0);
+            db::recompute_daily_stats(&conn, &day.format("%Y-%m-%d").to_string()).unwrap();
+        }
+        let before = analytics::build_analytics_summary(&conn, 2, &Workdays::ALL).unwrap();
+        assert_eq!(before.lifetime_breaks, 10);
+
+        let result = prune_before(&conn, today - chrono::Duration::days(2)).unwrap();
+        assert_eq!(result.records_pruned, 6);
+
+        let after = analytics::build_analytics_summary(&conn, 2, &Workdays::ALL).unwrap();
+        assert_eq!(after.lifetime_breaks, before.lifetime_breaks);
+        assert_eq!(after.lifetime_rest_seconds, before.lifetime_rest_seconds);
+        assert_eq!(after.current_day_streak, before.current_day_streak);
+        assert_eq!(after.best_day_streak, before.best_day_streak);
+    }
+
+    #[test]
+    fn test_prune_is_idempotent_and_watermark_only_advances() {
+        let conn = setup_test_db();
+        add_breaks(&conn, date("2024-01-15"), 1, 0);
+        prune_before(&conn, date("2024-02-01")).unwrap();
+        let again = prune_before(&conn, date("2024-01-01")).unwrap();
+        assert_eq!(again.records_pruned, 0);
+
+        let watermark = db::pruned_before(&conn).unwrap();
+        let expected = date("2024-02-01")
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis() as u64;
+        assert_eq!(watermark, expected);
+    }
+
+    #[test]
+    fn test_compact_vacuums() {
+        let conn = setup_test_db();
+        add_breaks(&conn, date("2024-01-15"), 1, 0);
+        let result = compact(&conn, None).unwrap();
+        assert!(result.vacuumed);
+        assert_eq!(result.records_pruned, 0);
+        assert_eq!(raw_count(&conn), 1);
+    }
+}
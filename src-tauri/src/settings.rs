@@ -1,4 +1,23 @@
 use crate::state::UserSettings;
+use crate::streaks::Workdays;
+
+const MAX_SKIP_REASONS: usize = 10;
+const MAX_SKIP_REASON_LEN: usize = 40;
+
+/// The reasons offered when skipping, from the `skip_reasons` setting. None if
+/// it isn't a usable list.
+pub fn parse_skip_reasons(json: &str) -> Option<Vec<String>> {
+    let reasons: Vec<String> = serde_json::from_str(json).ok()?;
+    let usable = (1..=MAX_SKIP_REASONS).contains(&reasons.len())
+        && reasons
+            .iter()
+            .all(|r| !r.trim().is_empty() && r.chars().count() <= MAX_SKIP_REASON_LEN)
+        && reasons
+            .iter()
+            .enumerate()
+            .all(|(i, r)| !reasons[..i].contains(r));
+    usable.then_some(reasons)
+}
 
 /// Validate user settings. Returns Ok(()) if valid, Err(message) if invalid.
 pub fn validate_settings(settings: &UserSettings) -> Result<(), String> {
@@ -14,6 +33,54 @@ pub fn validate_settings(settings: &UserSettings) -> Result<(), String> {
     if settings.daily_goal < 1 || settings.daily_goal > 100 {
         return Err("daily_goal must be between 1 and 100".to_string());
     }
+    if !["resume", "restart_if_long", "restart"].contains(&settings.idle_resume_policy.as_str()) {
+        return Err(
+            "idle_resume_policy must be 'resume', 'restart_if_long', or 'restart'".to_string(),
+        );
+    }
+    if settings.idle_long_away_minutes < 1 || settings.idle_long_away_minutes > 120 {
+        return Err("idle_long_away_minutes must be between 1 and 120".to_string());
+    }
+    if serde_json::from_str::<Vec<String>>(&settings.media_player_allowlist).is_err() {
+        return Err("media_player_allowlist must be a JSON array of strings".to_string());
+    }
+    if settings.meeting_max_defer_minutes > 240 {
+        return Err("meeting_max_defer_minutes must be between 0 and 240".to_string());
+    }
+    if settings.backup_daily_keep > 90 {
+        return Err("backup_daily_keep must be between 0 and 90".to_string());
+    }
+    if settings.backup_weekly_keep > 52 {
+        return Err("backup_weekly_keep must be between 0 and 52".to_string());
+    }
+    match settings.retention_unit.as_str() {
+        "days"
+            if settings.retention_amount != 0
+                && !(7..=3650).contains(&settings.retention_amount) =>
+        {
+            return Err("retention_amount must be 0 or between 7 and 3650 days".to_string());
+        }
+        "months" if settings.retention_amount > 120 => {
+            return Err("retention_amount must be between 0 and 120 months".to_string());
+        }
+        "days" | "months" => {}
+        _ => return Err("retention_unit must be 'days' or 'months'".to_string()),
+    }
+    if !["html", "markdown"].contains(&settings.report_format.as_str()) {
+        return Err("report_format must be 'html' or 'markdown'".to_string());
+    }
+    if Workdays::parse(&settings.workdays).is_none() {
+        return Err(
+            "workdays must be a JSON array naming at least one of mon, tue, wed, thu, fri, sat, sun"
+                .to_string(),
+        );
+    }
+    if parse_skip_reasons(&settings.skip_reasons).is_none() {
+        return Err(format!(
+            "skip_reasons must be a JSON array of 1 to {} distinct, non-empty reasons of at most {} characters",
+            MAX_SKIP_REASONS, MAX_SKIP_REASON_LEN
+        ));
+    }
     if !["system", "light", "dark"].contains(&settings.theme.as_str()) {
         return Err("theme must be 'system', 'light', or 'dark'".to_string());
     }
@@ -10,6 +10,27 @@ pub enum TimerPhase {
     Suspended,
 }
 
+impl TimerPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimerPhase::Working => "Working",
+            TimerPhase::Breaking => "Breaking",
+            TimerPhase::Paused => "Paused",
+            TimerPhase::Suspended => "Suspended",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Working" => Some(TimerPhase::Working),
+            "Breaking" => Some(TimerPhase::Breaking),
+            "Paused" => Some(TimerPhase::Paused),
+            "Suspended" => Some(TimerPhase::Suspended),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimerState {
     pub phase: TimerPhase,
@@ -30,6 +51,40 @@ pub struct UserSettings {
     pub launch_at_login: bool,
     pub daily_goal: u32,
     pub idle_pause_minutes: u32,
+    /// What happens to the work interval when returning from idle:
+    /// "resume", "restart_if_long", or "restart".
+    pub idle_resume_policy: String,
+    /// Minutes away (counted from the last input) after which
+    /// "restart_if_long" restarts the work interval.
+    pub idle_long_away_minutes: u32,
+    /// Treat a playing MPRIS media player as user activity (Linux only).
+    pub media_activity_enabled: bool,
+    /// JSON array of MPRIS player ids that count as activity. Empty = all players.
+    pub media_player_allowlist: String,
+    /// Defer breaks while the camera or microphone is in use (Linux only).
+    pub meeting_detection_enabled: bool,
+    /// Longest a single break may be deferred by a meeting.
+    pub meeting_max_defer_minutes: u32,
+    /// Daily snapshots to keep (0 = no daily backups).
+    pub backup_daily_keep: u32,
+    /// Weekly snapshots to keep (0 = no weekly backups).
+    pub backup_weekly_keep: u32,
+    /// Keep raw break records this many days/months (0 = forever).
+    pub retention_amount: u32,
+    /// "days" or "months".
+    pub retention_unit: String,
+    /// Write last week's report every Monday.
+    pub report_auto_enabled: bool,
+    /// "html" or "markdown".
+    pub report_format: String,
+    /// Folder for automatic reports (empty = Downloads).
+    pub report_dir: String,
+    /// JSON array of weekdays that count toward streaks ("mon" .. "sun").
+    pub workdays: String,
+    /// JSON array of the reasons offered when skipping a break.
+    pub skip_reasons: String,
+    /// Notify once a day with yesterday's totals and insights.
+    pub daily_summary_enabled: bool,
     pub theme: String,
     pub onboarding_completed: bool,
     pub onboarding_completed_at: Option<u64>,
@@ -49,6 +104,22 @@ impl Default for UserSettings {
             launch_at_login: false,
             daily_goal: 24,
             idle_pause_minutes: 5,
+            idle_resume_policy: "restart_if_long".to_string(),
+            idle_long_away_minutes: 15,
+            media_activity_enabled: true,
+            media_player_allowlist: "[]".to_string(),
+            meeting_detection_enabled: true,
+            meeting_max_defer_minutes: 60,
+            backup_daily_keep: 7,
+            backup_weekly_keep: 4,
+            retention_amount: 0,
+            retention_unit: "days".to_string(),
+            report_auto_enabled: false,
+            report_format: "html".to_string(),
+            report_dir: String::new(),
+            workdays: r#"["mon","tue","wed","thu","fri","sat","sun"]"#.to_string(),
+            skip_reasons: r#"["In a meeting","Deep focus","Presenting","Other"]"#.to_string(),
+            daily_summary_enabled: true,
             theme: "system".to_string(),
             onboarding_completed: false,
             onboarding_completed_at: None,
@@ -58,14 +129,137 @@ impl Default for UserSettings {
     }
 }
 
+/// How a break ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakOutcome {
+    /// Ran its full length.
+    Completed,
+    /// The user chose to skip it. The only outcome that lowers compliance.
+    Skipped,
+    /// The timer was reset while the break was showing.
+    Reset,
+    /// Postponed by the user to come back shortly.
+    Snoozed,
+    /// Never finished, e.g. the app quit mid-break.
+    Interrupted,
+    /// The onboarding preview break.
+    Demo,
+}
+
+impl BreakOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BreakOutcome::Completed => "completed",
+            BreakOutcome::Skipped => "skipped",
+            BreakOutcome::Reset => "reset",
+            BreakOutcome::Snoozed => "snoozed",
+            BreakOutcome::Interrupted => "interrupted",
+            BreakOutcome::Demo => "demo",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "completed" => Some(BreakOutcome::Completed),
+            "skipped" => Some(BreakOutcome::Skipped),
+            "reset" => Some(BreakOutcome::Reset),
+            "snoozed" => Some(BreakOutcome::Snoozed),
+            "interrupted" => Some(BreakOutcome::Interrupted),
+            "demo" => Some(BreakOutcome::Demo),
+            _ => None,
+        }
+    }
+
+    /// Best guess for records that only have the legacy completed/skipped flags.
+    pub fn from_flags(completed: bool, skipped: bool) -> Self {
+        match (completed, skipped) {
+            (true, _) => BreakOutcome::Completed,
+            (false, true) => BreakOutcome::Skipped,
+            (false, false) => BreakOutcome::Interrupted,
+        }
+    }
+}
+
+/// Where the action that ended a break came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakSource {
+    Tray,
+    Overlay,
+    MainWindow,
+    /// The command line. Nothing reaches a running instance from there yet.
+    Cli,
+    /// The idle monitor: the break ended while the user was away.
+    Idle,
+}
+
+impl BreakSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BreakSource::Tray => "tray",
+            BreakSource::Overlay => "overlay",
+            BreakSource::MainWindow => "main_window",
+            BreakSource::Cli => "cli",
+            BreakSource::Idle => "idle",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "tray" => Some(BreakSource::Tray),
+            "overlay" => Some(BreakSource::Overlay),
+            "main_window" => Some(BreakSource::MainWindow),
+            "cli" => Some(BreakSource::Cli),
+            "idle" => Some(BreakSource::Idle),
+            _ => None,
+        }
+    }
+
+    /// Source for a command invoked from the webview window with `label`.
+    pub fn from_window_label(label: &str) -> Self {
+        if label == "overlay" {
+            BreakSource::Overlay
+        } else {
+            BreakSource::MainWindow
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BreakRecord {
     pub id: i64,
     pub started_at: u64,
     pub duration_seconds: u32,
+    /// Kept in sync with `outcome` for older readers: true iff outcome is Completed.
     pub completed: bool,
+    /// True iff outcome is Skipped.
     pub skipped: bool,
     pub preceding_work_seconds: u32,
+    #[serde(default)]
+    pub ended_at: Option<u64>,
+    /// None while the break is still in progress.
+    #[serde(default)]
+    pub outcome: Option<BreakOutcome>,
+    /// None when the break ended on its own.
+    #[serde(default)]
+    pub source: Option<BreakSource>,
+    /// Free-text annotation added from the history view.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Why the break was skipped, from the configured reasons.
+    #[serde(default)]
+    pub skip_reason: Option<String>,
+}
+
+/// Goal, interval and break length in effect from `effective_date`
+/// (YYYY-MM-DD, UTC) until the next snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsSnapshot {
+    pub effective_date: String,
+    pub daily_goal: u32,
+    pub work_interval_minutes: u32,
+    pub break_duration_seconds: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,6 +285,124 @@ impl DailyStats {
     }
 }
 
+/// A break held back by a meeting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakDeferral {
+    pub id: i64,
+    pub started_at: u64,
+    pub reason: String,
+    pub deferred_seconds: u32,
+}
+
+/// Breaks deferred by meetings over a date range, grouped by reason.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeferralSummary {
+    pub reason: String,
+    pub deferrals: u32,
+    pub deferred_seconds: u64,
+}
+
+/// One contiguous stretch of a timer phase, clipped to the requested day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseInterval {
+    pub phase: TimerPhase,
+    pub started_at: u64,
+    /// None while the interval is still in progress.
+    pub ended_at: Option<u64>,
+}
+
+/// A day's phase intervals plus how long was spent in each phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayTimeline {
+    pub date: String,
+    pub intervals: Vec<PhaseInterval>,
+    pub working_seconds: u64,
+    pub paused_seconds: u64,
+    pub suspended_seconds: u64,
+    pub breaking_seconds: u64,
+}
+
+/// An achievement and when it was unlocked, if it has been.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub unlocked_at: Option<u64>,
+}
+
+/// Breaks taken against the breaks a day's working time called for. Unlike
+/// compliance, pausing the timer doesn't hide breaks that should have happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayCoverage {
+    pub date: String,
+    /// Working plus paused time: at the computer, earning breaks.
+    pub active_seconds: u64,
+    pub expected_breaks: u32,
+    pub breaks_completed: u32,
+    pub breaks_skipped: u32,
+    /// Expected breaks that fell due while the timer was paused.
+    pub missed_while_paused: u32,
+    /// breaks_completed / expected_breaks, 0.0 when none were expected.
+    pub coverage_rate: f64,
+}
+
+/// Weekly or monthly totals rolled up from daily_stats_cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodStats {
+    /// "2024-W03" for weeks, "2024-01" for months.
+    pub period: String,
+    /// "week" or "month".
+    pub kind: String,
+    pub breaks_completed: u32,
+    pub breaks_skipped: u32,
+    pub total_rest_seconds: u64,
+    pub days_tracked: u32,
+}
+
+/// Outcome of a retention run or manual compaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionResult {
+    pub records_pruned: u32,
+    pub days_rolled_up: u32,
+    /// First day kept (YYYY-MM-DD), or None if retention is off.
+    pub cutoff: Option<String>,
+    pub vacuumed: bool,
+}
+
+/// What happened when the database couldn't be opened normally at startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    /// "salvaged", "restored_backup", "fresh", or "temporary" (running on an
+    /// in-memory database; nothing will be saved this session).
+    pub outcome: String,
+    /// The error that triggered recovery.
+    pub problem: String,
+    /// Where the damaged file was moved, if it was.
+    pub quarantined_path: Option<String>,
+    pub backup_used: Option<String>,
+    pub rows_salvaged: u32,
+    pub occurred_at: u64,
+}
+
+/// Where this session keeps its data, and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataLocation {
+    pub dir: String,
+    /// "cli", "env", "portable", "remembered", or "default".
+    pub source: String,
+}
+
+/// A database snapshot on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    /// "daily", "weekly", or "pre-restore".
+    pub kind: String,
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalyticsSummary {
     pub today: DailyStats,
@@ -98,10 +410,123 @@ pub struct AnalyticsSummary {
     pub last_30_days: Vec<DailyStats>,
     pub current_day_streak: u32,
     pub best_day_streak: u32,
+    /// Banked freezes that will cover the next missed workday.
+    pub streak_freezes_available: u32,
+    pub today_coverage: DayCoverage,
+    pub trends: Trends,
     pub lifetime_breaks: u64,
     pub lifetime_rest_seconds: u64,
 }
 
+/// Completed breaks in one window against the window just before it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodDelta {
+    pub current: u32,
+    pub previous: u32,
+    /// (current - previous) / previous; None when previous is 0.
+    pub change_rate: Option<f64>,
+}
+
+/// Today's completed breaks so far against recent days at the same time of
+/// day, in local time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pace {
+    /// Local time of day compared at, e.g. "14:05".
+    pub as_of: String,
+    pub breaks_so_far: u32,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    /// Recent days with at least one completed break.
+    pub days_compared: u32,
+}
+
+/// Averages and comparisons derived from the daily stats. Windows of whole
+/// days end yesterday, so a day in progress doesn't drag them down.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trends {
+    /// Mean completed breaks per day over the last 7 and 30 whole days.
+    pub avg_breaks_7_days: f64,
+    pub avg_breaks_30_days: f64,
+    /// Trailing 7-day and 30-day means for each entry of `last_30_days`.
+    pub rolling_7_days: Vec<f64>,
+    pub rolling_30_days: Vec<f64>,
+    /// Last 7 whole days against the 7 before.
+    pub week_over_week: PeriodDelta,
+    /// Last 30 whole days against the 30 before.
+    pub month_over_month: PeriodDelta,
+    /// None until there are enough recent days to compare with.
+    pub pace: Option<Pace>,
+}
+
+/// Breaks in one hour-of-day on one weekday, in local time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeatmapCell {
+    /// 0 = Monday .. 6 = Sunday
+    pub weekday: u8,
+    /// 0..=23
+    pub hour: u8,
+    pub breaks_completed: u32,
+    pub breaks_skipped: u32,
+    pub compliance_rate: f64,
+}
+
+/// Compliance by local weekday and hour over a date range: 168 cells, Monday
+/// 00:00 first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyHeatmap {
+    pub from: String,
+    pub to: String,
+    pub cells: Vec<HeatmapCell>,
+}
+
+/// Skipped breaks given one reason, by local hour of day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkipReasonStats {
+    /// `None` for skips without a reason.
+    pub reason: Option<String>,
+    pub count: u32,
+    /// 24 entries, local 00:00 first.
+    pub by_hour: Vec<u32>,
+}
+
+/// Skipped breaks over a date range, most common reason first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkipReasonBreakdown {
+    pub from: String,
+    pub to: String,
+    pub total_skipped: u32,
+    pub reasons: Vec<SkipReasonStats>,
+}
+
+/// A number behind an insight, already formatted for display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InsightEvidence {
+    pub label: String,
+    pub value: String,
+}
+
+/// A finding from one of the insight rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Insight {
+    /// The rule that found it: "skip_window", "weekday_dip", "weekly_change"
+    /// or "streak_break".
+    pub rule: String,
+    pub message: String,
+    pub evidence: Vec<InsightEvidence>,
+    /// How strong the finding is, 0..=1. Insights are ranked by it.
+    pub score: f64,
+}
+
+/// What the daily summary notification reports about one (UTC) day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailySummary {
+    pub date: String,
+    pub breaks_completed: u32,
+    pub breaks_skipped: u32,
+    pub insights: Vec<Insight>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OnboardingState {
     pub onboarding_completed: bool,
@@ -114,15 +539,41 @@ pub struct OnboardingState {
 /// Internal timer bookkeeping — not exposed via IPC.
 pub struct TimerInternalState {
     pub phase_before_pause: TimerPhase,
-    pub current_break_record_id: Option<i64>,
+    /// Start time of the break in progress; identifies its break record.
+    pub current_break_started_at: Option<u64>,
     pub work_started_at: u64,
+    /// Wall-clock ms of the last user input before the timer was suspended.
+    pub idle_since: u64,
+    /// Set while the user is away but the timer isn't suspended: paused (the
+    /// timeline logs that stretch as away) or mid-break (the break then ends
+    /// in Suspended).
+    pub away: bool,
+    /// Set while a due break is being held back by a meeting.
+    pub deferral: Option<ActiveDeferral>,
+    /// Wall-clock ms the open phase_log interval was last marked live.
+    pub phase_seen_at: u64,
+}
+
+/// A break deferral in progress. Kept apart from the work phase, so deferred
+/// minutes don't count as work before the break.
+pub struct ActiveDeferral {
+    pub started_at: u64,
+    /// Wall-clock ms to look for the meeting again.
+    pub recheck_at: u64,
 }
 
 pub struct AppState {
     pub timer: Mutex<TimerState>,
     pub settings: Mutex<UserSettings>,
     pub db_path: String,
+    pub data_location: DataLocation,
     pub timer_internal: Mutex<TimerInternalState>,
+    /// Set if the database needed recovery at startup; cleared once the user dismisses it.
+    pub recovery: Mutex<Option<RecoveryReport>>,
+    /// True when running on an in-memory database because the real one was unavailable.
+    pub db_is_temporary: bool,
 }
 
+/// Read-only connection for queries. All writes go through `DbWriter`, so a
+/// slow query never holds up the timer.
 pub struct DbConnection(pub Mutex<Connection>);
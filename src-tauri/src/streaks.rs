@@ -0,0 +1,385 @@
+use crate::db;
+use crate::state::UserSettings;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::collections::{BTreeMap, HashSet};
+
+/// Goal-meeting workdays in a row that earn one streak freeze.
+pub const FREEZE_EARN_DAYS: u32 = 7;
+
+/// Most freezes that can be banked at once.
+pub const MAX_FREEZES: u32 = 2;
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Weekdays that count toward streaks. Other days neither extend nor break one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Workdays([bool; 7]);
+
+impl Workdays {
+    pub const ALL: Workdays = Workdays([true; 7]);
+
+    /// Parse the `workdays` setting, a JSON array like `["mon","tue"]`.
+    /// None if it's malformed or names no day.
+    pub fn parse(json: &str) -> Option<Self> {
+        let names: Vec<String> = serde_json::from_str(json).ok()?;
+        let mut days = [false; 7];
+        for name in &names {
+            let i = DAY_NAMES.iter().position(|d| d == name)?;
+            days[i] = true;
+        }
+        days.contains(&true).then_some(Workdays(days))
+    }
+
+    /// The configured workdays, or every day if the setting is unusable.
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        Workdays::parse(&settings.workdays).unwrap_or(Workdays::ALL)
+    }
+
+    /// The inverse of `parse`.
+    pub fn to_json(self) -> String {
+        let names: Vec<&str> = DAY_NAMES
+            .iter()
+            .zip(self.0)
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| *name)
+            .collect();
+        serde_json::to_string(&names).unwrap_or_default()
+    }
+
+    pub fn is_workday(&self, date: NaiveDate) -> bool {
+        self.0[date.weekday().num_days_from_monday() as usize]
+    }
+}
+
+/// The daily goal in effect on each day, so raising the goal doesn't rewrite
+/// whether past days met it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalHistory {
+    changes: BTreeMap<NaiveDate, u32>,
+    current: u32,
+}
+
+impl GoalHistory {
+    /// Load from settings_history. Without any recorded change, `current_goal`
+    /// applies to every day.
+    pub fn load(conn: &Connection, current_goal: u32) -> SqlResult<Self> {
+        let changes = db::get_settings_history(conn)?
+            .into_iter()
+            .filter_map(|snap| {
+                NaiveDate::parse_from_str(&snap.effective_date, "%Y-%m-%d")
+                    .ok()
+                    .map(|d| (d, snap.daily_goal))
+            })
+            .collect();
+        Ok(GoalHistory {
+            changes,
+            current: current_goal,
+        })
+    }
+
+    pub fn goal_on(&self, date: NaiveDate) -> u32 {
+        self.changes
+            .range(..=date)
+            .next_back()
+            .map_or(self.current, |(_, goal)| *goal)
+    }
+
+    pub fn met(&self, date: NaiveDate, completed: u32) -> bool {
+        completed >= self.goal_on(date)
+    }
+}
+
+/// The first day in daily_stats_cache, if any.
+pub fn first_day(conn: &Connection) -> SqlResult<Option<NaiveDate>> {
+    let first: Option<String> =
+        conn.query_row("SELECT MIN(date) FROM daily_stats_cache", [], |row| {
+            row.get(0)
+        })?;
+    Ok(first.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()))
+}
+
+/// Breaks completed per day from `from` through `to`, from daily_stats_cache.
+pub fn completed_between(
+    conn: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> SqlResult<BTreeMap<NaiveDate, u32>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, breaks_completed FROM daily_stats_cache
+         WHERE date >= ?1 AND date <= ?2",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            from.format("%Y-%m-%d").to_string(),
+            to.format("%Y-%m-%d").to_string()
+        ],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)? as u32)),
+    )?;
+    let mut days = BTreeMap::new();
+    for row in rows {
+        let (date, completed) = row?;
+        if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            days.insert(date, completed);
+        }
+    }
+    Ok(days)
+}
+
+/// Streaks as of the end of some day: the run still going and the longest
+/// one so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreakRun {
+    pub current: u32,
+    pub best: u32,
+}
+
+impl StreakRun {
+    /// Fold in the days from `from` through `through`. A workday that met the
+    /// goal in effect that day extends the run, any other workday ends it;
+    /// rest days and frozen days do neither.
+    pub fn advance(
+        &mut self,
+        goals: &GoalHistory,
+        workdays: &Workdays,
+        completed: &BTreeMap<NaiveDate, u32>,
+        frozen: &HashSet<NaiveDate>,
+        from: NaiveDate,
+        through: NaiveDate,
+    ) {
+        let mut day = from;
+        while day <= through {
+            if workdays.is_workday(day) && !frozen.contains(&day) {
+                if goals.met(day, completed.get(&day).copied().unwrap_or(0)) {
+                    self.current += 1;
+                    self.best = self.best.max(self.current);
+                } else {
+                    self.current = 0;
+                }
+            }
+            day += Duration::days(1);
+        }
+    }
+}
+
+/// Days covered by a freeze.
+pub fn frozen_days(conn: &Connection) -> SqlResult<HashSet<NaiveDate>> {
+    let mut stmt = conn.prepare("SELECT date FROM streak_freezes")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut days = HashSet::new();
+    for row in rows {
+        if let Ok(date) = NaiveDate::parse_from_str(&row?, "%Y-%m-%d") {
+            days.insert(date);
+        }
+    }
+    Ok(days)
+}
+
+pub fn freezes_available(conn: &Connection) -> SqlResult<u32> {
+    conn.query_row(
+        "SELECT freezes_available FROM streak_state WHERE id = 1",
+        [],
+        |row| Ok(row.get::<_, i32>(0)? as u32),
+    )
+}
+
+/// Settle every day from the last one processed through yesterday: earn a
+/// freeze for each `FREEZE_EARN_DAYS` workdays in a row that met the goal in
+/// effect that day (`daily_goal` if none was recorded), and
+/// spend one on each missed workday if any are banked. Spent freezes are
+/// stored, so a day stays frozen even if the goal or history changes later.
+/// Returns how many days were frozen.
+pub fn apply_freezes(
+    conn: &Connection,
+    daily_goal: u32,
+    workdays: &Workdays,
+    today: NaiveDate,
+) -> SqlResult<u32> {
+    let (processed_through, mut run, mut available): (Option<String>, u32, u32) = conn
+        .query_row(
+            "SELECT processed_through, current_run, freezes_available
+             FROM streak_state WHERE id = 1",
+            [],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get::<_, i32>(1)? as u32,
+                    row.get::<_, i32>(2)? as u32,
+                ))
+            },
+        )
+        .optional()?
+        .unwrap_or((None, 0, 0));
+
+    let start = match processed_through.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+    {
+        Some(last) => last + Duration::days(1),
+        None => match first_day(conn)? {
+            Some(first) => first,
+            None => return Ok(0),
+        },
+    };
+    let yesterday = today - Duration::days(1);
+    if start > yesterday {
+        return Ok(0);
+    }
+    let goals = GoalHistory::load(conn, daily_goal)?;
+    let completed = completed_between(conn, start, yesterday)?;
+
+    let already_frozen = frozen_days(conn)?;
+    let now = Utc::now().timestamp_millis();
+    let tx = conn.unchecked_transaction()?;
+    let mut frozen = 0;
+    let mut day = start;
+    while day <= yesterday {
+        if workdays.is_workday(day) && !already_frozen.contains(&day) {
+            if goals.met(day, completed.get(&day).copied().unwrap_or(0)) {
+                run += 1;
+                if run % FREEZE_EARN_DAYS == 0 {
+                    available = (available + 1).min(MAX_FREEZES);
+                }
+            } else if available > 0 {
+                available -= 1;
+                frozen += 1;
+                tx.execute(
+                    "INSERT OR IGNORE INTO streak_freezes (date, frozen_at) VALUES (?1, ?2)",
+                    params![day.format("%Y-%m-%d").to_string(), now],
+                )?;
+            } else {
+                run = 0;
+            }
+        }
+        day += Duration::days(1);
+    }
+
+    tx.execute(
+        "UPDATE streak_state
+         SET processed_through = ?1, current_run = ?2, freezes_available = ?3
+         WHERE id = 1",
+        params![
+            yesterday.format("%Y-%m-%d").to_string(),
+            run as i32,
+            available as i32
+        ],
+    )?;
+    tx.commit()?;
+    Ok(frozen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn set_completed(conn: &Connection, day: NaiveDate, completed: u32) {
+        conn.execute(
+            "INSERT OR REPLACE INTO daily_stats_cache
+                (date, breaks_completed, breaks_skipped, total_rest_seconds, longest_streak, compliance_rate)
+             VALUES (?1, ?2, 0, 0, 0, 1.0)",
+            params![day.format("%Y-%m-%d").to_string(), completed],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_workdays() {
+        let weekdays = Workdays::parse(r#"["mon","tue","wed","thu","fri"]"#).unwrap();
+        // 2024-01-13 is a Saturday
+        assert!(!weekdays.is_workday(date(2024, 1, 13)));
+        assert!(weekdays.is_workday(date(2024, 1, 15)));
+        assert_eq!(
+            Workdays::parse(&UserSettings::default().workdays),
+            Some(Workdays::ALL)
+        );
+        assert_eq!(Workdays::parse("[]"), None);
+        assert_eq!(Workdays::parse(r#"["monday"]"#), None);
+        assert_eq!(Workdays::parse("mon"), None);
+        assert_eq!(Workdays::parse(&weekdays.to_json()), Some(weekdays));
+    }
+
+    #[test]
+    fn test_freeze_is_earned_then_spent_on_a_missed_day() {
+        let conn = setup_test_db();
+        // Seven goal days earn a freeze, day 8 is missed, day 9 met
+        let first = date(2024, 1, 1);
+        for i in 0..7 {
+            set_completed(&conn, first + Duration::days(i), 3);
+        }
+        set_completed(&conn, first + Duration::days(8), 3);
+
+        let frozen = apply_freezes(&conn, 3, &Workdays::ALL, date(2024, 1, 10)).unwrap();
+        assert_eq!(frozen, 1);
+        assert_eq!(
+            frozen_days(&conn).unwrap(),
+            HashSet::from([first + Duration::days(7)])
+        );
+        assert_eq!(freezes_available(&conn).unwrap(), 0);
+
+        // Already settled: a second pass changes nothing
+        assert_eq!(
+            apply_freezes(&conn, 3, &Workdays::ALL, date(2024, 1, 10)).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rest_days_are_skipped_and_misses_without_freezes_reset() {
+        let conn = setup_test_db();
+        let weekdays = Workdays::parse(r#"["mon","tue","wed","thu","fri"]"#).unwrap();
+        // Mon 2024-01-01 .. Fri 01-05 and Mon 01-08 .. Tue 01-09 met; the
+        // weekend in between doesn't interrupt the run of 7
+        for d in [1, 2, 3, 4, 5, 8, 9] {
+            set_completed(&conn, date(2024, 1, d), 1);
+        }
+        apply_freezes(&conn, 1, &weekdays, date(2024, 1, 10)).unwrap();
+        assert_eq!(freezes_available(&conn).unwrap(), 1);
+
+        // Wednesday and Thursday missed: one freeze, then the run resets
+        let frozen = apply_freezes(&conn, 1, &weekdays, date(2024, 1, 12)).unwrap();
+        assert_eq!(frozen, 1);
+        assert!(frozen_days(&conn).unwrap().contains(&date(2024, 1, 10)));
+        assert_eq!(freezes_available(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_banked_freezes_are_capped() {
+        let conn = setup_test_db();
+        let first = date(2024, 1, 1);
+        for i in 0..(FREEZE_EARN_DAYS * (MAX_FREEZES + 1)) as i64 {
+            set_completed(&conn, first + Duration::days(i), 1);
+        }
+        apply_freezes(&conn, 1, &Workdays::ALL, date(2024, 1, 22)).unwrap();
+        assert_eq!(freezes_available(&conn).unwrap(), MAX_FREEZES);
+    }
+
+    #[test]
+    fn test_goal_history_applies_the_goal_in_effect() {
+        let conn = setup_test_db();
+        assert_eq!(
+            GoalHistory::load(&conn, 8)
+                .unwrap()
+                .goal_on(date(2024, 1, 1)),
+            8
+        );
+
+        conn.execute_batch(
+            "INSERT INTO settings_history VALUES ('1970-01-01', 4, 20, 20);
+             INSERT INTO settings_history VALUES ('2024-01-10', 12, 20, 20);",
+        )
+        .unwrap();
+        let goals = GoalHistory::load(&conn, 12).unwrap();
+        assert_eq!(goals.goal_on(date(2024, 1, 9)), 4);
+        assert_eq!(goals.goal_on(date(2024, 1, 10)), 12);
+        assert!(goals.met(date(2024, 1, 9), 5));
+        assert!(!goals.met(date(2024, 1, 11), 5));
+    }
+}
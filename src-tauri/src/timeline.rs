@@ -0,0 +1,209 @@
+use crate::state::{DayTimeline, PhaseInterval, TimerPhase};
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+
+/// How often the open interval is marked live, so a crash loses at most this
+/// much of the timeline.
+pub const HEARTBEAT_MS: u64 = 60_000;
+
+/// Close the open interval at `at` and open a new one in `phase`. A no-op if
+/// the open interval is already in `phase`.
+pub fn record_phase(conn: &Connection, phase: &TimerPhase, at: u64) -> SqlResult<()> {
+    let open: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT phase, started_at FROM phase_log WHERE ended_at IS NULL
+             ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    // Transitions can be backdated (idle suspension starts at the last input),
+    // but never before the interval they close
+    let at = match &open {
+        Some((open_phase, _)) if open_phase == phase.as_str() => return Ok(()),
+        Some((_, started_at)) => at.max(*started_at as u64),
+        None => at,
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE phase_log SET ended_at = ?1, last_seen_at = ?1 WHERE ended_at IS NULL",
+        params![at as i64],
+    )?;
+    tx.execute(
+        "INSERT INTO phase_log (phase, started_at, last_seen_at) VALUES (?1, ?2, ?2)",
+        params![phase.as_str(), at as i64],
+    )?;
+    tx.commit()
+}
+
+/// Mark the open interval as still live at `at`.
+pub fn heartbeat(conn: &Connection, at: u64) -> SqlResult<()> {
+    conn.execute(
+        "UPDATE phase_log SET last_seen_at = ?1 WHERE ended_at IS NULL",
+        params![at as i64],
+    )?;
+    Ok(())
+}
+
+/// Close intervals left open by a previous session at the last time they were
+/// seen live. Call once at startup, before recording the initial phase.
+pub fn close_dangling(conn: &Connection) -> SqlResult<usize> {
+    conn.execute(
+        "UPDATE phase_log SET ended_at = last_seen_at WHERE ended_at IS NULL",
+        [],
+    )
+}
+
+/// Phase intervals overlapping `date` (YYYY-MM-DD, UTC), clipped to the day.
+pub fn get_timeline(conn: &Connection, date: &str) -> SqlResult<DayTimeline> {
+    get_timeline_at(conn, date, Utc::now().timestamp_millis() as u64)
+}
+
+pub fn get_timeline_at(conn: &Connection, date: &str, now: u64) -> SqlResult<DayTimeline> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+        rusqlite::Error::InvalidParameterName(format!("bad date '{}': {}", date, e))
+    })?;
+    let day_start = day
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis() as u64;
+    let day_end = day_start + 86_400_000;
+
+    let mut stmt = conn.prepare(
+        "SELECT phase, started_at, ended_at FROM phase_log
+         WHERE started_at < ?1 AND (ended_at IS NULL OR ended_at > ?2)
+         ORDER BY started_at ASC, id ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![day_end as i64, day_start as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as u64,
+                row.get::<_, Option<i64>>(2)?.map(|v| v as u64),
+            ))
+        })?
+        .collect::<SqlResult<Vec<_>>>()?;
+
+    let mut timeline = DayTimeline {
+        date: date.to_string(),
+        intervals: Vec::new(),
+        working_seconds: 0,
+        paused_seconds: 0,
+        suspended_seconds: 0,
+        breaking_seconds: 0,
+    };
+
+    for (phase, started_at, ended_at) in rows {
+        let Some(phase) = TimerPhase::parse(&phase) else {
+            continue;
+        };
+        let start = started_at.max(day_start);
+        // An open interval runs until now, or until midnight on a past day
+        let end = match ended_at {
+            Some(end) => Some(end.min(day_end)),
+            None if now >= day_end => Some(day_end),
+            None => None,
+        };
+        let seconds = end.unwrap_or(now).saturating_sub(start) / 1000;
+        match phase {
+            TimerPhase::Working => timeline.working_seconds += seconds,
+            TimerPhase::Paused => timeline.paused_seconds += seconds,
+            TimerPhase::Suspended => timeline.suspended_seconds += seconds,
+            TimerPhase::Breaking => timeline.breaking_seconds += seconds,
+        }
+        timeline.intervals.push(PhaseInterval {
+            phase,
+            started_at: start,
+            ended_at: end,
+        });
+    }
+
+    Ok(timeline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    // 2024-01-15T00:00:00Z
+    const DAY: u64 = 1_705_276_800_000;
+    const MIN: u64 = 60_000;
+
+    #[test]
+    fn test_transitions_build_contiguous_intervals() {
+        let conn = setup_test_db();
+        record_phase(&conn, &TimerPhase::Working, DAY + 60 * MIN).unwrap();
+        record_phase(&conn, &TimerPhase::Breaking, DAY + 80 * MIN).unwrap();
+        record_phase(&conn, &TimerPhase::Working, DAY + 81 * MIN).unwrap();
+        // Same phase again is not a new interval
+        record_phase(&conn, &TimerPhase::Working, DAY + 90 * MIN).unwrap();
+        record_phase(&conn, &TimerPhase::Paused, DAY + 100 * MIN).unwrap();
+
+        let timeline = get_timeline_at(&conn, "2024-01-15", DAY + 110 * MIN).unwrap();
+        let phases: Vec<_> = timeline.intervals.iter().map(|i| &i.phase).collect();
+        assert_eq!(
+            phases,
+            vec![
+                &TimerPhase::Working,
+                &TimerPhase::Breaking,
+                &TimerPhase::Working,
+                &TimerPhase::Paused,
+            ]
+        );
+        assert_eq!(timeline.working_seconds, 39 * 60);
+        assert_eq!(timeline.breaking_seconds, 60);
+        assert_eq!(timeline.paused_seconds, 10 * 60);
+        assert_eq!(timeline.intervals[3].ended_at, None);
+    }
+
+    #[test]
+    fn test_intervals_are_clipped_to_the_day() {
+        let conn = setup_test_db();
+        record_phase(&conn, &TimerPhase::Working, DAY - 30 * MIN).unwrap();
+        record_phase(&conn, &TimerPhase::Suspended, DAY + 30 * MIN).unwrap();
+
+        let timeline = get_timeline_at(&conn, "2024-01-15", DAY + 5 * 24 * 60 * MIN).unwrap();
+        assert_eq!(timeline.intervals[0].started_at, DAY);
+        assert_eq!(timeline.working_seconds, 30 * 60);
+        // Still open, but the day is over
+        assert_eq!(timeline.intervals[1].ended_at, Some(DAY + 86_400_000));
+
+        let before = get_timeline_at(&conn, "2024-01-14", DAY + MIN).unwrap();
+        assert_eq!(before.working_seconds, 30 * 60);
+        assert_eq!(before.intervals.len(), 1);
+    }
+
+    #[test]
+    fn test_backdated_transition_never_inverts_interval() {
+        let conn = setup_test_db();
+        record_phase(&conn, &TimerPhase::Working, DAY + 10 * MIN).unwrap();
+        record_phase(&conn, &TimerPhase::Suspended, DAY + 5 * MIN).unwrap();
+
+        let timeline = get_timeline_at(&conn, "2024-01-15", DAY + 20 * MIN).unwrap();
+        assert_eq!(timeline.intervals[0].ended_at, Some(DAY + 10 * MIN));
+        assert_eq!(timeline.intervals[1].started_at, DAY + 10 * MIN);
+        assert_eq!(timeline.working_seconds, 0);
+        assert_eq!(timeline.suspended_seconds, 10 * 60);
+    }
+
+    #[test]
+    fn test_close_dangling_uses_last_heartbeat() {
+        let conn = setup_test_db();
+        record_phase(&conn, &TimerPhase::Working, DAY).unwrap();
+        heartbeat(&conn, DAY + 15 * MIN).unwrap();
+
+        assert_eq!(close_dangling(&conn).unwrap(), 1);
+        let timeline = get_timeline_at(&conn, "2024-01-15", DAY + 60 * MIN).unwrap();
+        assert_eq!(timeline.intervals[0].ended_at, Some(DAY + 15 * MIN));
+        assert_eq!(timeline.working_seconds, 15 * 60);
+    }
+}
@@ -1,34 +1,150 @@
-use crate::state::{AppState, DbConnection, TimerPhase, TimerState};
-use chrono::Utc;
+use crate::db_writer::DbWriter;
+use crate::idle::{IdleEvent, IdleTracker};
+use crate::media::{is_media_playing, parse_allowlist, SessionBus};
+use crate::state::{
+    ActiveDeferral, AppState, BreakOutcome, BreakSource, DbConnection, TimerPhase, TimerState,
+};
+use crate::streaks::Workdays;
+use chrono::{Local, Utc};
+use rusqlite::Connection;
+use std::sync::Arc;
 use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
 use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
 
 /// Spawn the timer loop as an async background task.
 /// Call this from `.setup()` in lib.rs.
 pub fn start_timer_loop(app: &AppHandle) {
+    let (idle_tx, mut idle_rx) = mpsc::unbounded_channel();
+    start_idle_monitor(app, idle_tx);
+
     let handle = app.clone();
     tauri::async_runtime::spawn(async move {
-        let mut idle_check_counter: u32 = 0;
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // Probing capture devices touches /proc and PulseAudio, so it runs off
+        // the runtime. Ticks wait for it: the break it decides on is due now.
+        // The deadline is fixed when the check starts, so idle events handled
+        // in the meantime don't extend it.
+        let mut meeting_check: Option<(JoinHandle<Option<&'static str>>, Instant)> = None;
         loop {
-            tick(&handle, &mut idle_check_counter);
-            tokio::time::sleep(Duration::from_secs(1)).await;
+            tokio::select! {
+                _ = interval.tick(), if meeting_check.is_none() => {
+                    meeting_check = start_meeting_check(&handle)
+                        .map(|check| (check, Instant::now() + MEETING_CHECK_TIMEOUT));
+                    if meeting_check.is_none() {
+                        tick(&handle, None);
+                    }
+                }
+                result = async {
+                    let (check, deadline) = meeting_check.as_mut().unwrap();
+                    tokio::time::timeout_at(*deadline, check).await
+                }, if meeting_check.is_some() => {
+                    meeting_check = None;
+                    tick(&handle, result.ok().and_then(Result::ok).flatten());
+                }
+                Some(event) = idle_rx.recv() => handle_idle_event(&handle, event),
+            }
+        }
+    });
+}
+
+/// How long a snoozed break is put off.
+pub const SNOOZE_MINUTES: u64 = 5;
+
+/// Longest a due break waits on meeting detection before starting anyway.
+const MEETING_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest the idle monitor waits on the session bus before counting media as
+/// not playing. A query still running then is picked up on a later poll.
+const MEDIA_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Spawn the idle monitor. It samples system idle time on an adaptive schedule
+/// and sends the timer loop an event only when the user goes idle or comes back.
+fn start_idle_monitor(app: &AppHandle, tx: mpsc::UnboundedSender<IdleEvent>) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut tracker = IdleTracker::new();
+        let media_bus = Arc::new(SessionBus::new());
+        // D-Bus calls block, so they run off the runtime; at most one at a time
+        let mut media_query: Option<JoinHandle<bool>> = None;
+        loop {
+            let settings = handle.state::<AppState>().settings.lock().unwrap().clone();
+            let threshold_secs = settings.idle_pause_minutes as u64 * 60;
+
+            // 0 = idle detection disabled; None = not available on this platform
+            let idle_secs = if threshold_secs == 0 {
+                None
+            } else {
+                crate::idle::get_idle_seconds()
+            };
+
+            let (event, delay_secs) = match idle_secs {
+                Some(idle_secs) => {
+                    // Watching a video isn't being away — only ask the bus
+                    // once input alone would count as idle.
+                    let media_playing =
+                        if idle_secs >= threshold_secs && settings.media_activity_enabled {
+                            let query = media_query.get_or_insert_with(|| {
+                                let bus = media_bus.clone();
+                                let allowlist = parse_allowlist(&settings.media_player_allowlist);
+                                tauri::async_runtime::spawn_blocking(move || {
+                                    is_media_playing(&*bus, &allowlist)
+                                })
+                            });
+                            match tokio::time::timeout(MEDIA_QUERY_TIMEOUT, query).await {
+                                Ok(result) => {
+                                    media_query = None;
+                                    result.unwrap_or(false)
+                                }
+                                Err(_) => false,
+                            }
+                        } else {
+                            false
+                        };
+                    let idle_secs = if media_playing { 0 } else { idle_secs };
+                    let now_ms = Utc::now().timestamp_millis() as u64;
+                    let event = tracker.observe(threshold_secs, idle_secs, now_ms);
+                    (event, tracker.next_poll_secs(threshold_secs, idle_secs))
+                }
+                None => (tracker.disable(), crate::idle::MAX_POLL_SECS),
+            };
+
+            if let Some(event) = event {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(delay_secs)).await;
         }
     });
 }
 
 /// One tick of the timer state machine.
 /// Reads current state, computes remaining time via wall clock,
-/// handles phase transitions, emits events.
-fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
-    // Check for idle every 30 seconds
-    *idle_check_counter += 1;
-    if *idle_check_counter >= 30 {
-        *idle_check_counter = 0;
-        check_idle(app);
-    }
-
+/// handles phase transitions, emits events. `meeting_reason` is what meeting
+/// detection found, when it ran for this tick.
+fn tick(app: &AppHandle, meeting_reason: Option<&'static str>) {
     let now_ms = Utc::now().timestamp_millis() as u64;
 
+    // Keep the open timeline interval marked live so a crash loses little of it
+    let heartbeat_due = {
+        let state = app.state::<AppState>();
+        let mut internal = state.timer_internal.lock().unwrap();
+        let due = now_ms.saturating_sub(internal.phase_seen_at) >= crate::timeline::HEARTBEAT_MS;
+        if due {
+            internal.phase_seen_at = now_ms;
+        }
+        due
+    };
+    if heartbeat_due {
+        write(app, move |conn| {
+            let _ = crate::timeline::heartbeat(conn, now_ms);
+        });
+    }
+
     // Determine what transition (if any) should happen.
     // We gather everything we need, then release locks before doing I/O and events.
     let transition = {
@@ -43,7 +159,42 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
                 let remaining = timer.phase_duration.saturating_sub(elapsed_secs);
                 timer.seconds_remaining = remaining;
 
-                if remaining == 0 {
+                let defer_reason = meeting_reason.filter(|_| {
+                    crate::meeting::within_defer_cap(
+                        internal.deferral.as_ref().map(|d| d.started_at),
+                        now_ms,
+                        settings.meeting_max_defer_minutes,
+                    )
+                });
+                let recheck_at = internal
+                    .deferral
+                    .as_ref()
+                    .map(|d| d.recheck_at)
+                    .filter(|at| *at > now_ms);
+
+                if let (0, Some(recheck_at)) = (remaining, recheck_at) {
+                    // Held back by a meeting; the deferral isn't work time,
+                    // so it counts down on its own
+                    timer.seconds_remaining = (recheck_at - now_ms).div_ceil(1000);
+                    Transition::Tick(timer.clone())
+                } else if let (0, Some(reason)) = (remaining, defer_reason) {
+                    // In a meeting — push the break back and look again shortly
+                    let recheck = crate::meeting::DEFER_RECHECK_SECS;
+                    timer.seconds_remaining = recheck;
+
+                    let is_new = internal.deferral.is_none();
+                    let deferral = internal.deferral.get_or_insert(ActiveDeferral {
+                        started_at: now_ms,
+                        recheck_at: 0,
+                    });
+                    deferral.recheck_at = now_ms + recheck * 1000;
+
+                    Transition::DeferBreak {
+                        snapshot: timer.clone(),
+                        reason,
+                        is_new,
+                    }
+                } else if remaining == 0 {
                     // Working → Breaking
                     let break_duration = settings.break_duration_seconds as u64;
                     let preceding_work = timer.phase_duration as u32;
@@ -54,11 +205,17 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
                     timer.phase_started_at = now_ms;
 
                     internal.work_started_at = 0;
+                    // Later writes find the break record by its start time,
+                    // since the writer runs them in order
+                    internal.current_break_started_at = Some(now_ms);
+                    internal.away = false;
+                    let deferral = internal.deferral.take();
 
                     let snapshot = timer.clone();
                     Transition::StartBreak {
                         snapshot,
                         preceding_work,
+                        deferral,
                         notification_enabled: settings.notification_enabled,
                         overlay_enabled: settings.overlay_enabled,
                     }
@@ -73,14 +230,20 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
 
                 if remaining == 0 {
                     // Breaking → Working (or Paused if onboarding not complete)
-                    let break_record_id = internal.current_break_record_id.take();
+                    let break_started_at = internal.current_break_started_at.take();
                     let actual_duration = timer.phase_duration as u32;
                     let is_demo = !settings.onboarding_completed;
 
                     let work_duration = settings.work_interval_minutes as u64 * 60;
+                    let away = std::mem::take(&mut internal.away) && !is_demo;
                     if is_demo {
                         // Demo break during onboarding — return to Paused
                         timer.phase = TimerPhase::Paused;
+                    } else if away {
+                        // Ended while the user was away: suspend until they're back
+                        timer.phase = TimerPhase::Suspended;
+                        internal.phase_before_pause = TimerPhase::Working;
+                        timer.breaks_completed_today += 1;
                     } else {
                         timer.phase = TimerPhase::Working;
                         timer.breaks_completed_today += 1;
@@ -94,8 +257,10 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
                     let snapshot = timer.clone();
                     Transition::CompleteBreak {
                         snapshot,
-                        break_record_id,
+                        break_started_at,
                         actual_duration,
+                        is_demo,
+                        source: away.then_some(BreakSource::Idle),
                         overlay_enabled: settings.overlay_enabled,
                     }
                 } else {
@@ -111,26 +276,42 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
 
     // Now handle side effects outside of locks
     match transition {
+        Transition::DeferBreak {
+            snapshot,
+            reason,
+            is_new,
+        } => {
+            if is_new {
+                eprintln!("[timer] Deferring break: {} in use", reason);
+                write(app, move |conn| {
+                    if let Err(e) = crate::db::insert_break_deferral(conn, now_ms, reason) {
+                        eprintln!("[timer] Failed to insert break deferral: {}", e);
+                    }
+                });
+            }
+
+            crate::tray::update_tray_status(app, &snapshot.phase, snapshot.seconds_remaining);
+            let _ = app.emit("break-deferred", reason);
+            let _ = app.emit("timer-tick", &snapshot);
+        }
         Transition::StartBreak {
             snapshot,
             preceding_work,
+            deferral,
             notification_enabled,
             overlay_enabled,
         } => {
+            if let Some(deferral) = deferral {
+                finish_deferral(app, deferral, now_ms);
+            }
+            log_phase(app, &snapshot.phase, now_ms);
+
             // Insert break record in DB
-            if let Some(db_conn) = try_state::<DbConnection>(app) {
-                let db = db_conn.0.lock().unwrap();
-                match crate::db::insert_break_record(&db, now_ms, preceding_work) {
-                    Ok(id) => {
-                        let state = app.state::<AppState>();
-                        let mut internal = state.timer_internal.lock().unwrap();
-                        internal.current_break_record_id = Some(id);
-                    }
-                    Err(e) => {
-                        eprintln!("[timer] Failed to insert break record: {}", e);
-                    }
+            write(app, move |conn| {
+                if let Err(e) = crate::db::insert_break_record(conn, now_ms, preceding_work) {
+                    eprintln!("[timer] Failed to insert break record: {}", e);
                 }
-            }
+            });
 
             if notification_enabled {
                 crate::notifications::send_break_notification(app);
@@ -145,39 +326,45 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
         }
         Transition::CompleteBreak {
             snapshot,
-            break_record_id,
+            break_started_at,
             actual_duration,
+            is_demo,
+            source,
             overlay_enabled,
         } => {
-            // Finalize break record in DB (only for real breaks, not demo)
-            if let Some(id) = break_record_id {
-                if let Some(db_conn) = try_state::<DbConnection>(app) {
-                    let db = db_conn.0.lock().unwrap();
-                    let _ =
-                        crate::db::update_break_completion(&db, id, actual_duration, true, false);
-                    let today = Utc::now().format("%Y-%m-%d").to_string();
-                    let _ = crate::db::recompute_daily_stats(&db, &today);
-                }
+            log_phase(app, &snapshot.phase, now_ms);
+
+            // Finalize break record in DB
+            if let Some(started_at) = break_started_at {
+                let outcome = if is_demo {
+                    BreakOutcome::Demo
+                } else {
+                    BreakOutcome::Completed
+                };
+                finish_break(app, started_at, actual_duration, outcome, source, None);
             }
 
             // Check if this was the first real break (not a demo break)
             {
                 let state = app.state::<AppState>();
                 let mut settings = state.settings.lock().unwrap();
-                if settings.onboarding_completed
-                    && !settings.first_break_completed
-                    && break_record_id.is_some()
-                {
+                if !is_demo && !settings.first_break_completed && break_started_at.is_some() {
                     settings.first_break_completed = true;
-                    if let Some(db_conn) = try_state::<DbConnection>(app) {
-                        let db = db_conn.0.lock().unwrap();
-                        let _ = crate::db::save_settings(&db, &settings);
-                    }
+                    let snapshot = settings.clone();
+                    write(app, move |conn| {
+                        let _ = crate::db::save_settings(conn, &snapshot);
+                    });
                     drop(settings);
                     let _ = app.emit("first-break-celebrated", ());
                 }
             }
 
+            if !is_demo && break_started_at.is_some() {
+                // Rolls the stored totals over if the day changed mid-session
+                crate::maintenance::run_rollover(app);
+                check_achievements(app);
+            }
+
             if overlay_enabled {
                 crate::overlay::hide_overlay(app);
             }
@@ -193,6 +380,139 @@ fn tick(app: &AppHandle, idle_check_counter: &mut u32) {
     }
 }
 
+/// True if the timer is Working and the interval has run out.
+fn break_is_due(app: &AppHandle, now_ms: u64) -> bool {
+    let state = app.state::<AppState>();
+    let timer = state.timer.lock().unwrap();
+    let internal = state.timer_internal.lock().unwrap();
+    timer.phase == TimerPhase::Working
+        && now_ms.saturating_sub(timer.phase_started_at) / 1000 >= timer.phase_duration
+        && internal
+            .deferral
+            .as_ref()
+            .is_none_or(|d| d.recheck_at <= now_ms)
+}
+
+/// Start meeting detection on a blocking thread if a break is due and
+/// detection is on. None if there's nothing to check.
+fn start_meeting_check(app: &AppHandle) -> Option<JoinHandle<Option<&'static str>>> {
+    let enabled = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .meeting_detection_enabled;
+    let now_ms = Utc::now().timestamp_millis() as u64;
+    if !enabled || !break_is_due(app, now_ms) {
+        return None;
+    }
+    Some(tauri::async_runtime::spawn_blocking(|| {
+        crate::meeting::detect().reason()
+    }))
+}
+
+/// Queue a write on the database writer thread. Never blocks, so the tick
+/// can't stall behind a slow query.
+pub fn write(app: &AppHandle, job: impl FnOnce(&mut Connection) + Send + 'static) {
+    if let Some(writer) = try_state::<DbWriter>(app) {
+        writer.send(job);
+    }
+}
+
+/// Append a phase change to the timeline.
+pub fn log_phase(app: &AppHandle, phase: &TimerPhase, at: u64) {
+    let phase = phase.clone();
+    write(app, move |conn| {
+        if let Err(e) = crate::timeline::record_phase(conn, &phase, at) {
+            eprintln!("[timer] Failed to log phase change: {}", e);
+        }
+    });
+}
+
+/// Finalize the break that started at `started_at` and refresh today's stats.
+fn finish_break(
+    app: &AppHandle,
+    started_at: u64,
+    duration_seconds: u32,
+    outcome: BreakOutcome,
+    source: Option<BreakSource>,
+    skip_reason: Option<String>,
+) {
+    write(app, move |conn| {
+        if let Ok(Some(id)) = crate::db::open_break_record_id(conn, started_at) {
+            if let Err(e) = crate::db::finish_break_record(
+                conn,
+                id,
+                duration_seconds,
+                outcome,
+                source,
+                skip_reason.as_deref(),
+            ) {
+                eprintln!("[timer] Failed to finish break record: {}", e);
+            }
+        }
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let _ = crate::db::recompute_daily_stats(conn, &today);
+    });
+}
+
+/// Unlock any achievements now earned, announcing each with an
+/// `achievement-unlocked` event and a notification. The rules run on the
+/// reader once the writes queued before this (finishing the break) are in;
+/// only the unlocks go through the writer.
+pub fn check_achievements(app: &AppHandle) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let workdays = Workdays::from_settings(&settings);
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(writer) = try_state::<DbWriter>(&handle) else {
+            return;
+        };
+        if writer.call(|_| ()).is_err() {
+            return;
+        }
+        let earned = {
+            let db = handle.state::<DbConnection>();
+            let conn = db.0.lock().unwrap();
+            crate::achievements::newly_earned(&conn, settings.daily_goal, &workdays, &Local::now())
+        };
+        let earned = match earned {
+            Ok(earned) if earned.is_empty() => return,
+            Ok(earned) => earned,
+            Err(e) => {
+                eprintln!("[timer] Failed to check achievements: {}", e);
+                return;
+            }
+        };
+        let app = handle.clone();
+        writer.send(move |conn| {
+            let unlocked = match crate::achievements::unlock(conn, &earned) {
+                Ok(unlocked) => unlocked,
+                Err(e) => {
+                    eprintln!("[timer] Failed to unlock achievements: {}", e);
+                    return;
+                }
+            };
+            for achievement in unlocked {
+                if settings.notification_enabled {
+                    crate::notifications::send_achievement_notification(&app, &achievement);
+                }
+                let _ = app.emit("achievement-unlocked", &achievement);
+            }
+        });
+    });
+}
+
+/// Record how long a deferral lasted.
+fn finish_deferral(app: &AppHandle, deferral: ActiveDeferral, now_ms: u64) {
+    let deferred_secs = (now_ms.saturating_sub(deferral.started_at) / 1000) as u32;
+    write(app, move |conn| {
+        if let Ok(Some(id)) = crate::db::break_deferral_id(conn, deferral.started_at) {
+            let _ = crate::db::finish_break_deferral(conn, id, deferred_secs);
+        }
+    });
+}
+
 /// Try to get managed state. Returns None if not yet managed (shouldn't happen in practice).
 fn try_state<T: Send + Sync + 'static>(app: &AppHandle) -> Option<tauri::State<'_, T>> {
     app.try_state::<T>()
@@ -222,6 +542,7 @@ pub fn pause(app: &AppHandle) -> TimerState {
     drop(internal);
     drop(timer);
 
+    log_phase(app, &result.phase, now_ms);
     crate::tray::update_tray_status(app, &result.phase, result.seconds_remaining);
     let _ = app.emit("timer-paused", &result);
     let _ = app.emit("timer-tick", &result);
@@ -233,11 +554,12 @@ pub fn pause(app: &AppHandle) -> TimerState {
 pub fn resume(app: &AppHandle) -> TimerState {
     let state = app.state::<AppState>();
     let mut timer = state.timer.lock().unwrap();
-    let internal = state.timer_internal.lock().unwrap();
+    let mut internal = state.timer_internal.lock().unwrap();
 
     if timer.phase != TimerPhase::Paused {
         return timer.clone();
     }
+    internal.away = false;
 
     let now_ms = Utc::now().timestamp_millis() as u64;
 
@@ -252,6 +574,7 @@ pub fn resume(app: &AppHandle) -> TimerState {
     drop(internal);
     drop(timer);
 
+    log_phase(app, &result.phase, now_ms);
     crate::tray::update_tray_status(app, &result.phase, result.seconds_remaining);
     let _ = app.emit("timer-resumed", &result);
     let _ = app.emit("timer-tick", &result);
@@ -260,7 +583,7 @@ pub fn resume(app: &AppHandle) -> TimerState {
 }
 
 /// Skip the current break. Logs it as skipped, returns to Working.
-pub fn skip_break(app: &AppHandle) -> TimerState {
+pub fn skip_break(app: &AppHandle, source: BreakSource, reason: Option<String>) -> TimerState {
     let state = app.state::<AppState>();
     let mut timer = state.timer.lock().unwrap();
     let mut internal = state.timer_internal.lock().unwrap();
@@ -272,7 +595,7 @@ pub fn skip_break(app: &AppHandle) -> TimerState {
 
     let now_ms = Utc::now().timestamp_millis() as u64;
     let elapsed = ((now_ms.saturating_sub(timer.phase_started_at)) / 1000) as u32;
-    let break_record_id = internal.current_break_record_id.take();
+    let break_started_at = internal.current_break_started_at.take();
 
     // Reset to working
     let settings = state.settings.lock().unwrap().clone();
@@ -290,15 +613,18 @@ pub fn skip_break(app: &AppHandle) -> TimerState {
     drop(timer);
 
     // Update break record as skipped
-    if let Some(id) = break_record_id {
-        if let Some(db_conn) = try_state::<DbConnection>(app) {
-            let db = db_conn.0.lock().unwrap();
-            let _ = crate::db::update_break_completion(&db, id, elapsed, false, true);
-            let today = Utc::now().format("%Y-%m-%d").to_string();
-            let _ = crate::db::recompute_daily_stats(&db, &today);
-        }
+    if let Some(started_at) = break_started_at {
+        finish_break(
+            app,
+            started_at,
+            elapsed,
+            BreakOutcome::Skipped,
+            Some(source),
+            reason,
+        );
     }
 
+    log_phase(app, &result.phase, now_ms);
     crate::overlay::hide_overlay(app);
     crate::tray::update_tray_status(app, &result.phase, result.seconds_remaining);
     let _ = app.emit("break-skipped", &result);
@@ -307,24 +633,76 @@ pub fn skip_break(app: &AppHandle) -> TimerState {
     result
 }
 
+/// Put the current break off for `SNOOZE_MINUTES`. Logged as snoozed, which
+/// doesn't count against compliance; the break comes back after a short stretch
+/// of work instead of a full interval.
+pub fn snooze_break(app: &AppHandle, source: BreakSource) -> TimerState {
+    let state = app.state::<AppState>();
+    let mut timer = state.timer.lock().unwrap();
+    let mut internal = state.timer_internal.lock().unwrap();
+
+    // Only real breaks can be snoozed; the onboarding demo just runs out
+    let onboarding_completed = state.settings.lock().unwrap().onboarding_completed;
+    if timer.phase != TimerPhase::Breaking || !onboarding_completed {
+        return timer.clone();
+    }
+
+    let now_ms = Utc::now().timestamp_millis() as u64;
+    let elapsed = ((now_ms.saturating_sub(timer.phase_started_at)) / 1000) as u32;
+    let break_started_at = internal.current_break_started_at.take();
+
+    let snooze = SNOOZE_MINUTES * 60;
+    timer.phase = TimerPhase::Working;
+    timer.phase_duration = snooze;
+    timer.seconds_remaining = snooze;
+    timer.phase_started_at = now_ms;
+
+    internal.work_started_at = now_ms;
+
+    let result = timer.clone();
+    drop(internal);
+    drop(timer);
+
+    if let Some(started_at) = break_started_at {
+        finish_break(
+            app,
+            started_at,
+            elapsed,
+            BreakOutcome::Snoozed,
+            Some(source),
+            None,
+        );
+    }
+
+    log_phase(app, &result.phase, now_ms);
+    crate::overlay::hide_overlay(app);
+    crate::tray::update_tray_status(app, &result.phase, result.seconds_remaining);
+    let _ = app.emit("break-snoozed", &result);
+    let _ = app.emit("timer-tick", &result);
+
+    result
+}
+
 /// Reset the timer to a fresh work interval.
-pub fn reset(app: &AppHandle) -> TimerState {
+pub fn reset(app: &AppHandle, source: BreakSource) -> TimerState {
     let state = app.state::<AppState>();
     let mut timer = state.timer.lock().unwrap();
     let mut internal = state.timer_internal.lock().unwrap();
 
     let now_ms = Utc::now().timestamp_millis() as u64;
 
-    // If in a break, mark it as skipped
-    let break_record_id = if timer.phase == TimerPhase::Breaking {
+    // If in a break, close it out as reset (not a user skip)
+    let break_started_at = if timer.phase == TimerPhase::Breaking {
         let elapsed = ((now_ms.saturating_sub(timer.phase_started_at)) / 1000) as u32;
-        let id = internal.current_break_record_id.take();
-        id.map(|id| (id, elapsed))
+        let started_at = internal.current_break_started_at.take();
+        started_at.map(|started_at| (started_at, elapsed))
     } else {
-        internal.current_break_record_id.take();
+        internal.current_break_started_at.take();
         None
     };
 
+    let deferral = internal.deferral.take();
+
     let settings = state.settings.lock().unwrap().clone();
     let work_duration = settings.work_interval_minutes as u64 * 60;
 
@@ -339,16 +717,23 @@ pub fn reset(app: &AppHandle) -> TimerState {
     drop(internal);
     drop(timer);
 
+    if let Some(deferral) = deferral {
+        finish_deferral(app, deferral, now_ms);
+    }
+
     // Finalize any in-progress break record
-    if let Some((id, elapsed)) = break_record_id {
-        if let Some(db_conn) = try_state::<DbConnection>(app) {
-            let db = db_conn.0.lock().unwrap();
-            let _ = crate::db::update_break_completion(&db, id, elapsed, false, true);
-            let today = Utc::now().format("%Y-%m-%d").to_string();
-            let _ = crate::db::recompute_daily_stats(&db, &today);
-        }
+    if let Some((started_at, elapsed)) = break_started_at {
+        finish_break(
+            app,
+            started_at,
+            elapsed,
+            BreakOutcome::Reset,
+            Some(source),
+            None,
+        );
     }
 
+    log_phase(app, &result.phase, now_ms);
     crate::overlay::hide_overlay(app);
     crate::tray::update_tray_status(app, &result.phase, result.seconds_remaining);
     let _ = app.emit("timer-tick", &result);
@@ -356,71 +741,109 @@ pub fn reset(app: &AppHandle) -> TimerState {
     result
 }
 
-/// Check system idle time and transition to/from Suspended as needed.
-/// Called every ~30 seconds from the timer loop.
-fn check_idle(app: &AppHandle) {
+/// Transition to/from Suspended in response to an idle monitor event.
+fn handle_idle_event(app: &AppHandle, event: IdleEvent) {
     let state = app.state::<AppState>();
     let settings = state.settings.lock().unwrap().clone();
+    let now_ms = Utc::now().timestamp_millis() as u64;
 
-    // 0 = idle detection disabled
-    if settings.idle_pause_minutes == 0 {
-        return;
-    }
+    match event {
+        IdleEvent::BecameIdle { idle_secs } => {
+            let idle_since = now_ms.saturating_sub(idle_secs * 1000);
+
+            // Suspend if currently Working
+            let mut timer = state.timer.lock().unwrap();
+            let mut internal = state.timer_internal.lock().unwrap();
+            if timer.phase == TimerPhase::Paused {
+                // The timer stays paused, but nobody is working through it
+                // anymore; the timeline shows the stretch as away.
+                internal.away = true;
+                drop(internal);
+                drop(timer);
+                log_phase(app, &TimerPhase::Suspended, idle_since);
+                return;
+            }
+            if timer.phase == TimerPhase::Breaking {
+                // Stepping away is what the break is for, so it runs on; if
+                // it ends before the user is back, work waits for them.
+                internal.away = true;
+                internal.idle_since = idle_since;
+                return;
+            }
+            if timer.phase != TimerPhase::Working {
+                return;
+            }
 
-    let threshold_secs = settings.idle_pause_minutes as u64 * 60;
-    let idle_secs = match crate::idle::get_idle_seconds() {
-        Some(s) => s,
-        None => return, // Detection not available on this platform
-    };
+            // Freeze remaining time as of the last input — the idle stretch
+            // before the threshold was hit wasn't really work.
+            let elapsed = idle_since.saturating_sub(timer.phase_started_at) / 1000;
+            timer.seconds_remaining = timer.phase_duration.saturating_sub(elapsed);
+            internal.phase_before_pause = TimerPhase::Working;
+            internal.idle_since = idle_since;
+            timer.phase = TimerPhase::Suspended;
 
-    if idle_secs >= threshold_secs {
-        // Suspend if currently Working
-        let mut timer = state.timer.lock().unwrap();
-        if timer.phase != TimerPhase::Working {
-            return;
-        }
+            let snapshot = timer.clone();
+            drop(internal);
+            drop(timer);
 
-        let mut internal = state.timer_internal.lock().unwrap();
-        let now_ms = Utc::now().timestamp_millis() as u64;
-
-        // Freeze remaining time using wall clock
-        let elapsed = now_ms.saturating_sub(timer.phase_started_at) / 1000;
-        timer.seconds_remaining = timer.phase_duration.saturating_sub(elapsed);
-        internal.phase_before_pause = TimerPhase::Working;
-        timer.phase = TimerPhase::Suspended;
-
-        let snapshot = timer.clone();
-        drop(internal);
-        drop(timer);
-
-        crate::tray::update_tray_status(app, &snapshot.phase, snapshot.seconds_remaining);
-        let _ = app.emit("timer-paused", &snapshot);
-        let _ = app.emit("timer-tick", &snapshot);
-    } else if idle_secs < threshold_secs {
-        // Resume with fresh work interval if currently Suspended
-        let mut timer = state.timer.lock().unwrap();
-        if timer.phase != TimerPhase::Suspended {
-            return;
+            log_phase(app, &snapshot.phase, idle_since);
+            crate::tray::update_tray_status(app, &snapshot.phase, snapshot.seconds_remaining);
+            let _ = app.emit("timer-paused", &snapshot);
+            let _ = app.emit("timer-tick", &snapshot);
         }
+        IdleEvent::BecameActive => {
+            // Resume if currently Suspended, per the configured policy
+            let mut timer = state.timer.lock().unwrap();
+            let mut internal = state.timer_internal.lock().unwrap();
+            if std::mem::take(&mut internal.away) {
+                match timer.phase {
+                    TimerPhase::Paused => {
+                        drop(internal);
+                        drop(timer);
+                        log_phase(app, &TimerPhase::Paused, now_ms);
+                        return;
+                    }
+                    TimerPhase::Breaking => return,
+                    _ => {}
+                }
+            }
+            if timer.phase != TimerPhase::Suspended {
+                return;
+            }
 
-        let now_ms = Utc::now().timestamp_millis() as u64;
-        let work_duration = settings.work_interval_minutes as u64 * 60;
-
-        timer.phase = TimerPhase::Working;
-        timer.phase_duration = work_duration;
-        timer.seconds_remaining = work_duration;
-        timer.phase_started_at = now_ms;
-
-        let mut internal = state.timer_internal.lock().unwrap();
-        internal.work_started_at = now_ms;
+            let work_duration = settings.work_interval_minutes as u64 * 60;
+            let away_secs = now_ms.saturating_sub(internal.idle_since) / 1000;
+            let remaining = crate::idle::work_seconds_after_idle(
+                &settings.idle_resume_policy,
+                timer.seconds_remaining,
+                work_duration,
+                away_secs,
+                settings.idle_long_away_minutes as u64 * 60,
+            );
+
+            timer.phase = TimerPhase::Working;
+            if remaining == work_duration {
+                timer.phase_duration = work_duration;
+                timer.seconds_remaining = work_duration;
+                timer.phase_started_at = now_ms;
+                internal.work_started_at = now_ms;
+            } else {
+                // Same trick as resume(): backdate the start so wall-clock math
+                // yields the remaining time.
+                let elapsed_before = timer.phase_duration.saturating_sub(remaining);
+                timer.seconds_remaining = remaining;
+                timer.phase_started_at = now_ms - (elapsed_before * 1000);
+            }
 
-        let snapshot = timer.clone();
-        drop(internal);
-        drop(timer);
+            let snapshot = timer.clone();
+            drop(internal);
+            drop(timer);
 
-        crate::tray::update_tray_status(app, &snapshot.phase, snapshot.seconds_remaining);
-        let _ = app.emit("timer-resumed", &snapshot);
-        let _ = app.emit("timer-tick", &snapshot);
+            log_phase(app, &snapshot.phase, now_ms);
+            crate::tray::update_tray_status(app, &snapshot.phase, snapshot.seconds_remaining);
+            let _ = app.emit("timer-resumed", &snapshot);
+            let _ = app.emit("timer-tick", &snapshot);
+        }
     }
 }
 
@@ -428,16 +851,24 @@ fn check_idle(app: &AppHandle) {
 /// so we can release locks before performing side effects.
 enum Transition {
     Tick(TimerState),
+    DeferBreak {
+        snapshot: TimerState,
+        reason: &'static str,
+        is_new: bool,
+    },
     StartBreak {
         snapshot: TimerState,
         preceding_work: u32,
+        deferral: Option<ActiveDeferral>,
         notification_enabled: bool,
         overlay_enabled: bool,
     },
     CompleteBreak {
         snapshot: TimerState,
-        break_record_id: Option<i64>,
+        break_started_at: Option<u64>,
         actual_duration: u32,
+        is_demo: bool,
+        source: Option<BreakSource>,
         overlay_enabled: bool,
     },
 }
@@ -1,4 +1,4 @@
-use crate::state::{AppState, TimerPhase};
+use crate::state::{AppState, BreakSource, TimerPhase};
 use tauri::{
     image::Image,
     menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem},
@@ -33,6 +33,8 @@ pub fn create_tray(app: &AppHandle) -> Result<TrayMenuState, Box<dyn std::error:
     let status_item = MenuItem::with_id(app, "status", "Next break in 20:00", false, None::<&str>)?;
     let pause_item = MenuItem::with_id(app, "pause_resume", "Pause", true, None::<&str>)?;
     let skip_item = MenuItem::with_id(app, "skip_break", "Skip Break", true, None::<&str>)?;
+    let snooze_label = format!("Snooze Break {} Minutes", crate::timer::SNOOZE_MINUTES);
+    let snooze_item = MenuItem::with_id(app, "snooze_break", snooze_label, true, None::<&str>)?;
     let reset_item = MenuItem::with_id(app, "reset_timer", "Reset Timer", true, None::<&str>)?;
     let dashboard_item =
         MenuItem::with_id(app, "open_dashboard", "Open Dashboard", true, None::<&str>)?;
@@ -50,6 +52,7 @@ pub fn create_tray(app: &AppHandle) -> Result<TrayMenuState, Box<dyn std::error:
             &sep1,
             &pause_item,
             &skip_item,
+            &snooze_item,
             &reset_item,
             &sep2,
             &dashboard_item,
@@ -92,10 +95,13 @@ fn handle_menu_event(app: &AppHandle, event: &MenuEvent) {
             }
         }
         "skip_break" => {
-            crate::timer::skip_break(app);
+            crate::timer::skip_break(app, BreakSource::Tray, None);
+        }
+        "snooze_break" => {
+            crate::timer::snooze_break(app, BreakSource::Tray);
         }
         "reset_timer" => {
-            crate::timer::reset(app);
+            crate::timer::reset(app, BreakSource::Tray);
         }
         "open_dashboard" => {
             if let Some(window) = app.get_webview_window("main") {
@@ -0,0 +1,218 @@
+use crate::analytics::local_midnight;
+use crate::db;
+use crate::state::{Pace, PeriodDelta, Trends};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection, Result as SqlResult};
+use std::collections::HashMap;
+
+/// Recent days looked at for the pace bands.
+const PACE_WINDOW_DAYS: i64 = 30;
+
+/// Fewer active days than this and there's no "usual" to compare with.
+const MIN_PACE_DAYS: usize = 5;
+
+/// Averages, deltas and today's pace. `today` is the UTC day, matching
+/// daily_stats_cache; the pace compares local times of day.
+pub fn build_trends(conn: &Connection, today: NaiveDate) -> SqlResult<Trends> {
+    // 60 whole days plus today: enough for month over month and for a
+    // trailing 30-day mean at the start of the last 30 days
+    let from = today - Duration::days(60);
+    let days = db::get_daily_stats_range(
+        conn,
+        &from.format("%Y-%m-%d").to_string(),
+        &today.format("%Y-%m-%d").to_string(),
+    )?;
+    let completed: Vec<u32> = days.iter().map(|d| d.breaks_completed).collect();
+
+    let mut trends = summarize(&completed);
+    trends.pace = pace_in(conn, &Local::now())?;
+    Ok(trends)
+}
+
+/// Everything but the pace, from completed breaks per day, oldest first and
+/// ending with today.
+fn summarize(completed: &[u32]) -> Trends {
+    let today = completed.len() - 1;
+    // Sum of the `len` whole days ending `ago` days before today
+    let window = |ago: usize, len: usize| -> u32 {
+        let end = today.saturating_sub(ago);
+        completed[end.saturating_sub(len - 1)..=end].iter().sum()
+    };
+
+    // Trailing `len`-day mean for each of the last 30 days, over fewer days
+    // where the history is shorter
+    let rolling = |len: usize| -> Vec<f64> {
+        (today.saturating_sub(29)..=today)
+            .map(|i| {
+                let start = i.saturating_sub(len - 1);
+                completed[start..=i].iter().sum::<u32>() as f64 / (i - start + 1) as f64
+            })
+            .collect()
+    };
+
+    Trends {
+        avg_breaks_7_days: window(1, 7) as f64 / 7.0,
+        avg_breaks_30_days: window(1, 30) as f64 / 30.0,
+        rolling_7_days: rolling(7),
+        rolling_30_days: rolling(30),
+        week_over_week: delta(window(1, 7), window(8, 7)),
+        month_over_month: delta(window(1, 30), window(31, 30)),
+        pace: None,
+    }
+}
+
+pub fn delta(current: u32, previous: u32) -> PeriodDelta {
+    PeriodDelta {
+        current,
+        previous,
+        change_rate: (previous > 0).then(|| (current as f64 - previous as f64) / previous as f64),
+    }
+}
+
+/// Today's completed breaks up to `now` against each recent day up to the
+/// same local time, over days that had any completed break at all.
+fn pace_in<Tz: TimeZone>(conn: &Connection, now: &DateTime<Tz>) -> SqlResult<Option<Pace>> {
+    let tz = now.timezone();
+    let today = now.date_naive();
+    let now_ms = now.timestamp_millis();
+    let since_midnight = now_ms - local_midnight(&tz, today);
+    let first = today - Duration::days(PACE_WINDOW_DAYS);
+
+    let mut stmt = conn.prepare(
+        "SELECT started_at FROM break_records
+         WHERE outcome = 'completed' AND started_at >= ?1 AND started_at < ?2",
+    )?;
+    let rows = stmt.query_map(params![local_midnight(&tz, first), now_ms], |row| {
+        row.get::<_, i64>(0)
+    })?;
+
+    // Per local day: (completed by this time of day, completed all day)
+    let mut by_day: HashMap<NaiveDate, (u32, u32)> = HashMap::new();
+    for row in rows {
+        let started_at = row?;
+        let Some(local) = tz.timestamp_millis_opt(started_at).single() else {
+            continue;
+        };
+        let day = local.date_naive();
+        let entry = by_day.entry(day).or_default();
+        entry.1 += 1;
+        if started_at - local_midnight(&tz, day) < since_midnight {
+            entry.0 += 1;
+        }
+    }
+
+    let breaks_so_far = by_day.get(&today).map_or(0, |(by_now, _)| *by_now);
+    let mut usual: Vec<u32> = by_day
+        .iter()
+        .filter(|(day, (_, all_day))| **day != today && *all_day > 0)
+        .map(|(_, (by_now, _))| *by_now)
+        .collect();
+    if usual.len() < MIN_PACE_DAYS {
+        return Ok(None);
+    }
+    usual.sort_unstable();
+
+    Ok(Some(Pace {
+        as_of: now.naive_local().format("%H:%M").to_string(),
+        breaks_so_far,
+        p25: percentile(&usual, 0.25),
+        median: percentile(&usual, 0.5),
+        p75: percentile(&usual, 0.75),
+        days_compared: usual.len() as u32,
+    }))
+}
+
+/// Linearly interpolated percentile of sorted, non-empty values.
+fn percentile(sorted: &[u32], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] as f64 + (sorted[hi] as f64 - sorted[lo] as f64) * (rank - lo as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{finish_break_record, init_db_conn, insert_break_record};
+    use crate::state::BreakOutcome;
+    use chrono::FixedOffset;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db_conn(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_summarize_windows() {
+        // 61 days: 30 days of 2, 23 of 4, 7 of 6, then today with 1
+        let mut completed = vec![2; 30];
+        completed.extend(vec![4; 23]);
+        completed.extend(vec![6; 7]);
+        completed.push(1);
+
+        let trends = summarize(&completed);
+        assert_eq!(trends.avg_breaks_7_days, 6.0);
+        assert!((trends.avg_breaks_30_days - (23.0 * 4.0 + 42.0) / 30.0).abs() < 1e-9);
+        assert_eq!(trends.week_over_week.current, 42);
+        assert_eq!(trends.week_over_week.previous, 28);
+        assert_eq!(trends.week_over_week.change_rate, Some(0.5));
+        assert_eq!(trends.month_over_month.previous, 60);
+        assert_eq!(trends.rolling_7_days.len(), 30);
+        assert_eq!(trends.rolling_7_days[28], 6.0);
+        // Today's partial count pulls the last point down
+        assert!((trends.rolling_7_days[29] - 37.0 / 7.0).abs() < 1e-9);
+        assert_eq!(trends.rolling_30_days.len(), 30);
+        assert!((trends.rolling_30_days[0] - (28.0 * 2.0 + 2.0 * 4.0) / 30.0).abs() < 1e-9);
+        assert!((trends.rolling_30_days[29] - (22.0 * 4.0 + 42.0 + 1.0) / 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_no_change_rate_from_zero() {
+        let trends = summarize(&[0; 61]);
+        assert_eq!(trends.week_over_week.change_rate, None);
+        assert_eq!(trends.avg_breaks_30_days, 0.0);
+    }
+
+    #[test]
+    fn test_percentile_interpolates() {
+        assert_eq!(percentile(&[1, 2, 3, 4], 0.5), 2.5);
+        assert_eq!(percentile(&[1, 2, 3, 4], 0.25), 1.75);
+        assert_eq!(percentile(&[7], 0.75), 7.0);
+    }
+
+    #[test]
+    fn test_pace_compares_the_same_time_of_day() {
+        let conn = setup_test_db();
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let add = |day: u32, hour: u32| {
+            let at = tz
+                .with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64;
+            let id = insert_break_record(&conn, at, 1200).unwrap();
+            finish_break_record(&conn, id, 20, BreakOutcome::Completed, None, None).unwrap();
+        };
+        // Five earlier days with 1..=5 breaks before 14:00 and one after
+        for day in 1..=5 {
+            for i in 0..day {
+                add(day, 8 + i);
+            }
+            add(day, 16);
+        }
+        // Today, 14:00 local: one break so far
+        add(10, 9);
+        let now = tz.with_ymd_and_hms(2024, 3, 10, 14, 0, 0).unwrap();
+
+        let pace = pace_in(&conn, &now).unwrap().unwrap();
+        assert_eq!(pace.as_of, "14:00");
+        assert_eq!(pace.breaks_so_far, 1);
+        assert_eq!(pace.days_compared, 5);
+        assert_eq!(pace.median, 3.0);
+        assert_eq!(pace.p25, 2.0);
+        assert_eq!(pace.p75, 4.0);
+
+        // Too few days to call anything usual
+        let early = tz.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap();
+        assert_eq!(pace_in(&conn, &early).unwrap(), None);
+    }
+}
@@ -5,6 +5,7 @@ import Dashboard from "./pages/Dashboard";
 import Settings from "./pages/Settings";
 import Onboarding from "./pages/Onboarding";
 import MiniOverlay from "./components/MiniOverlay";
+import RecoveryDialog from "./components/RecoveryDialog";
 import type { UserSettings } from "./lib/types";
 import { getSettings } from "./lib/commands";
 import { useOnboarding } from "./hooks/useOnboarding";
@@ -164,7 +165,12 @@ function App() {
     return <MiniOverlay />;
   }
 
-  return <MainApp />;
+  return (
+    <>
+      <MainApp />
+      <RecoveryDialog />
+    </>
+  );
 }
 
 export default App;
@@ -0,0 +1,64 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import type { Achievement } from "../lib/types";
+import { getAchievements } from "../lib/commands";
+
+export default function Achievements() {
+  const [achievements, setAchievements] = useState<Achievement[]>([]);
+
+  useEffect(() => {
+    const load = () => {
+      getAchievements()
+        .then(setAchievements)
+        .catch((err) => console.error("Failed to load achievements:", err));
+    };
+    load();
+
+    const unlisten = listen("achievement-unlocked", load);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  if (achievements.length === 0) return null;
+  const unlocked = achievements.filter((a) => a.unlocked_at !== null).length;
+
+  return (
+    <div className="rounded-2xl bg-white dark:bg-gray-800 p-5 space-y-3">
+      <div className="flex items-baseline justify-between">
+        <h3 className="text-sm font-medium text-gray-500 dark:text-gray-400 uppercase tracking-wide">
+          Achievements
+        </h3>
+        <span className="text-xs text-gray-400 dark:text-gray-500">
+          {unlocked} of {achievements.length}
+        </span>
+      </div>
+
+      <ul className="grid grid-cols-2 gap-2">
+        {achievements.map((a) => (
+          <li
+            key={a.id}
+            className={`rounded-lg p-2 text-sm ${
+              a.unlocked_at !== null
+                ? "bg-amber-50 dark:bg-amber-900/30"
+                : "bg-gray-50 dark:bg-gray-700/40 opacity-60"
+            }`}
+            title={
+              a.unlocked_at !== null
+                ? `Unlocked ${new Date(a.unlocked_at).toLocaleDateString()}`
+                : "Locked"
+            }
+          >
+            <div className="font-medium">
+              {a.unlocked_at !== null ? "🏆 " : "🔒 "}
+              {a.title}
+            </div>
+            <div className="text-xs text-gray-500 dark:text-gray-400">
+              {a.description}
+            </div>
+          </li>
+        ))}
+      </ul>
+    </div>
+  );
+}
@@ -1,8 +1,15 @@
-import type { DailyStats } from "../lib/types";
+import type { DailyStats, DayCoverage } from "../lib/types";
 import EmptyState from "./EmptyState";
 
-export default function ComplianceRate({ today }: { today: DailyStats }) {
+export default function ComplianceRate({
+  today,
+  coverage,
+}: {
+  today: DailyStats;
+  coverage: DayCoverage;
+}) {
   const rate = Math.round(today.compliance_rate * 100);
+  const coverageRate = Math.round(coverage.coverage_rate * 100);
   const noBreaks =
     today.breaks_completed === 0 && today.breaks_skipped === 0;
 
@@ -41,6 +48,26 @@ export default function ComplianceRate({ today }: { today: DailyStats }) {
           {today.breaks_skipped} skipped
         </span>
       </div>
+
+      {coverage.expected_breaks > 0 && (
+        <div className="pt-2 border-t border-gray-100 dark:border-gray-700 space-y-1 text-sm">
+          <div className="flex justify-between">
+            <span className="text-gray-500 dark:text-gray-400">Coverage</span>
+            <span className="font-medium">
+              {coverageRate}%{" "}
+              <span className="text-gray-400 font-normal">
+                of {coverage.expected_breaks} expected
+              </span>
+            </span>
+          </div>
+          {coverage.missed_while_paused > 0 && (
+            <div className="flex justify-between text-gray-500 dark:text-gray-400">
+              <span>Missed while paused</span>
+              <span>{coverage.missed_while_paused}</span>
+            </div>
+          )}
+        </div>
+      )}
     </div>
   );
 }
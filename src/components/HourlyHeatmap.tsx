@@ -0,0 +1,77 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import type { HeatmapCell, HourlyHeatmap as HeatmapData } from "../lib/types";
+import { getHourlyHeatmap } from "../lib/commands";
+
+const WEEKDAYS = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const RANGE_DAYS = 30;
+
+/** The UTC day of `d`, matching how the backend keys daily data. */
+function utcDate(d: Date): string {
+  return d.toISOString().slice(0, 10);
+}
+
+function cellClass(cell: HeatmapCell): string {
+  const total = cell.breaks_completed + cell.breaks_skipped;
+  if (total === 0) return "bg-gray-100 dark:bg-gray-700";
+  if (cell.compliance_rate >= 0.75) return "bg-green-500 dark:bg-green-500/80";
+  if (cell.compliance_rate >= 0.5) return "bg-green-300 dark:bg-green-700/60";
+  if (cell.compliance_rate >= 0.25) return "bg-orange-300 dark:bg-orange-700/60";
+  return "bg-orange-500 dark:bg-orange-500/80";
+}
+
+export default function HourlyHeatmap() {
+  const [heatmap, setHeatmap] = useState<HeatmapData | null>(null);
+
+  useEffect(() => {
+    const load = () => {
+      const to = new Date();
+      const from = new Date();
+      from.setUTCDate(to.getUTCDate() - (RANGE_DAYS - 1));
+      getHourlyHeatmap(utcDate(from), utcDate(to))
+        .then(setHeatmap)
+        .catch((err) => console.error("Failed to load heatmap:", err));
+    };
+    load();
+
+    const unlisten = listen("break-completed", load);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  if (!heatmap) return null;
+
+  return (
+    <div className="rounded-2xl bg-white dark:bg-gray-800 p-5 space-y-3">
+      <h3 className="text-sm font-medium text-gray-500 dark:text-gray-400 uppercase tracking-wide">
+        When you take breaks
+      </h3>
+
+      <div className="space-y-0.5">
+        {WEEKDAYS.map((name, weekday) => (
+          <div key={name} className="flex items-center gap-1">
+            <span className="w-8 text-[10px] text-gray-400 dark:text-gray-500">
+              {name}
+            </span>
+            <div className="grid grid-cols-24 gap-0.5 flex-1">
+              {heatmap.cells
+                .filter((c) => c.weekday === weekday)
+                .map((cell) => (
+                  <div
+                    key={cell.hour}
+                    className={`aspect-square rounded-sm ${cellClass(cell)}`}
+                    title={`${name} ${cell.hour}:00 — ${cell.breaks_completed} completed, ${cell.breaks_skipped} skipped`}
+                  />
+                ))}
+            </div>
+          </div>
+        ))}
+      </div>
+
+      <p className="text-[10px] text-gray-400 dark:text-gray-500">
+        Last {RANGE_DAYS} days by hour. Orange hours are when breaks get skipped.
+      </p>
+    </div>
+  );
+}
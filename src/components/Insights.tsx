@@ -0,0 +1,62 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import type { Insight } from "../lib/types";
+import { getInsights } from "../lib/commands";
+
+export default function Insights() {
+  const [insights, setInsights] = useState<Insight[]>([]);
+  const [expanded, setExpanded] = useState<string | null>(null);
+
+  useEffect(() => {
+    const load = () => {
+      getInsights()
+        .then(setInsights)
+        .catch((err) => console.error("Failed to load insights:", err));
+    };
+    load();
+
+    const unlistenCompleted = listen("break-completed", load);
+    const unlistenSkipped = listen("break-skipped", load);
+    return () => {
+      unlistenCompleted.then((fn) => fn());
+      unlistenSkipped.then((fn) => fn());
+    };
+  }, []);
+
+  if (insights.length === 0) return null;
+
+  return (
+    <div className="rounded-2xl bg-white dark:bg-gray-800 p-5 space-y-3">
+      <h3 className="text-sm font-medium text-gray-500 dark:text-gray-400 uppercase tracking-wide">
+        Insights
+      </h3>
+
+      <ul className="space-y-2">
+        {insights.map((insight) => (
+          <li key={insight.rule} className="text-sm">
+            <button
+              onClick={() =>
+                setExpanded((open) =>
+                  open === insight.rule ? null : insight.rule
+                )
+              }
+              className="w-full text-left hover:text-blue-600 dark:hover:text-blue-400"
+            >
+              💡 {insight.message}
+            </button>
+            {expanded === insight.rule && (
+              <dl className="mt-1 ml-6 grid grid-cols-2 gap-x-3 text-xs text-gray-500 dark:text-gray-400">
+                {insight.evidence.map((e) => (
+                  <div key={e.label} className="contents">
+                    <dt>{e.label}</dt>
+                    <dd className="tabular-nums text-right">{e.value}</dd>
+                  </div>
+                ))}
+              </dl>
+            )}
+          </li>
+        ))}
+      </ul>
+    </div>
+  );
+}
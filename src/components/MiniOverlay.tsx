@@ -1,18 +1,21 @@
 import { useEffect, useState } from "react";
 import { listen } from "@tauri-apps/api/event";
-import { skipBreak, getSettings } from "../lib/commands";
+import { skipBreak, snoozeBreak, getSettings } from "../lib/commands";
 import type { TimerState } from "../lib/types";
 
 function MiniOverlay() {
   const [timer, setTimer] = useState<TimerState | null>(null);
   const [visible, setVisible] = useState(false);
   const [isFirstBreak, setIsFirstBreak] = useState(false);
+  // The onboarding demo break can't be snoozed
+  const [canSnooze, setCanSnooze] = useState(false);
 
   useEffect(() => {
     // Check if this is the user's first-ever break
     getSettings()
       .then((s) => {
         setIsFirstBreak(s.onboarding_completed && !s.first_break_completed);
+        setCanSnooze(s.onboarding_completed);
       })
       .catch(() => {});
 
@@ -36,6 +39,7 @@ function MiniOverlay() {
       getSettings()
         .then((s) => {
           setIsFirstBreak(s.onboarding_completed && !s.first_break_completed);
+          setCanSnooze(s.onboarding_completed);
         })
         .catch(() => {});
     });
@@ -127,7 +131,15 @@ function MiniOverlay() {
             : "Look away — rest your eyes"}
         </span>
 
-        {/* Skip button — de-emphasized */}
+        {/* Snooze and skip buttons — de-emphasized */}
+        {canSnooze && (
+          <button
+            onClick={() => snoozeBreak()}
+            className="text-white/30 hover:text-white/60 text-xs ml-1 transition-colors cursor-pointer select-none"
+          >
+            snooze
+          </button>
+        )}
         <button
           onClick={handleSkip}
           className="text-white/30 hover:text-white/60 text-xs ml-1 transition-colors cursor-pointer select-none"
@@ -0,0 +1,56 @@
+import { useEffect, useState } from "react";
+import { dismissRecoveryReport, getRecoveryReport } from "../lib/commands";
+import type { RecoveryReport } from "../lib/types";
+
+function describe(report: RecoveryReport): string {
+  switch (report.outcome) {
+    case "salvaged":
+      return `Blinky's database was damaged. ${report.rows_salvaged} records were recovered into a new database.`;
+    case "restored_backup":
+      return `Blinky's database was damaged and nothing could be recovered from it, so the backup ${report.backup_used} was restored.`;
+    case "fresh":
+      return "Blinky's database was damaged and no backup was available, so a new, empty database was created.";
+    case "temporary":
+      return "Blinky couldn't open its database, so it is running without saving anything this session. Your data has not been touched.";
+  }
+}
+
+export default function RecoveryDialog() {
+  const [report, setReport] = useState<RecoveryReport | null>(null);
+
+  useEffect(() => {
+    getRecoveryReport().then(setReport).catch(console.error);
+  }, []);
+
+  if (!report) return null;
+
+  return (
+    <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/40 p-5">
+      <div className="max-w-md rounded-2xl bg-white dark:bg-gray-800 p-5 space-y-3 shadow-xl">
+        <h2 className="text-lg font-semibold">Database recovered</h2>
+        <p className="text-sm">{describe(report)}</p>
+        {report.quarantined_path && (
+          <p className="text-xs text-gray-500 dark:text-gray-400 break-all">
+            The damaged file was kept at {report.quarantined_path}
+          </p>
+        )}
+        <details className="text-xs text-gray-500 dark:text-gray-400">
+          <summary className="cursor-pointer">Details</summary>
+          <p className="mt-1 break-all">{report.problem}</p>
+        </details>
+        <div className="flex justify-end">
+          <button
+            onClick={() => {
+              dismissRecoveryReport()
+                .then(() => setReport(null))
+                .catch(console.error);
+            }}
+            className="px-4 py-2 rounded-lg bg-blue-600 text-white text-sm font-medium hover:bg-blue-700 transition-colors"
+          >
+            OK
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
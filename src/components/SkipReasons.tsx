@@ -0,0 +1,93 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import type { SkipReasonBreakdown } from "../lib/types";
+import { getSkipReasons } from "../lib/commands";
+
+const RANGE_DAYS = 30;
+
+/** The UTC day of `d`, matching how the backend keys daily data. */
+function utcDate(d: Date): string {
+  return d.toISOString().slice(0, 10);
+}
+
+/** The hour with the most skips, or null if there were none. */
+function peakHour(byHour: number[]): number | null {
+  let peak: number | null = null;
+  byHour.forEach((count, hour) => {
+    if (count > 0 && (peak === null || count > byHour[peak])) peak = hour;
+  });
+  return peak;
+}
+
+export default function SkipReasons() {
+  const [breakdown, setBreakdown] = useState<SkipReasonBreakdown | null>(null);
+
+  useEffect(() => {
+    const load = () => {
+      const to = new Date();
+      const from = new Date();
+      from.setUTCDate(to.getUTCDate() - (RANGE_DAYS - 1));
+      getSkipReasons(utcDate(from), utcDate(to))
+        .then(setBreakdown)
+        .catch((err) => console.error("Failed to load skip reasons:", err));
+    };
+    load();
+
+    const unlisten = listen("break-skipped", load);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  if (!breakdown || breakdown.total_skipped === 0) return null;
+
+  return (
+    <div className="rounded-2xl bg-white dark:bg-gray-800 p-5 space-y-3">
+      <h3 className="text-sm font-medium text-gray-500 dark:text-gray-400 uppercase tracking-wide">
+        Why breaks get skipped
+      </h3>
+
+      <ul className="space-y-2">
+        {breakdown.reasons.map((r) => {
+          const share = r.count / breakdown.total_skipped;
+          const peak = peakHour(r.by_hour);
+          const busiest = Math.max(...r.by_hour);
+          return (
+            <li key={r.reason ?? ""} className="space-y-1">
+              <div className="flex items-center justify-between text-sm">
+                <span className={r.reason ? "" : "text-gray-400"}>
+                  {r.reason ?? "No reason given"}
+                </span>
+                <span className="tabular-nums text-gray-500 dark:text-gray-400">
+                  {r.count} · {Math.round(share * 100)}%
+                </span>
+              </div>
+              <div className="grid grid-cols-24 gap-0.5 h-4 items-end">
+                {r.by_hour.map((count, hour) => (
+                  <div
+                    key={hour}
+                    className="rounded-sm bg-orange-400 dark:bg-orange-500/80"
+                    style={{
+                      height: count > 0 ? `${(count / busiest) * 100}%` : 0,
+                    }}
+                    title={`${hour}:00 — ${count} skipped`}
+                  />
+                ))}
+              </div>
+              {peak !== null && (
+                <p className="text-[10px] text-gray-400 dark:text-gray-500">
+                  Mostly around {peak}:00
+                </p>
+              )}
+            </li>
+          );
+        })}
+      </ul>
+
+      <p className="text-[10px] text-gray-400 dark:text-gray-500">
+        {breakdown.total_skipped} skipped in the last {RANGE_DAYS} days, by
+        hour of day.
+      </p>
+    </div>
+  );
+}
@@ -48,6 +48,13 @@ export default function StreakCard({
       <p className="text-xs text-gray-400 dark:text-gray-500">
         Best: {analytics.best_day_streak} day
         {analytics.best_day_streak !== 1 ? "s" : ""}
+        {analytics.streak_freezes_available > 0 && (
+          <>
+            {" "}
+            &middot; {analytics.streak_freezes_available} streak freeze
+            {analytics.streak_freezes_available !== 1 ? "s" : ""}
+          </>
+        )}
       </p>
 
       <div className="pt-2 border-t border-gray-100 dark:border-gray-700">
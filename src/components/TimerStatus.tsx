@@ -1,11 +1,23 @@
+import { useEffect, useState } from "react";
 import type { TimerState } from "../lib/types";
 import {
+  getSettings,
   pauseTimer,
   resumeTimer,
   skipBreak,
+  snoozeBreak,
   resetTimer,
 } from "../lib/commands";
 
+function parseReasons(json: string): string[] {
+  try {
+    const parsed = JSON.parse(json);
+    return Array.isArray(parsed) ? parsed.filter((r) => typeof r === "string") : [];
+  } catch {
+    return [];
+  }
+}
+
 function formatTime(seconds: number): string {
   const m = Math.floor(seconds / 60);
   const s = seconds % 60;
@@ -34,6 +46,26 @@ export default function TimerStatus({
   timer: TimerState | null;
   isFirstDay?: boolean;
 }) {
+  const [skipReasons, setSkipReasons] = useState<string[]>([]);
+  const [choosingReason, setChoosingReason] = useState(false);
+
+  useEffect(() => {
+    if (!choosingReason) return;
+    getSettings()
+      .then((s) => setSkipReasons(parseReasons(s.skip_reasons)))
+      .catch(console.error);
+  }, [choosingReason]);
+
+  // The picker only makes sense while the break it would skip is running
+  useEffect(() => {
+    if (timer?.phase !== "Breaking") setChoosingReason(false);
+  }, [timer?.phase]);
+
+  const skip = (reason?: string) => {
+    setChoosingReason(false);
+    skipBreak(reason).catch(console.error);
+  };
+
   if (!timer) {
     return (
       <div className="rounded-2xl bg-white dark:bg-gray-800 p-6 text-center">
@@ -89,13 +121,22 @@ export default function TimerStatus({
 
         {isBreaking && (
           <button
-            onClick={() => skipBreak().catch(console.error)}
+            onClick={() => setChoosingReason((open) => !open)}
             className="px-4 py-2 rounded-lg bg-orange-100 text-orange-700 dark:bg-orange-900/30 dark:text-orange-300 text-sm font-medium hover:bg-orange-200 dark:hover:bg-orange-900/50 transition-colors"
           >
             Skip
           </button>
         )}
 
+        {isBreaking && (
+          <button
+            onClick={() => snoozeBreak().catch(console.error)}
+            className="px-4 py-2 rounded-lg bg-gray-200 dark:bg-gray-700 text-sm font-medium hover:bg-gray-300 dark:hover:bg-gray-600 transition-colors"
+          >
+            Snooze
+          </button>
+        )}
+
         <button
           onClick={() => resetTimer().catch(console.error)}
           className="px-4 py-2 rounded-lg bg-gray-200 dark:bg-gray-700 text-sm font-medium hover:bg-gray-300 dark:hover:bg-gray-600 transition-colors"
@@ -104,6 +145,31 @@ export default function TimerStatus({
         </button>
       </div>
 
+      {isBreaking && choosingReason && (
+        <div className="space-y-2">
+          <p className="text-xs text-gray-500 dark:text-gray-400">
+            Why are you skipping?
+          </p>
+          <div className="flex flex-wrap items-center justify-center gap-2">
+            {skipReasons.map((reason) => (
+              <button
+                key={reason}
+                onClick={() => skip(reason)}
+                className="px-3 py-1.5 rounded-full bg-orange-50 text-orange-700 dark:bg-orange-900/20 dark:text-orange-300 text-xs font-medium hover:bg-orange-100 dark:hover:bg-orange-900/40 transition-colors"
+              >
+                {reason}
+              </button>
+            ))}
+            <button
+              onClick={() => skip()}
+              className="px-3 py-1.5 rounded-full bg-gray-100 dark:bg-gray-700 text-xs font-medium text-gray-500 dark:text-gray-400 hover:bg-gray-200 dark:hover:bg-gray-600 transition-colors"
+            >
+              Just skip
+            </button>
+          </div>
+        </div>
+      )}
+
       <p className="text-sm text-gray-400 dark:text-gray-500">
         {timer.breaks_completed_today} break
         {timer.breaks_completed_today !== 1 ? "s" : ""} completed today
@@ -0,0 +1,69 @@
+import type { Pace, PeriodDelta, Trends } from "../lib/types";
+
+function DeltaRow({ label, delta }: { label: string; delta: PeriodDelta }) {
+  const rate = delta.change_rate;
+  const color =
+    rate === null || rate === 0
+      ? "text-gray-400"
+      : rate > 0
+        ? "text-green-600 dark:text-green-400"
+        : "text-orange-500 dark:text-orange-400";
+
+  return (
+    <div className="flex justify-between text-sm">
+      <span className="text-gray-500 dark:text-gray-400">{label}</span>
+      <span>
+        {delta.current}{" "}
+        <span className={color}>
+          {rate === null
+            ? ""
+            : `${rate >= 0 ? "▲" : "▼"} ${Math.abs(Math.round(rate * 100))}%`}
+        </span>
+      </span>
+    </div>
+  );
+}
+
+function paceMessage(pace: Pace): string {
+  const behind = Math.round(pace.median - pace.breaks_so_far);
+  const plural = (n: number) => `${n} break${n !== 1 ? "s" : ""}`;
+  if (pace.breaks_so_far < pace.p25 && behind > 0) {
+    return `You're ${plural(behind)} behind your usual pace by ${pace.as_of}.`;
+  }
+  if (pace.breaks_so_far > pace.p75 && behind < 0) {
+    return `You're ${plural(-behind)} ahead of your usual pace by ${pace.as_of}.`;
+  }
+  return `Right on your usual pace for ${pace.as_of}.`;
+}
+
+export default function TrendsCard({ trends }: { trends: Trends }) {
+  return (
+    <div className="rounded-2xl bg-white dark:bg-gray-800 p-5 space-y-3">
+      <h3 className="text-sm font-medium text-gray-500 dark:text-gray-400 uppercase tracking-wide">
+        Trends
+      </h3>
+
+      {trends.pace && <p className="text-sm">{paceMessage(trends.pace)}</p>}
+
+      <div className="flex gap-6 text-sm">
+        <span>
+          <span className="font-medium">
+            {trends.avg_breaks_7_days.toFixed(1)}
+          </span>{" "}
+          <span className="text-gray-400">a day, last 7 days</span>
+        </span>
+        <span>
+          <span className="font-medium">
+            {trends.avg_breaks_30_days.toFixed(1)}
+          </span>{" "}
+          <span className="text-gray-400">last 30</span>
+        </span>
+      </div>
+
+      <div className="space-y-1">
+        <DeltaRow label="Week over week" delta={trends.week_over_week} />
+        <DeltaRow label="Month over month" delta={trends.month_over_month} />
+      </div>
+    </div>
+  );
+}
@@ -1,11 +1,33 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
+  Achievement,
   TimerState,
   UserSettings,
   BreakRecord,
   DailyStats,
+  DayCoverage,
   AnalyticsSummary,
+  BackupInfo,
+  CsvExportOptions,
+  DeferralSummary,
+  ImportMode,
+  ImportResult,
   OnboardingState,
+  PeriodStats,
+  DayTimeline,
+  BreakEdit,
+  BreakRecordEdit,
+  HistoryPage,
+  HourlyHeatmap,
+  Insight,
+  SkipReasonBreakdown,
+  ReportFormat,
+  ReportPeriod,
+  HistoryQuery,
+  DataLocation,
+  RecoveryReport,
+  RetentionResult,
+  SettingsSnapshot,
 } from "./types";
 
 export async function getTimerState(): Promise<TimerState> {
@@ -20,8 +42,12 @@ export async function resumeTimer(): Promise<TimerState> {
   return invoke("resume_timer");
 }
 
-export async function skipBreak(): Promise<TimerState> {
-  return invoke("skip_break");
+export async function skipBreak(reason?: string): Promise<TimerState> {
+  return invoke("skip_break", { reason: reason ?? null });
+}
+
+export async function snoozeBreak(): Promise<TimerState> {
+  return invoke("snooze_break");
 }
 
 export async function resetTimer(): Promise<TimerState> {
@@ -32,6 +58,10 @@ export async function getSettings(): Promise<UserSettings> {
   return invoke("get_settings");
 }
 
+export async function getSettingsHistory(): Promise<SettingsSnapshot[]> {
+  return invoke("get_settings_history");
+}
+
 export async function updateSettings(
   settings: UserSettings
 ): Promise<UserSettings> {
@@ -42,6 +72,44 @@ export async function getAnalyticsSummary(): Promise<AnalyticsSummary> {
   return invoke("get_analytics_summary");
 }
 
+export async function getAchievements(): Promise<Achievement[]> {
+  return invoke("get_achievements");
+}
+
+export async function getCoverage(
+  from: string,
+  to: string
+): Promise<DayCoverage[]> {
+  return invoke("get_coverage", { from, to });
+}
+
+export async function getHourlyHeatmap(
+  from: string,
+  to: string
+): Promise<HourlyHeatmap> {
+  return invoke("get_hourly_heatmap", { from, to });
+}
+
+export async function getInsights(): Promise<Insight[]> {
+  return invoke("get_insights");
+}
+
+export async function getSkipReasons(
+  from: string,
+  to: string
+): Promise<SkipReasonBreakdown> {
+  return invoke("get_skip_reasons", { from, to });
+}
+
+/** Writes the report for the last complete week or month; returns its path. */
+export async function generateReport(
+  period: ReportPeriod,
+  format: ReportFormat,
+  destination?: string
+): Promise<string> {
+  return invoke("generate_report", { period, format, destination });
+}
+
 export async function getBreakHistory(
   limit: number,
   offset: number
@@ -49,6 +117,29 @@ export async function getBreakHistory(
   return invoke("get_break_history", { limit, offset });
 }
 
+export async function queryBreakHistory(
+  query?: HistoryQuery
+): Promise<HistoryPage> {
+  return invoke("query_break_history", { query });
+}
+
+export async function editBreakRecord(
+  id: number,
+  edit: BreakEdit
+): Promise<BreakRecord> {
+  return invoke("edit_break_record", { id, edit });
+}
+
+export async function deleteBreakRecord(id: number): Promise<void> {
+  return invoke("delete_break_record", { id });
+}
+
+export async function getBreakRecordEdits(
+  breakId?: number
+): Promise<BreakRecordEdit[]> {
+  return invoke("get_break_record_edits", { breakId });
+}
+
 export async function getDailyStatsRange(
   from: string,
   to: string
@@ -56,14 +147,80 @@ export async function getDailyStatsRange(
   return invoke("get_daily_stats_range", { from, to });
 }
 
-export async function exportDataCsv(): Promise<string> {
-  return invoke("export_data_csv");
+export async function getDeferralSummary(
+  from: string,
+  to: string
+): Promise<DeferralSummary[]> {
+  return invoke("get_deferral_summary", { from, to });
+}
+
+export async function exportDataCsv(
+  options?: CsvExportOptions
+): Promise<string> {
+  return invoke("export_data_csv", { options: options ?? null });
+}
+
+export async function exportDailyStatsCsv(
+  options?: CsvExportOptions
+): Promise<string> {
+  return invoke("export_daily_stats_csv", { options: options ?? null });
+}
+
+export async function exportArchive(destination?: string): Promise<string> {
+  return invoke("export_archive", { destination: destination ?? null });
+}
+
+export async function importArchive(
+  contents: string,
+  mode: ImportMode
+): Promise<ImportResult> {
+  return invoke("import_archive", { contents, mode });
+}
+
+export async function compactNow(): Promise<RetentionResult> {
+  return invoke("compact_now");
+}
+
+export async function rebuildAggregates(): Promise<void> {
+  return invoke("rebuild_aggregates");
+}
+
+export async function getPeriodStats(
+  kind: "week" | "month"
+): Promise<PeriodStats[]> {
+  return invoke("get_period_stats", { kind });
+}
+
+export async function getTimeline(date: string): Promise<DayTimeline> {
+  return invoke("get_timeline", { date });
 }
 
 export async function clearAllData(): Promise<boolean> {
   return invoke("clear_all_data");
 }
 
+// --- Backup commands ---
+
+export async function getRecoveryReport(): Promise<RecoveryReport | null> {
+  return invoke("get_recovery_report");
+}
+
+export async function dismissRecoveryReport(): Promise<boolean> {
+  return invoke("dismiss_recovery_report");
+}
+
+export async function getDataLocation(): Promise<DataLocation> {
+  return invoke("get_data_location");
+}
+
+export async function listBackups(): Promise<BackupInfo[]> {
+  return invoke("list_backups");
+}
+
+export async function restoreBackup(fileName: string): Promise<UserSettings> {
+  return invoke("restore_backup", { fileName });
+}
+
 // --- Onboarding commands ---
 
 export async function getOnboardingState(): Promise<OnboardingState> {
@@ -8,6 +8,8 @@ export interface TimerState {
   breaks_completed_today: number;
 }
 
+export type IdleResumePolicy = "resume" | "restart_if_long" | "restart";
+
 export interface UserSettings {
   work_interval_minutes: number;
   break_duration_seconds: number;
@@ -18,6 +20,27 @@ export interface UserSettings {
   launch_at_login: boolean;
   daily_goal: number;
   idle_pause_minutes: number;
+  idle_resume_policy: IdleResumePolicy;
+  idle_long_away_minutes: number;
+  media_activity_enabled: boolean;
+  media_player_allowlist: string;
+  meeting_detection_enabled: boolean;
+  meeting_max_defer_minutes: number;
+  backup_daily_keep: number;
+  backup_weekly_keep: number;
+  /** Keep raw break records this long; 0 keeps them forever. */
+  retention_amount: number;
+  retention_unit: RetentionUnit;
+  report_auto_enabled: boolean;
+  report_format: ReportFormat;
+  /** Empty means Downloads. */
+  report_dir: string;
+  /** JSON array of weekdays that count toward streaks, e.g. '["mon","tue"]'. */
+  workdays: string;
+  /** JSON array of the reasons offered when skipping a break. */
+  skip_reasons: string;
+  /** Notify once a day with yesterday's totals and insights. */
+  daily_summary_enabled: boolean;
   theme: string;
   onboarding_completed: boolean;
   onboarding_completed_at: number | null;
@@ -32,6 +55,69 @@ export interface BreakRecord {
   completed: boolean;
   skipped: boolean;
   preceding_work_seconds: number;
+  ended_at: number | null;
+  outcome: BreakOutcome | null;
+  source: BreakSource | null;
+  note: string | null;
+  skip_reason: string | null;
+}
+
+export type BreakOutcome =
+  | "completed"
+  | "skipped"
+  | "reset"
+  | "snoozed"
+  | "interrupted"
+  | "demo";
+
+export type BreakSource = "tray" | "overlay" | "main_window" | "cli" | "idle";
+
+export interface HistoryCursor {
+  started_at: number;
+  id: number;
+}
+
+export interface HistoryQuery {
+  from?: string;
+  to?: string;
+  outcomes?: BreakOutcome[];
+  min_duration_seconds?: number;
+  cursor?: HistoryCursor;
+  limit?: number;
+}
+
+export interface HistoryPage {
+  records: BreakRecord[];
+  next_cursor: HistoryCursor | null;
+  total_count: number;
+  page: {
+    completed: number;
+    skipped: number;
+    other: number;
+    total_duration_seconds: number;
+    rest_seconds: number;
+  };
+}
+
+export interface BreakEdit {
+  outcome?: BreakOutcome;
+  duration_seconds?: number;
+  /** An empty string removes the note. */
+  note?: string;
+}
+
+export interface BreakRecordEdit {
+  id: number;
+  break_id: number;
+  break_started_at: number;
+  action: "edit" | "delete";
+  edited_at: number;
+  old_outcome: BreakOutcome | null;
+  new_outcome: BreakOutcome | null;
+  old_duration_seconds: number | null;
+  new_duration_seconds: number | null;
+  old_note: string | null;
+  new_note: string | null;
 }
 
 export interface DailyStats {
@@ -43,6 +129,56 @@ export interface DailyStats {
   compliance_rate: number;
 }
 
+export interface HeatmapCell {
+  /** 0 = Monday .. 6 = Sunday */
+  weekday: number;
+  hour: number;
+  breaks_completed: number;
+  breaks_skipped: number;
+  compliance_rate: number;
+}
+
+export interface HourlyHeatmap {
+  from: string;
+  to: string;
+  cells: HeatmapCell[];
+}
+
+export interface SkipReasonStats {
+  /** null for skips without a reason. */
+  reason: string | null;
+  count: number;
+  /** 24 entries, local 00:00 first. */
+  by_hour: number[];
+}
+
+export interface InsightEvidence {
+  label: string;
+  value: string;
+}
+
+export interface Insight {
+  rule: "skip_window" | "weekday_dip" | "weekly_change" | "streak_break";
+  message: string;
+  evidence: InsightEvidence[];
+  /** 0..1; insights come strongest first. */
+  score: number;
+}
+
+export interface SkipReasonBreakdown {
+  from: string;
+  to: string;
+  total_skipped: number;
+  reasons: SkipReasonStats[];
+}
+
+export interface SettingsSnapshot {
+  effective_date: string;
+  daily_goal: number;
+  work_interval_minutes: number;
+  break_duration_seconds: number;
+}
+
 export interface OnboardingState {
   onboarding_completed: boolean;
   onboarding_completed_at: number | null;
@@ -51,12 +187,144 @@ export interface OnboardingState {
   is_first_day: boolean;
 }
 
+export interface DeferralSummary {
+  reason: string;
+  deferrals: number;
+  deferred_seconds: number;
+}
+
+export interface BackupInfo {
+  file_name: string;
+  kind: "daily" | "weekly" | "pre-restore";
+  created_at: number;
+  size_bytes: number;
+}
+
+export type ExportStatusFilter = "all" | "completed" | "skipped";
+
+export type TimestampFormat = "epoch_ms" | "iso_utc" | "iso_local";
+
+export interface CsvExportOptions {
+  from?: string;
+  to?: string;
+  status?: ExportStatusFilter;
+  destination?: string;
+  timestamp_format?: TimestampFormat;
+  /** Empty or omitted means all columns. */
+  columns?: string[];
+}
+
+export interface RecoveryReport {
+  outcome: "salvaged" | "restored_backup" | "fresh" | "temporary";
+  problem: string;
+  quarantined_path: string | null;
+  backup_used: string | null;
+  rows_salvaged: number;
+  occurred_at: number;
+}
+
+export type RetentionUnit = "days" | "months";
+
+export type ReportFormat = "html" | "markdown";
+
+export type ReportPeriod = "week" | "month";
+
+export interface PhaseInterval {
+  phase: TimerPhase;
+  started_at: number;
+  ended_at: number | null;
+}
+
+export interface DayTimeline {
+  date: string;
+  intervals: PhaseInterval[];
+  working_seconds: number;
+  paused_seconds: number;
+  suspended_seconds: number;
+  breaking_seconds: number;
+}
+
+export interface PeriodStats {
+  period: string;
+  kind: "week" | "month";
+  breaks_completed: number;
+  breaks_skipped: number;
+  total_rest_seconds: number;
+  days_tracked: number;
+}
+
+export interface RetentionResult {
+  records_pruned: number;
+  days_rolled_up: number;
+  cutoff: string | null;
+  vacuumed: boolean;
+}
+
+export type ImportMode = "merge" | "replace";
+
+export interface ImportResult {
+  records_imported: number;
+  records_skipped: number;
+  days_rebuilt: number;
+  settings_applied: boolean;
+}
+
+export interface Achievement {
+  id: string;
+  title: string;
+  description: string;
+  unlocked_at: number | null;
+}
+
+export interface DayCoverage {
+  date: string;
+  active_seconds: number;
+  expected_breaks: number;
+  breaks_completed: number;
+  breaks_skipped: number;
+  missed_while_paused: number;
+  coverage_rate: number;
+}
+
+export interface PeriodDelta {
+  current: number;
+  previous: number;
+  change_rate: number | null;
+}
+
+export interface Pace {
+  as_of: string;
+  breaks_so_far: number;
+  p25: number;
+  median: number;
+  p75: number;
+  days_compared: number;
+}
+
+export interface Trends {
+  avg_breaks_7_days: number;
+  avg_breaks_30_days: number;
+  rolling_7_days: number[];
+  rolling_30_days: number[];
+  week_over_week: PeriodDelta;
+  month_over_month: PeriodDelta;
+  pace: Pace | null;
+}
+
 export interface AnalyticsSummary {
   today: DailyStats;
   last_7_days: DailyStats[];
   last_30_days: DailyStats[];
   current_day_streak: number;
   best_day_streak: number;
+  streak_freezes_available: number;
+  today_coverage: DayCoverage;
+  trends: Trends;
   lifetime_breaks: number;
   lifetime_rest_seconds: number;
 }
+
+export interface DataLocation {
+  dir: string;
+  source: "cli" | "env" | "portable" | "remembered" | "default";
+}
@@ -8,6 +8,11 @@ import TimerStatus from "../components/TimerStatus";
 import StreakCard from "../components/StreakCard";
 import DailyChart from "../components/DailyChart";
 import WeeklyHeatmap from "../components/WeeklyHeatmap";
+import HourlyHeatmap from "../components/HourlyHeatmap";
+import SkipReasons from "../components/SkipReasons";
+import TrendsCard from "../components/TrendsCard";
+import Insights from "../components/Insights";
+import Achievements from "../components/Achievements";
 import ComplianceRate from "../components/ComplianceRate";
 import Tooltip, { PulsingDot } from "../components/Tooltip";
 
@@ -239,7 +244,10 @@ export default function Dashboard() {
                   activeTooltip !== "compliance"
                 }
               />
-              <ComplianceRate today={analytics.today} />
+              <ComplianceRate
+                today={analytics.today}
+                coverage={analytics.today_coverage}
+              />
             </div>
           </div>
 
@@ -252,8 +260,18 @@ export default function Dashboard() {
             <DailyChart days={analytics.last_7_days} />
           </div>
 
+          <TrendsCard trends={analytics.trends} />
+
+          <Insights />
+
           <WeeklyHeatmap days={analytics.last_7_days} />
 
+          <HourlyHeatmap />
+
+          <SkipReasons />
+
+          <Achievements />
+
           {analytics.lifetime_breaks === 0 &&
           analytics.lifetime_rest_seconds === 0 ? (
             <p className="text-center text-sm text-gray-400 dark:text-gray-500 pt-2">
@@ -1,7 +1,31 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { useSettings } from "../hooks/useSettings";
-import { exportDataCsv, clearAllData } from "../lib/commands";
-import type { UserSettings } from "../lib/types";
+import {
+  exportDataCsv,
+  exportDailyStatsCsv,
+  exportArchive,
+  importArchive,
+  compactNow,
+  rebuildAggregates,
+  clearAllData,
+  listBackups,
+  restoreBackup,
+  getDataLocation,
+  generateReport,
+} from "../lib/commands";
+import type {
+  BackupInfo,
+  CsvExportOptions,
+  DataLocation,
+  ExportStatusFilter,
+  IdleResumePolicy,
+  ImportMode,
+  ReportFormat,
+  ReportPeriod,
+  RetentionUnit,
+  TimestampFormat,
+  UserSettings,
+} from "../lib/types";
 
 function Toggle({
   label,
@@ -91,10 +115,500 @@ function Section({
   );
 }
 
+const LOCATION_SOURCES: Record<DataLocation["source"], string> = {
+  cli: "set with --data-dir",
+  env: "set with BLINKY_DATA_DIR",
+  portable: "portable mode",
+  remembered: "chosen earlier",
+  default: "default",
+};
+
+function DataLocationInfo() {
+  const [location, setLocation] = useState<DataLocation | null>(null);
+
+  useEffect(() => {
+    getDataLocation().then(setLocation).catch(console.error);
+  }, []);
+
+  if (!location) return null;
+
+  return (
+    <div className="py-1">
+      <p className="text-sm">Data folder</p>
+      <p className="text-xs text-gray-400 break-all">
+        {location.dir} ({LOCATION_SOURCES[location.source]})
+      </p>
+    </div>
+  );
+}
+
+const WEEKDAYS = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+function WorkdayPicker({
+  value,
+  onChange,
+}: {
+  value: string;
+  onChange: (workdays: string) => void;
+}) {
+  let selected: string[];
+  try {
+    selected = JSON.parse(value);
+  } catch {
+    selected = WEEKDAYS;
+  }
+
+  const toggle = (day: string) => {
+    const next = selected.includes(day)
+      ? selected.filter((d) => d !== day)
+      : [...selected, day];
+    // At least one day has to count
+    if (next.length === 0) return;
+    onChange(JSON.stringify(WEEKDAYS.filter((d) => next.includes(d))));
+  };
+
+  return (
+    <div className="py-2 space-y-1.5">
+      <span className="text-sm">Streak days</span>
+      <div className="flex gap-1">
+        {WEEKDAYS.map((day) => (
+          <button
+            key={day}
+            onClick={() => toggle(day)}
+            className={`flex-1 text-xs rounded-lg py-1 capitalize ${
+              selected.includes(day)
+                ? "bg-blue-500 text-white"
+                : "bg-gray-100 dark:bg-gray-700 text-gray-500 dark:text-gray-400"
+            }`}
+          >
+            {day}
+          </button>
+        ))}
+      </div>
+      <p className="text-xs text-gray-400">
+        Other days don&apos;t count toward or break your streak.
+      </p>
+    </div>
+  );
+}
+
+const MAX_SKIP_REASONS = 10;
+const MAX_SKIP_REASON_LENGTH = 40;
+
+function SkipReasonEditor({
+  value,
+  onChange,
+}: {
+  value: string;
+  onChange: (skipReasons: string) => void;
+}) {
+  const [draft, setDraft] = useState("");
+  let reasons: string[];
+  try {
+    reasons = JSON.parse(value);
+  } catch {
+    reasons = [];
+  }
+
+  const trimmed = draft.trim();
+  const canAdd =
+    trimmed.length > 0 &&
+    !reasons.includes(trimmed) &&
+    reasons.length < MAX_SKIP_REASONS;
+
+  const add = () => {
+    if (!canAdd) return;
+    onChange(JSON.stringify([...reasons, trimmed]));
+    setDraft("");
+  };
+
+  const remove = (reason: string) => {
+    // At least one reason has to be offered
+    if (reasons.length <= 1) return;
+    onChange(JSON.stringify(reasons.filter((r) => r !== reason)));
+  };
+
+  return (
+    <div className="py-2 space-y-1.5">
+      <span className="text-sm">Skip reasons</span>
+      <div className="flex flex-wrap gap-1">
+        {reasons.map((reason) => (
+          <span
+            key={reason}
+            className="inline-flex items-center gap-1 text-xs rounded-full bg-gray-100 dark:bg-gray-700 px-2 py-1"
+          >
+            {reason}
+            {reasons.length > 1 && (
+              <button
+                onClick={() => remove(reason)}
+                aria-label={`Remove ${reason}`}
+                className="text-gray-400 hover:text-red-500"
+              >
+                &times;
+              </button>
+            )}
+          </span>
+        ))}
+      </div>
+      <div className="flex gap-2">
+        <input
+          type="text"
+          value={draft}
+          maxLength={MAX_SKIP_REASON_LENGTH}
+          placeholder="Add a reason"
+          onChange={(e) => setDraft(e.target.value)}
+          onKeyDown={(e) => e.key === "Enter" && add()}
+          className="flex-1 text-sm bg-gray-100 dark:bg-gray-700 rounded-lg px-2 py-1 border-0 outline-none focus:ring-2 focus:ring-blue-500"
+        />
+        <button
+          onClick={add}
+          disabled={!canAdd}
+          className="text-xs px-3 py-1 rounded-lg bg-blue-500 text-white disabled:opacity-40"
+        >
+          Add
+        </button>
+      </div>
+      <p className="text-xs text-gray-400">
+        Offered when you skip a break, so you can see why breaks get skipped.
+      </p>
+    </div>
+  );
+}
+
+function BackupList() {
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [confirming, setConfirming] = useState<string | null>(null);
+
+  useEffect(() => {
+    listBackups().then(setBackups).catch(console.error);
+  }, []);
+
+  if (backups.length === 0) {
+    return (
+      <p className="text-xs text-gray-400 py-1">No backups yet.</p>
+    );
+  }
+
+  return (
+    <ul className="space-y-1 py-1">
+      {backups.map((b) => (
+        <li
+          key={b.file_name}
+          className="flex items-center justify-between text-sm"
+        >
+          <span>
+            {new Date(b.created_at).toLocaleString()}{" "}
+            <span className="text-xs text-gray-400">({b.kind})</span>
+          </span>
+          {confirming === b.file_name ? (
+            <button
+              onClick={() => {
+                restoreBackup(b.file_name)
+                  .then(() => window.location.reload())
+                  .catch(console.error);
+              }}
+              className="text-xs text-red-600 dark:text-red-400 font-medium"
+            >
+              Replace current data?
+            </button>
+          ) : (
+            <button
+              onClick={() => setConfirming(b.file_name)}
+              className="text-xs text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+            >
+              Restore
+            </button>
+          )}
+        </li>
+      ))}
+    </ul>
+  );
+}
+
+const BREAK_COLUMNS = [
+  "id",
+  "started_at",
+  "duration_seconds",
+  "completed",
+  "skipped",
+  "preceding_work_seconds",
+  "ended_at",
+  "outcome",
+  "source",
+  "note",
+];
+
+const selectClass =
+  "text-sm bg-gray-100 dark:bg-gray-700 rounded-lg px-2 py-1 border-0 outline-none focus:ring-2 focus:ring-blue-500";
+
+function CsvExport() {
+  const [from, setFrom] = useState("");
+  const [to, setTo] = useState("");
+  const [status, setStatus] = useState<ExportStatusFilter>("all");
+  const [timestampFormat, setTimestampFormat] =
+    useState<TimestampFormat>("iso_local");
+  const [columns, setColumns] = useState<string[]>(BREAK_COLUMNS);
+  const [destination, setDestination] = useState("");
+  const [message, setMessage] = useState<string | null>(null);
+
+  const options = (): CsvExportOptions => ({
+    from: from || undefined,
+    to: to || undefined,
+    destination: destination.trim() || undefined,
+  });
+
+  const run = (promise: Promise<string>) =>
+    promise
+      .then((path) => setMessage(`Saved to ${path}`))
+      .catch((e) => setMessage(String(e)));
+
+  return (
+    <div className="space-y-2 py-1 text-sm">
+      <div className="flex items-center gap-2">
+        <input
+          type="date"
+          value={from}
+          onChange={(e) => setFrom(e.target.value)}
+          className={selectClass}
+        />
+        <span className="text-gray-400">to</span>
+        <input
+          type="date"
+          value={to}
+          onChange={(e) => setTo(e.target.value)}
+          className={selectClass}
+        />
+      </div>
+      <div className="flex items-center gap-2">
+        <select
+          value={status}
+          onChange={(e) => setStatus(e.target.value as ExportStatusFilter)}
+          className={selectClass}
+        >
+          <option value="all">All breaks</option>
+          <option value="completed">Completed only</option>
+          <option value="skipped">Skipped only</option>
+        </select>
+        <select
+          value={timestampFormat}
+          onChange={(e) => setTimestampFormat(e.target.value as TimestampFormat)}
+          className={selectClass}
+        >
+          <option value="iso_local">Local time (ISO 8601)</option>
+          <option value="iso_utc">UTC (ISO 8601)</option>
+          <option value="epoch_ms">Epoch milliseconds</option>
+        </select>
+      </div>
+      <div className="flex flex-wrap gap-x-3 gap-y-1">
+        {BREAK_COLUMNS.map((c) => (
+          <label key={c} className="flex items-center gap-1 text-xs">
+            <input
+              type="checkbox"
+              checked={columns.includes(c)}
+              onChange={(e) =>
+                setColumns(
+                  e.target.checked
+                    ? BREAK_COLUMNS.filter(
+                        (col) => col === c || columns.includes(col)
+                      )
+                    : columns.filter((col) => col !== c)
+                )
+              }
+            />
+            {c}
+          </label>
+        ))}
+      </div>
+      <input
+        type="text"
+        value={destination}
+        placeholder="Save to (defaults to Downloads)"
+        onChange={(e) => setDestination(e.target.value)}
+        className={`${selectClass} w-full`}
+      />
+      <div className="flex gap-3">
+        <button
+          disabled={columns.length === 0}
+          onClick={() =>
+            run(
+              exportDataCsv({
+                ...options(),
+                status,
+                timestamp_format: timestampFormat,
+                columns,
+              })
+            )
+          }
+          className="text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300 disabled:opacity-50"
+        >
+          Export breaks
+        </button>
+        <button
+          onClick={() => run(exportDailyStatsCsv(options()))}
+          className="text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+        >
+          Export daily totals
+        </button>
+      </div>
+      {message && (
+        <p className="text-xs text-gray-500 dark:text-gray-400">{message}</p>
+      )}
+    </div>
+  );
+}
+
+function ReportButtons({
+  format,
+  destination,
+}: {
+  format: ReportFormat;
+  destination: string;
+}) {
+  const [message, setMessage] = useState<string | null>(null);
+
+  const run = (period: ReportPeriod) =>
+    generateReport(period, format, destination || undefined)
+      .then((path) => setMessage(`Saved to ${path}`))
+      .catch((e) => setMessage(String(e)));
+
+  return (
+    <div className="py-1 space-y-1">
+      <div className="flex gap-3 text-sm">
+        <button
+          onClick={() => run("week")}
+          className="text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+        >
+          Last week's report
+        </button>
+        <button
+          onClick={() => run("month")}
+          className="text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+        >
+          Last month's report
+        </button>
+      </div>
+      {message && (
+        <p className="text-xs text-gray-500 dark:text-gray-400">{message}</p>
+      )}
+    </div>
+  );
+}
+
+function CompactButton() {
+  const [message, setMessage] = useState<string | null>(null);
+
+  return (
+    <div className="py-1">
+      <button
+        onClick={() => {
+          compactNow()
+            .then((r) =>
+              setMessage(
+                r.records_pruned > 0
+                  ? `Pruned ${r.records_pruned} breaks from ${r.days_rolled_up} days and compacted`
+                  : "Database compacted"
+              )
+            )
+            .catch((e) => setMessage(String(e)));
+        }}
+        className="text-sm text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+      >
+        Compact now
+      </button>
+      {message && (
+        <p className="text-xs text-gray-500 dark:text-gray-400">{message}</p>
+      )}
+    </div>
+  );
+}
+
+function RebuildButton() {
+  const [message, setMessage] = useState<string | null>(null);
+
+  return (
+    <div className="py-1">
+      <button
+        onClick={() => {
+          rebuildAggregates()
+            .then(() => setMessage("Streaks and lifetime totals rebuilt"))
+            .catch((e) => setMessage(String(e)));
+        }}
+        className="text-sm text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+      >
+        Rebuild statistics
+      </button>
+      {message && (
+        <p className="text-xs text-gray-500 dark:text-gray-400">{message}</p>
+      )}
+    </div>
+  );
+}
+
+function ArchiveControls() {
+  const [mode, setMode] = useState<ImportMode>("merge");
+  const [message, setMessage] = useState<string | null>(null);
+
+  const onImport = (file: File) => {
+    file
+      .text()
+      .then((contents) => importArchive(contents, mode))
+      .then((r) => {
+        if (r.settings_applied) {
+          window.location.reload();
+          return;
+        }
+        setMessage(
+          `Imported ${r.records_imported} breaks (${r.records_skipped} already present)`
+        );
+      })
+      .catch((e) => setMessage(String(e)));
+  };
+
+  return (
+    <div className="space-y-2 py-1">
+      <button
+        onClick={() => {
+          exportArchive()
+            .then((path) => setMessage(`Saved to ${path}`))
+            .catch((e) => setMessage(String(e)));
+        }}
+        className="text-sm text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300"
+      >
+        Export full archive (JSON)
+      </button>
+      <div className="flex items-center gap-2 text-sm">
+        <select
+          value={mode}
+          onChange={(e) => setMode(e.target.value as ImportMode)}
+          className={selectClass}
+        >
+          <option value="merge">Merge with current data</option>
+          <option value="replace">Replace current data</option>
+        </select>
+        <label className="text-blue-600 dark:text-blue-400 hover:text-blue-800 dark:hover:text-blue-300 cursor-pointer">
+          Import archive…
+          <input
+            type="file"
+            accept="application/json,.json"
+            className="hidden"
+            onChange={(e) => {
+              const file = e.target.files?.[0];
+              if (file) onImport(file);
+              e.target.value = "";
+            }}
+          />
+        </label>
+      </div>
+      {message && (
+        <p className="text-xs text-gray-500 dark:text-gray-400">{message}</p>
+      )}
+    </div>
+  );
+}
+
 export default function Settings({ onResetOnboarding }: { onResetOnboarding: () => Promise<void> }) {
   const { settings, saving, error, save } = useSettings();
   const [confirmClear, setConfirmClear] = useState(false);
-  const [exportPath, setExportPath] = useState<string | null>(null);
 
   if (!settings) {
     return (
@@ -149,6 +663,14 @@ export default function Settings({ onResetOnboarding }: { onResetOnboarding: ()
             />
           </div>
         </div>
+        <WorkdayPicker
+          value={settings.workdays}
+          onChange={(workdays) => update({ workdays })}
+        />
+        <SkipReasonEditor
+          value={settings.skip_reasons}
+          onChange={(skip_reasons) => update({ skip_reasons })}
+        />
       </Section>
 
       <Section title="Notifications">
@@ -157,6 +679,13 @@ export default function Settings({ onResetOnboarding }: { onResetOnboarding: ()
           checked={settings.notification_enabled}
           onChange={(v) => update({ notification_enabled: v })}
         />
+        {settings.notification_enabled && (
+          <Toggle
+            label="Daily summary"
+            checked={settings.daily_summary_enabled}
+            onChange={(v) => update({ daily_summary_enabled: v })}
+          />
+        )}
         <Toggle
           label="Overlay reminder"
           checked={settings.overlay_enabled}
@@ -180,6 +709,25 @@ export default function Settings({ onResetOnboarding }: { onResetOnboarding: ()
         )}
       </Section>
 
+      <Section title="Meetings">
+        <Toggle
+          label="Hold breaks while camera or mic is in use"
+          checked={settings.meeting_detection_enabled}
+          onChange={(v) => update({ meeting_detection_enabled: v })}
+        />
+        {settings.meeting_detection_enabled && (
+          <SliderField
+            label="Hold a break for at most"
+            value={settings.meeting_max_defer_minutes}
+            min={0}
+            max={240}
+            step={15}
+            unit="min"
+            onChange={(v) => update({ meeting_max_defer_minutes: v })}
+          />
+        )}
+      </Section>
+
       <Section title="System">
         <Toggle
           label="Launch at login"
@@ -195,6 +743,70 @@ export default function Settings({ onResetOnboarding }: { onResetOnboarding: ()
           unit={settings.idle_pause_minutes === 0 ? "(off)" : "min"}
           onChange={(v) => update({ idle_pause_minutes: v })}
         />
+        {settings.idle_pause_minutes > 0 && (
+          <div className="py-2">
+            <div className="flex items-center justify-between">
+              <span className="text-sm">When I come back</span>
+              <select
+                value={settings.idle_resume_policy}
+                onChange={(e) =>
+                  update({
+                    idle_resume_policy: e.target.value as IdleResumePolicy,
+                  })
+                }
+                className="text-sm bg-gray-100 dark:bg-gray-700 rounded-lg px-2 py-1 border-0 outline-none focus:ring-2 focus:ring-blue-500"
+              >
+                <option value="resume">Continue where I left off</option>
+                <option value="restart_if_long">
+                  Restart if I was away a while
+                </option>
+                <option value="restart">Always restart</option>
+              </select>
+            </div>
+          </div>
+        )}
+        {settings.idle_pause_minutes > 0 &&
+          settings.idle_resume_policy === "restart_if_long" && (
+            <SliderField
+              label="Away long enough to restart"
+              value={settings.idle_long_away_minutes}
+              min={1}
+              max={60}
+              step={1}
+              unit="min"
+              onChange={(v) => update({ idle_long_away_minutes: v })}
+            />
+          )}
+        {settings.idle_pause_minutes > 0 && (
+          <Toggle
+            label="Media playback counts as activity"
+            checked={settings.media_activity_enabled}
+            onChange={(v) => update({ media_activity_enabled: v })}
+          />
+        )}
+        {settings.idle_pause_minutes > 0 && settings.media_activity_enabled && (
+          <div className="py-2 space-y-1">
+            <span className="text-sm">Only these players (blank = all)</span>
+            <input
+              type="text"
+              placeholder="vlc, mpv, firefox"
+              defaultValue={(
+                JSON.parse(settings.media_player_allowlist) as string[]
+              ).join(", ")}
+              onBlur={(e) =>
+                update({
+                  media_player_allowlist: JSON.stringify(
+                    e.target.value
+                      .split(",")
+                      .map((p) => p.trim())
+                      .filter((p) => p.length > 0)
+                  ),
+                })
+              }
+              className="w-full text-sm bg-gray-100 dark:bg-gray-700 rounded-lg px-2 py-1 border-0 outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </div>
+        )}
         <div className="py-2">
           <div className="flex items-center justify-between">
             <span className="text-sm">Theme</span>
@@ -211,7 +823,101 @@ export default function Settings({ onResetOnboarding }: { onResetOnboarding: ()
         </div>
       </Section>
 
+      <Section title="Backups">
+        <SliderField
+          label="Daily backups to keep"
+          value={settings.backup_daily_keep}
+          min={0}
+          max={30}
+          step={1}
+          unit={settings.backup_daily_keep === 0 ? "(off)" : ""}
+          onChange={(v) => update({ backup_daily_keep: v })}
+        />
+        <SliderField
+          label="Weekly backups to keep"
+          value={settings.backup_weekly_keep}
+          min={0}
+          max={12}
+          step={1}
+          unit={settings.backup_weekly_keep === 0 ? "(off)" : ""}
+          onChange={(v) => update({ backup_weekly_keep: v })}
+        />
+        <BackupList />
+        <ArchiveControls />
+      </Section>
+
+      <Section title="Data Retention">
+        <div className="flex items-center justify-between py-2">
+          <span className="text-sm">Keep detailed history for</span>
+          <div className="flex items-center gap-2">
+            <input
+              type="number"
+              min={0}
+              value={settings.retention_amount}
+              onChange={(e) =>
+                update({ retention_amount: Math.max(0, Number(e.target.value)) })
+              }
+              className={`${selectClass} w-16`}
+            />
+            <select
+              value={settings.retention_unit}
+              onChange={(e) =>
+                update({ retention_unit: e.target.value as RetentionUnit })
+              }
+              className={selectClass}
+            >
+              <option value="days">days</option>
+              <option value="months">months</option>
+            </select>
+          </div>
+        </div>
+        <p className="text-xs text-gray-400">
+          {settings.retention_amount === 0
+            ? "Individual breaks are kept forever."
+            : "Older breaks are rolled up into daily, weekly and monthly totals; streaks and lifetime totals are unaffected."}
+        </p>
+        <CompactButton />
+        <RebuildButton />
+      </Section>
+
+      <Section title="Export">
+        <CsvExport />
+      </Section>
+
+      <Section title="Reports">
+        <div className="flex items-center justify-between py-2">
+          <span className="text-sm">Format</span>
+          <select
+            value={settings.report_format}
+            onChange={(e) =>
+              update({ report_format: e.target.value as ReportFormat })
+            }
+            className={selectClass}
+          >
+            <option value="html">HTML</option>
+            <option value="markdown">Markdown</option>
+          </select>
+        </div>
+        <input
+          type="text"
+          value={settings.report_dir}
+          placeholder="Save to (defaults to Downloads)"
+          onChange={(e) => update({ report_dir: e.target.value })}
+          className={`${selectClass} w-full`}
+        />
+        <Toggle
+          label="Write last week's report every Monday"
+          checked={settings.report_auto_enabled}
+          onChange={(v) => update({ report_auto_enabled: v })}
+        />
+        <ReportButtons
+          format={settings.report_format}
+          destination={settings.report_dir}
+        />
+      </Section>
+
       <Section title="About">
+        <DataLocationInfo />
         <button
           onClick={() => {
             onResetOnboarding().catch(console.error);
@@ -228,22 +934,6 @@ export default function Settings({ onResetOnboarding }: { onResetOnboarding: ()
         </h3>
 
         <div className="flex flex-col gap-2">
-          <button
-            onClick={() => {
-              exportDataCsv()
-                .then((path) => setExportPath(path))
-                .catch(console.error);
-            }}
-            className="px-4 py-2 rounded-lg bg-gray-200 dark:bg-gray-700 text-sm font-medium hover:bg-gray-300 dark:hover:bg-gray-600 transition-colors text-left"
-          >
-            Export data as CSV
-          </button>
-          {exportPath && (
-            <p className="text-xs text-green-600 dark:text-green-400">
-              Saved to {exportPath}
-            </p>
-          )}
-
           {confirmClear ? (
             <div className="flex items-center gap-2">
               <span className="text-sm text-red-500">Are you sure?</span>